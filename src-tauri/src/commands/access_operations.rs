@@ -0,0 +1,422 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use super::file_operations::FileError;
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// Why a path is reachable from the webview.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantSource
+{
+    /// A folder opened through `open_folder_dialog`.
+    Workspace,
+    /// A file picked in the open or save dialog.
+    Dialog,
+    /// The app's own config directory.
+    Config,
+    /// Approved by the user through `request_path_access`.
+    User,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AccessGrant
+{
+    pub path: String,
+    pub source: GrantSource,
+    /// Folders grant everything beneath them; files grant only themselves.
+    pub recursive: bool,
+}
+
+/// The set of paths IPC commands may touch. Grants are stored as
+/// canonical paths, and requested paths are canonicalized before they
+/// are compared, so "..", symlinks and case tricks cannot escape.
+#[derive(Default, Debug)]
+pub struct AccessPolicy
+{
+    grants: Vec<AccessGrant>,
+    /// Backend-owned files that stay out of reach even inside a grant.
+    reserved: Vec<PathBuf>,
+}
+
+/// Symlink chains longer than this are refused, like the kernel's ELOOP.
+const MAX_LINK_HOPS: usize = 40;
+
+/// `None` until `enforce` runs during app setup; unit tests call the
+/// commands directly and never install a policy.
+static POLICY: OnceLock<Mutex<Option<AccessPolicy>>> = OnceLock::new();
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Asks the user, through a native dialog the webview cannot answer, to
+/// allow access to `path`. Returns whether access was granted.
+#[tauri::command]
+pub async fn request_path_access(app: AppHandle, path: String) -> Result<bool, FileError>
+{
+    if check_access(&path).is_ok()
+    {
+        return Ok(true);
+    }
+
+    let target = PathBuf::from(&path);
+    let is_dir = target.is_dir();
+    let message = format!(
+        "Notemac++ wants to access the {} '{}', which is outside the open workspace.\n\nAllow access?",
+        if is_dir { "folder" } else { "file" },
+        path
+    );
+
+    let approved = tauri::async_runtime::spawn_blocking(move || {
+        app.dialog()
+            .message(message)
+            .title("Allow File Access")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom("Allow".to_string(), "Deny".to_string()))
+            .blocking_show()
+    })
+    .await
+    .map_err(|e| FileError::new(&path, "io", e.to_string()))?;
+
+    if !approved
+    {
+        eprintln!("Access denied: user declined access to '{}'", path);
+        return Ok(false);
+    }
+
+    grant(&target, GrantSource::User, is_dir);
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn revoke_path_access(path: String) -> Result<(), String>
+{
+    let canonical = canonical_location(Path::new(&path), true)
+        .ok_or_else(|| format!("Cannot resolve '{}'", path))?;
+
+    with_policy(|policy| policy.revoke(&canonical));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_path_grants() -> Result<Vec<AccessGrant>, String>
+{
+    Ok(with_policy(|policy| policy.grants.clone()).unwrap_or_default())
+}
+
+// ─── Policy ─────────────────────────────────────────────────────
+
+impl AccessPolicy
+{
+    pub fn add(&mut self, path: &Path, source: GrantSource, recursive: bool)
+    {
+        let Some(canonical) = canonical_location(path, true)
+        else
+        {
+            return;
+        };
+
+        let path = canonical.to_string_lossy().into_owned();
+        if let Some(existing) = self.grants.iter_mut().find(|g| g.path == path)
+        {
+            existing.recursive |= recursive;
+            return;
+        }
+        self.grants.push(AccessGrant { path, source, recursive });
+    }
+
+    pub fn revoke(&mut self, canonical: &Path)
+    {
+        self.grants.retain(|g| Path::new(&g.path) != canonical);
+    }
+
+    pub fn reserve(&mut self, path: &Path)
+    {
+        if let Some(canonical) = canonical_location(path, true)
+        {
+            self.reserved.push(canonical);
+        }
+    }
+
+    /// Whether `path` lies inside a grant. With `follow_links` a final
+    /// symlink is judged by its target (reads, writes); without, by where
+    /// the link itself lives (rename, delete).
+    pub fn allows(&self, path: &Path, follow_links: bool) -> bool
+    {
+        let Some(canonical) = canonical_location(path, follow_links)
+        else
+        {
+            return false;
+        };
+        if self.reserved.iter().any(|reserved| canonical.starts_with(reserved))
+        {
+            return false;
+        }
+
+        self.grants.iter().any(|grant| {
+            let granted = Path::new(&grant.path);
+            canonical == granted || (grant.recursive && canonical.starts_with(granted))
+        })
+    }
+}
+
+/// Installs an enforcing policy. Called once from the app's setup hook,
+/// before any command can run; the config dir is always reachable.
+pub fn enforce(config_dir: Option<PathBuf>)
+{
+    let mut policy = AccessPolicy::default();
+    if let Some(dir) = config_dir
+    {
+        // The directory may not exist yet on first launch
+        let _ = std::fs::create_dir_all(&dir);
+        policy.add(&dir, GrantSource::Config, true);
+    }
+
+    *policy_lock().lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(policy);
+}
+
+pub fn grant(path: &Path, source: GrantSource, recursive: bool)
+{
+    with_policy(|policy| policy.add(path, source, recursive));
+}
+
+/// Keeps a file the backend owns, such as saved settings in the config
+/// dir, out of reach of every IPC command. Call after `enforce`.
+pub fn reserve(path: &Path)
+{
+    with_policy(|policy| policy.reserve(path));
+}
+
+/// Fails with an "access-denied" error, and logs the denial, when `path`
+/// (or what it links to) is outside every grant.
+pub fn check_access(path: &str) -> Result<(), FileError>
+{
+    check(path, true)
+}
+
+/// Like `check_access`, but a symlink is judged by where it lives rather
+/// than where it points. For operations on the entry itself.
+pub fn check_entry_access(path: &str) -> Result<(), FileError>
+{
+    check(path, false)
+}
+
+fn check(path: &str, follow_links: bool) -> Result<(), FileError>
+{
+    match with_policy(|policy| policy.allows(Path::new(path), follow_links))
+    {
+        None | Some(true) => Ok(()),
+        Some(false) => {
+            eprintln!("Access denied: '{}' is outside the workspace and has not been granted", path);
+            Err(FileError::new(
+                path,
+                "access-denied",
+                format!("Access to '{}' is not allowed; open its folder or grant access first", path),
+            ))
+        },
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn policy_lock() -> &'static Mutex<Option<AccessPolicy>>
+{
+    POLICY.get_or_init(|| Mutex::new(None))
+}
+
+/// Runs `f` against the installed policy; `None` when nothing is enforced.
+fn with_policy<T>(f: impl FnOnce(&mut AccessPolicy) -> T) -> Option<T>
+{
+    let mut guard = policy_lock().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    guard.as_mut().map(f)
+}
+
+/// The canonical form of `path`, which need not exist yet: the deepest
+/// existing ancestor is canonicalized and the missing tail appended.
+fn canonical_location(path: &Path, follow_links: bool) -> Option<PathBuf>
+{
+    if !path.is_absolute()
+    {
+        return None;
+    }
+
+    if !follow_links
+    {
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name())
+        {
+            return canonical_location(parent, true).map(|parent| parent.join(name));
+        }
+    }
+
+    resolve_missing_tail(path, MAX_LINK_HOPS)
+}
+
+/// Canonicalizes the deepest existing ancestor of `path` and appends the
+/// rest. A dangling symlink along the way is followed to where it points,
+/// since writing through it would create its target.
+fn resolve_missing_tail(path: &Path, hops_left: usize) -> Option<PathBuf>
+{
+    let mut existing = path;
+    let mut tail = Vec::new();
+    loop
+    {
+        if let Ok(canonical) = existing.canonicalize()
+        {
+            return Some(tail.iter().rev().fold(canonical, |acc, part| acc.join(part)));
+        }
+
+        if let Ok(target) = std::fs::read_link(existing)
+        {
+            let target = existing.parent()?.join(target);
+            let rejoined = tail.iter().rev().fold(target, |acc, part| acc.join(part));
+            return resolve_missing_tail(&rejoined, hops_left.checked_sub(1)?);
+        }
+
+        // A missing tail ending in ".." has no file name, so paths that
+        // canonicalize could not resolve are refused rather than guessed
+        let name = existing.file_name()?;
+        tail.push(name.to_os_string());
+        existing = existing.parent()?;
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn policy_for(root: &Path) -> AccessPolicy
+    {
+        let mut policy = AccessPolicy::default();
+        policy.add(root, GrantSource::Workspace, true);
+        policy
+    }
+
+    // ── Grants ──────────────────────────────────────────────────
+
+    #[test]
+    fn allows_paths_inside_a_workspace_root()
+    {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        let policy = policy_for(&root);
+
+        assert!(policy.allows(&root.join("src/main.rs"), true));
+        assert!(policy.allows(&root.join("src/new_file.rs"), true));
+        assert!(policy.allows(&root, true));
+        assert!(!policy.allows(&dir.path().join("secret.txt"), true));
+    }
+
+    #[test]
+    fn rejects_parent_components_that_escape()
+    {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        fs::write(dir.path().join("secret.txt"), "").unwrap();
+        let policy = policy_for(&root);
+
+        assert!(!policy.allows(&root.join("../secret.txt"), true));
+        assert!(!policy.allows(&root.join("missing/../../secret.txt"), true));
+        assert!(!policy.allows(Path::new("relative/path.txt"), true));
+    }
+
+    #[test]
+    fn file_grants_are_not_recursive()
+    {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("picked.txt");
+        fs::write(&file, "").unwrap();
+
+        let mut policy = AccessPolicy::default();
+        policy.add(&file, GrantSource::Dialog, false);
+
+        assert!(policy.allows(&file, true));
+        assert!(!policy.allows(&dir.path().join("other.txt"), true));
+    }
+
+    #[test]
+    fn reserved_files_stay_out_of_reach_inside_a_grant()
+    {
+        let dir = TempDir::new().unwrap();
+        let settings = dir.path().join("settings.json");
+        let mut policy = policy_for(dir.path());
+        policy.reserve(&settings);
+
+        assert!(!policy.allows(&settings, true));
+        assert!(!policy.allows(&settings, false));
+        assert!(policy.allows(&dir.path().join("other.json"), true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_links_are_judged_by_their_target()
+    {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        let outside = dir.path().join("autostart/evil.desktop");
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let reserved = root.join("settings.json");
+        std::os::unix::fs::symlink("settings.json", root.join("alias.json")).unwrap();
+
+        let mut policy = policy_for(&root);
+        policy.reserve(&reserved);
+
+        assert!(!policy.allows(&root.join("link"), true));
+        assert!(policy.allows(&root.join("link"), false));
+        assert!(!policy.allows(&root.join("alias.json"), true));
+    }
+
+    #[test]
+    fn revoke_removes_a_grant()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut policy = policy_for(dir.path());
+
+        policy.revoke(&dir.path().canonicalize().unwrap());
+        assert!(!policy.allows(&dir.path().join("file.txt"), true));
+    }
+
+    // ── Symlinks ────────────────────────────────────────────────
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_out_of_the_workspace_is_judged_by_its_target()
+    {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        fs::write(dir.path().join("secret.txt"), "").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), root.join("link.txt")).unwrap();
+        let policy = policy_for(&root);
+
+        assert!(!policy.allows(&root.join("link.txt"), true));
+        assert!(policy.allows(&root.join("link.txt"), false));
+    }
+
+    // ── Serialization ───────────────────────────────────────────
+
+    #[test]
+    fn access_grant_serializes_source_in_lowercase()
+    {
+        let grant = AccessGrant {
+            path: "/tmp/project".into(),
+            source: GrantSource::Workspace,
+            recursive: true,
+        };
+
+        let json = serde_json::to_value(&grant).unwrap();
+        assert_eq!(json["source"], "workspace");
+        assert_eq!(json["recursive"], true);
+    }
+}
@@ -0,0 +1,652 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::access_operations::{check_access, reserve};
+use super::save_operations::{atomic_write, content_hash, resolve_symlink_target};
+
+/// Folder created next to the edited file when no backup directory is set,
+/// the same default Notepad++ uses.
+const DEFAULT_BACKUP_DIR_NAME: &str = "nppBackup";
+const BACKUP_EXTENSION: &str = "bak";
+const SETTINGS_FILE_NAME: &str = "backup-settings.json";
+
+/// Backup settings pushed from the frontend's preferences.
+static BACKUP_SETTINGS: OnceLock<Mutex<BackupSettings>> = OnceLock::new();
+/// Where the settings are saved; unset in tests, which keep them in memory.
+static SETTINGS_STORE: OnceLock<PathBuf> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode
+{
+    /// No backups (the default).
+    #[default]
+    None,
+    /// A single `name.bak`, overwritten on every save.
+    Simple,
+    /// Timestamped `name.YYYY-MM-DD_HHMMSS.bak` copies.
+    Verbose,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BackupSettings
+{
+    pub mode: BackupMode,
+    /// Custom backup directory; `None` means `nppBackup` beside each file.
+    pub directory: Option<String>,
+    /// Maximum verbose backups kept per file.
+    #[serde(rename = "maxCount")]
+    pub max_count: Option<usize>,
+    /// Verbose backups older than this are deleted.
+    #[serde(rename = "maxAgeDays")]
+    pub max_age_days: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BackupEntry
+{
+    pub path: String,
+    /// "simple" or "verbose"
+    pub kind: String,
+    pub size: u64,
+    /// When the backup was made, in milliseconds since the Unix epoch:
+    /// the stamp in a verbose backup's name, a simple backup's mtime.
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// A custom backup directory must be inside the allowed roots, like any
+/// other path the backend writes to. The settings are kept across restarts.
+#[tauri::command]
+pub async fn set_backup_settings(settings: BackupSettings) -> Result<(), String>
+{
+    if let Some(directory) = &settings.directory
+    {
+        check_access(directory).map_err(|e| e.message)?;
+    }
+    *settings_lock().lock().map_err(|_| "Backup settings lock poisoned".to_string())? = settings.clone();
+
+    match SETTINGS_STORE.get()
+    {
+        Some(store) => save_settings(store, &settings)
+            .map_err(|e| format!("Failed to save backup settings '{}': {}", store.display(), e)),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_backup_settings() -> Result<BackupSettings, String>
+{
+    Ok(current_settings())
+}
+
+/// Lists the backups of `path`, newest first.
+#[tauri::command]
+pub async fn list_backups(path: String) -> Result<Vec<BackupEntry>, String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    list_backups_for(Path::new(&path), &current_settings())
+        .map_err(|e| format!("Failed to list backups for '{}': {}", path, e))
+}
+
+/// Restores `backup_path` over `path`. When backups are enabled the current
+/// on-disk version is backed up first, so the restore itself can be undone.
+#[tauri::command]
+pub async fn restore_backup(path: String, backup_path: String) -> Result<(), String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    let settings = current_settings();
+    let target = Path::new(&path);
+
+    let known = list_backups_for(target, &settings)
+        .map_err(|e| format!("Failed to list backups for '{}': {}", path, e))?;
+    if !known.iter().any(|entry| entry.path == backup_path)
+    {
+        return Err(format!("'{}' is not a backup of '{}'", backup_path, path));
+    }
+
+    let bytes = fs::read(&backup_path)
+        .map_err(|e| format!("Failed to read backup '{}': {}", backup_path, e))?;
+
+    // The backup bytes are already in memory, so even a "simple" backup
+    // overwriting them here is safe: restoring again undoes the restore.
+    create_backup(target, &settings)
+        .map_err(|e| format!("Failed to back up '{}': {}", path, e))?;
+
+    atomic_write(target, &bytes)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to restore '{}': {}", path, e))
+}
+
+// ─── Settings ───────────────────────────────────────────────────
+
+/// Loads the saved settings at startup. The file is reserved, so the
+/// webview cannot rewrite it through the config dir grant and point
+/// backups somewhere `set_backup_settings` would refuse.
+pub fn load_settings(config_dir: Option<PathBuf>)
+{
+    let Some(store) = config_dir.map(|dir| dir.join(SETTINGS_FILE_NAME))
+    else
+    {
+        return;
+    };
+    reserve(&store);
+
+    match fs::read(&store)
+    {
+        Ok(bytes) => match serde_json::from_slice::<BackupSettings>(&bytes)
+        {
+            Ok(settings) => {
+                if let Ok(mut current) = settings_lock().lock()
+                {
+                    *current = settings;
+                }
+            },
+            Err(e) => eprintln!("Ignoring unreadable backup settings '{}': {}", store.display(), e),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+        Err(e) => eprintln!("Failed to read backup settings '{}': {}", store.display(), e),
+    }
+    let _ = SETTINGS_STORE.set(store);
+}
+
+fn save_settings(store: &Path, settings: &BackupSettings) -> io::Result<()>
+{
+    let json = serde_json::to_vec_pretty(settings).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(dir) = store.parent()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(store, &json).map(|_| ())
+}
+
+// ─── Backup Logic ───────────────────────────────────────────────
+
+/// Called by the save path before the file is replaced.
+pub fn backup_before_save(path: &Path) -> io::Result<Option<PathBuf>>
+{
+    create_backup(path, &current_settings())
+}
+
+/// Copies the current on-disk version of `path` into the backup directory
+/// and applies retention. Returns `None` when backups are disabled or the
+/// file does not exist yet.
+pub fn create_backup(path: &Path, settings: &BackupSettings) -> io::Result<Option<PathBuf>>
+{
+    if settings.mode == BackupMode::None
+    {
+        return Ok(None);
+    }
+
+    let source = resolve_symlink_target(path)?;
+    if !source.is_file()
+    {
+        return Ok(None);
+    }
+
+    let dir = backup_dir_for(path, settings)?;
+    fs::create_dir_all(&dir)?;
+    let name = file_name_of(path)?;
+
+    let backup_path = match settings.mode
+    {
+        BackupMode::Simple => dir.join(format!("{}.{}", name, BACKUP_EXTENSION)),
+        _ => unique_verbose_path(&dir, &name, SystemTime::now()),
+    };
+
+    fs::copy(&source, &backup_path)?;
+
+    if settings.mode == BackupMode::Verbose
+    {
+        apply_retention(path, settings, SystemTime::now())?;
+    }
+
+    Ok(Some(backup_path))
+}
+
+/// Lists simple and verbose backups of `path`, newest first.
+pub fn list_backups_for(path: &Path, settings: &BackupSettings) -> io::Result<Vec<BackupEntry>>
+{
+    let dir = backup_dir_for(path, settings)?;
+    let name = file_name_of(path)?;
+
+    let read_dir = match fs::read_dir(&dir)
+    {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = Vec::new();
+    for entry in read_dir
+    {
+        let entry = entry?;
+        let entry_name = entry.file_name().to_string_lossy().into_owned();
+        let kind = match classify_backup(&entry_name, &name)
+        {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        // fs::copy keeps the source's mtime on some platforms, so a verbose
+        // backup's age comes from its name
+        let metadata = entry.metadata()?;
+        let made_at = stamp_millis(&entry_name, &name)
+            .unwrap_or_else(|| metadata.modified().map(to_epoch_millis).unwrap_or(0));
+        entries.push(BackupEntry {
+            path: entry.path().to_string_lossy().into_owned(),
+            kind: kind.to_string(),
+            size: metadata.len(),
+            created_at: made_at,
+        });
+    }
+
+    // Backups made within the same second are ordered by collision suffix
+    entries.sort_by(|a, b| {
+        b.created_at.cmp(&a.created_at)
+            .then_with(|| collision_index(&b.path).cmp(&collision_index(&a.path)))
+    });
+    Ok(entries)
+}
+
+/// Deletes verbose backups beyond `max_count` or older than `max_age_days`.
+/// Backups that cannot be deleted are logged and skipped.
+pub fn apply_retention(path: &Path, settings: &BackupSettings, now: SystemTime) -> io::Result<()>
+{
+    let verbose: Vec<BackupEntry> = list_backups_for(path, settings)?
+        .into_iter()
+        .filter(|entry| entry.kind == "verbose")
+        .collect();
+
+    let cutoff = settings.max_age_days
+        .map(|days| to_epoch_millis(now.checked_sub(Duration::from_secs(days * 86_400)).unwrap_or(UNIX_EPOCH)));
+
+    for (index, entry) in verbose.iter().enumerate()
+    {
+        let over_count = settings.max_count.is_some_and(|max| index >= max);
+        let too_old = cutoff.is_some_and(|cutoff| entry.created_at < cutoff);
+        if !over_count && !too_old
+        {
+            continue;
+        }
+        // One stuck file must not keep the rest from being pruned
+        if let Err(e) = fs::remove_file(&entry.path)
+        {
+            if e.kind() != io::ErrorKind::NotFound
+            {
+                eprintln!("Failed to remove old backup '{}': {}", entry.path, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves where backups of `path` live. Custom directories are shared by
+/// many folders, so each source folder gets its own hashed subdirectory.
+pub fn backup_dir_for(path: &Path, settings: &BackupSettings) -> io::Result<PathBuf>
+{
+    let parent = path.parent()
+        .ok_or_else(|| io::Error::other("Cannot determine parent directory"))?;
+
+    Ok(match &settings.directory
+    {
+        Some(dir) => PathBuf::from(dir).join(format!("{:016x}", content_hash(parent.to_string_lossy().as_bytes()))),
+        None => parent.join(DEFAULT_BACKUP_DIR_NAME),
+    })
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn settings_lock() -> &'static Mutex<BackupSettings>
+{
+    BACKUP_SETTINGS.get_or_init(|| Mutex::new(BackupSettings::default()))
+}
+
+fn current_settings() -> BackupSettings
+{
+    settings_lock().lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+fn file_name_of(path: &Path) -> io::Result<String>
+{
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::other("Path has no file name"))
+}
+
+/// Returns "simple" or "verbose" if `entry_name` is a backup of `file_name`.
+fn classify_backup(entry_name: &str, file_name: &str) -> Option<&'static str>
+{
+    let stamp = entry_name.strip_prefix(file_name)?
+        .strip_prefix('.')?
+        .strip_suffix(BACKUP_EXTENSION)?;
+
+    if stamp.is_empty()
+    {
+        return Some("simple");
+    }
+
+    // "YYYY-MM-DD_HHMMSS" optionally followed by a "-N" collision suffix
+    let (base, suffix) = stamp.strip_suffix('.')?.split_at_checked(17)?;
+    let valid_base = base.bytes().enumerate().all(|(i, c)| match i
+    {
+        4 | 7 => c == b'-',
+        10 => c == b'_',
+        _ => c.is_ascii_digit(),
+    });
+    let valid_suffix = suffix.is_empty()
+        || suffix.strip_prefix('-').is_some_and(|n| !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit()));
+    let valid = valid_base && valid_suffix;
+
+    if valid { Some("verbose") } else { None }
+}
+
+/// The "N" of a `name.STAMP-N.bak` backup, 0 when there is no suffix.
+fn collision_index(backup_path: &str) -> u32
+{
+    backup_path.strip_suffix(&format!(".{}", BACKUP_EXTENSION))
+        .and_then(|rest| rest.rsplit_once('-'))
+        .and_then(|(_, n)| n.parse().ok())
+        .unwrap_or(0)
+}
+
+fn unique_verbose_path(dir: &Path, name: &str, now: SystemTime) -> PathBuf
+{
+    let stamp = format_timestamp(now);
+    let mut candidate = dir.join(format!("{}.{}.{}", name, stamp, BACKUP_EXTENSION));
+    let mut counter = 1;
+    while candidate.exists()
+    {
+        candidate = dir.join(format!("{}.{}-{}.{}", name, stamp, counter, BACKUP_EXTENSION));
+        counter += 1;
+    }
+    candidate
+}
+
+/// Reads back the UTC `YYYY-MM-DD_HHMMSS` stamp of a verbose backup of
+/// `file_name`, as milliseconds since the Unix epoch.
+fn stamp_millis(entry_name: &str, file_name: &str) -> Option<u64>
+{
+    let stamp = entry_name.strip_prefix(file_name)?.strip_prefix('.')?.get(..17)?;
+    let number = |range: std::ops::Range<usize>| stamp.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(13..15)?, number(15..17)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day)
+    {
+        return None;
+    }
+
+    // Days-from-civil (Howard Hinnant's algorithm), the inverse of format_timestamp
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok().map(|secs| secs * 1000)
+}
+
+/// Formats a UTC timestamp as `YYYY-MM-DD_HHMMSS`.
+fn format_timestamp(time: SystemTime) -> String
+{
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, day_secs) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+        year, month, day,
+        day_secs / 3600, (day_secs % 3600) / 60, day_secs % 60
+    )
+}
+
+fn to_epoch_millis(time: SystemTime) -> u64
+{
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn settings(mode: BackupMode) -> BackupSettings
+    {
+        BackupSettings { mode, ..Default::default() }
+    }
+
+    // ── Naming ───────────────────────────────────────────────────
+
+    #[test]
+    fn formats_timestamps_in_utc()
+    {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(format_timestamp(time), "2023-11-14_221320");
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01_000000");
+    }
+
+    #[test]
+    fn reads_timestamps_back_from_names()
+    {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let name = format!("a.txt.{}-3.bak", format_timestamp(time));
+        assert_eq!(stamp_millis(&name, "a.txt"), Some(1_700_000_000_000));
+        assert_eq!(stamp_millis("a.txt.2024-02-29_000000.bak", "a.txt"), Some(1_709_164_800_000));
+        assert_eq!(stamp_millis("a.txt.bak", "a.txt"), None);
+    }
+
+    #[test]
+    fn classifies_backup_names()
+    {
+        assert_eq!(classify_backup("notes.txt.bak", "notes.txt"), Some("simple"));
+        assert_eq!(classify_backup("notes.txt.2026-01-02_030405.bak", "notes.txt"), Some("verbose"));
+        assert_eq!(classify_backup("notes.txt.2026-01-02_030405-2.bak", "notes.txt"), Some("verbose"));
+        assert_eq!(classify_backup("notes.txt.old.bak", "notes.txt"), None);
+        assert_eq!(classify_backup("notes.txt.2026-01-02_030405-x.bak", "notes.txt"), None);
+        assert_eq!(classify_backup("notes.txt2.bak", "notes.txt"), None);
+        assert_eq!(classify_backup("other.txt.bak", "notes.txt"), None);
+    }
+
+    #[test]
+    fn custom_directory_separates_source_folders()
+    {
+        let settings = BackupSettings { directory: Some("/backups".into()), ..settings(BackupMode::Simple) };
+        let a = backup_dir_for(Path::new("/a/file.txt"), &settings).unwrap();
+        let b = backup_dir_for(Path::new("/b/file.txt"), &settings).unwrap();
+
+        assert_ne!(a, b);
+        // FNV-1a of "/a": folder names must not change between releases
+        assert_eq!(a, Path::new("/backups/07d66707b49cd92d"));
+        assert_eq!(a, backup_dir_for(Path::new("/a/other.txt"), &settings).unwrap());
+    }
+
+    // ── create_backup ────────────────────────────────────────────
+
+    #[test]
+    fn no_backup_when_disabled_or_missing()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+
+        assert!(create_backup(&path, &settings(BackupMode::Simple)).unwrap().is_none());
+        fs::write(&path, "x").unwrap();
+        assert!(create_backup(&path, &settings(BackupMode::None)).unwrap().is_none());
+        assert!(!dir.path().join(DEFAULT_BACKUP_DIR_NAME).exists());
+    }
+
+    #[test]
+    fn simple_backup_overwrites_single_copy()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        let settings = settings(BackupMode::Simple);
+
+        fs::write(&path, "v1").unwrap();
+        let first = create_backup(&path, &settings).unwrap().unwrap();
+        fs::write(&path, "v2").unwrap();
+        let second = create_backup(&path, &settings).unwrap().unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first, dir.path().join("nppBackup").join("file.txt.bak"));
+        assert_eq!(fs::read_to_string(&second).unwrap(), "v2");
+    }
+
+    #[test]
+    fn verbose_backups_accumulate_and_respect_max_count()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        let settings = BackupSettings { max_count: Some(2), ..settings(BackupMode::Verbose) };
+
+        for version in ["v1", "v2", "v3"]
+        {
+            fs::write(&path, version).unwrap();
+            create_backup(&path, &settings).unwrap();
+        }
+
+        let backups = list_backups_for(&path, &settings).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(backups.iter().all(|b| b.kind == "verbose"));
+        let contents: Vec<String> = backups.iter().map(|b| fs::read_to_string(&b.path).unwrap()).collect();
+        assert!(contents.contains(&"v3".to_string()));
+        assert!(!contents.contains(&"v1".to_string()));
+    }
+
+    #[test]
+    fn retention_removes_old_backups()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "x").unwrap();
+        let settings = BackupSettings { max_age_days: Some(7), ..settings(BackupMode::Verbose) };
+        create_backup(&path, &settings).unwrap();
+
+        let in_thirty_days = SystemTime::now() + Duration::from_secs(30 * 86_400);
+        apply_retention(&path, &settings, in_thirty_days).unwrap();
+
+        assert!(list_backups_for(&path, &settings).unwrap().is_empty());
+    }
+
+    #[test]
+    fn retention_ages_backups_by_their_name_not_their_mtime()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "x").unwrap();
+        let settings = BackupSettings { max_age_days: Some(7), ..settings(BackupMode::Verbose) };
+        let backup = create_backup(&path, &settings).unwrap().unwrap();
+
+        // As if the copy kept the mtime of a file untouched for months
+        let months_ago = SystemTime::now() - Duration::from_secs(90 * 86_400);
+        fs::File::options().write(true).open(&backup).unwrap().set_modified(months_ago).unwrap();
+        apply_retention(&path, &settings, SystemTime::now()).unwrap();
+
+        assert!(backup.exists());
+    }
+
+    #[test]
+    fn list_backups_ignores_unrelated_files()
+    {
+        let dir = TempDir::new().unwrap();
+        let backup_dir = dir.path().join(DEFAULT_BACKUP_DIR_NAME);
+        fs::create_dir(&backup_dir).unwrap();
+        fs::write(backup_dir.join("file.txt.bak"), "a").unwrap();
+        fs::write(backup_dir.join("other.txt.bak"), "b").unwrap();
+        fs::write(backup_dir.join("file.txt.swp"), "c").unwrap();
+
+        let backups = list_backups_for(&dir.path().join("file.txt"), &settings(BackupMode::Simple)).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].kind, "simple");
+    }
+
+    // ── restore_backup ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn restore_backup_replaces_file_contents()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        let backup_dir = dir.path().join(DEFAULT_BACKUP_DIR_NAME);
+        fs::create_dir(&backup_dir).unwrap();
+        fs::write(&path, "current").unwrap();
+        fs::write(backup_dir.join("file.txt.bak"), "previous").unwrap();
+
+        restore_backup(
+            path.to_string_lossy().into_owned(),
+            backup_dir.join("file.txt.bak").to_string_lossy().into_owned(),
+        ).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous");
+    }
+
+    #[tokio::test]
+    async fn restore_backup_rejects_foreign_files()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.txt");
+        let stranger = dir.path().join("secret.txt");
+        fs::write(&path, "current").unwrap();
+        fs::write(&stranger, "secret").unwrap();
+
+        let result = restore_backup(
+            path.to_string_lossy().into_owned(),
+            stranger.to_string_lossy().into_owned(),
+        ).await;
+
+        assert!(result.unwrap_err().contains("is not a backup"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "current");
+    }
+
+    // ── Serialization ────────────────────────────────────────────
+
+    #[test]
+    fn settings_deserialize_from_camel_case()
+    {
+        let settings: BackupSettings = serde_json::from_value(serde_json::json!({
+            "mode": "verbose",
+            "directory": "/tmp/backups",
+            "maxCount": 5,
+            "maxAgeDays": 30
+        })).unwrap();
+
+        assert_eq!(settings.mode, BackupMode::Verbose);
+        assert_eq!(settings.max_count, Some(5));
+        assert_eq!(settings.max_age_days, Some(30));
+    }
+
+    #[test]
+    fn settings_are_saved_as_json()
+    {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join("config").join(SETTINGS_FILE_NAME);
+        let settings = BackupSettings { max_count: Some(3), ..settings(BackupMode::Verbose) };
+
+        save_settings(&store, &settings).unwrap();
+        let saved: BackupSettings = serde_json::from_slice(&fs::read(&store).unwrap()).unwrap();
+        assert_eq!(saved, settings);
+    }
+}
@@ -0,0 +1,486 @@
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::access_operations::check_access;
+use super::backup_operations::backup_before_save;
+use super::encoding_operations::{TextEncoding, detect_encoding, sniff_bom};
+use super::file_operations::FileError;
+use super::merge_operations::refresh_version;
+use super::save_operations::atomic_write_with;
+use super::watcher_operations::record_own_write;
+
+/// Cap on bytes returned by one `read_bytes` call.
+const MAX_READ_LENGTH: u64 = 16 * 1024 * 1024;
+/// How much of a file is inspected when sniffing for binary content.
+const SNIFF_LENGTH: u64 = 8 * 1024;
+
+/// Well-known signatures, checked before any heuristics.
+const MAGIC_NUMBERS: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "png"),
+    (b"\xFF\xD8\xFF", "jpeg"),
+    (b"GIF87a", "gif"),
+    (b"GIF89a", "gif"),
+    (b"%PDF-", "pdf"),
+    (b"PK\x03\x04", "zip"),
+    (b"\x1F\x8B", "gzip"),
+    (b"\x7FELF", "elf"),
+    (b"\xFE\xED\xFA\xCE", "mach-o"),
+    (b"\xFE\xED\xFA\xCF", "mach-o"),
+    (b"\xCE\xFA\xED\xFE", "mach-o"),
+    (b"\xCF\xFA\xED\xFE", "mach-o"),
+    (b"\0asm", "wasm"),
+    (b"SQLite format 3\0", "sqlite"),
+];
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ByteChunk
+{
+    pub offset: u64,
+    /// Base64 on the wire; a JSON number array would be several times larger.
+    #[serde(serialize_with = "as_base64")]
+    pub bytes: Vec<u8>,
+    #[serde(rename = "fileSize")]
+    pub file_size: u64,
+}
+
+/// One patch operation. Offsets refer to the file as it is on disk
+/// before the patch, so edits can be listed in any order. Bytes travel as
+/// base64, like `ByteChunk`'s.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ByteEdit
+{
+    Overwrite
+    {
+        offset: u64,
+        #[serde(deserialize_with = "from_base64")]
+        bytes: Vec<u8>,
+    },
+    Insert
+    {
+        offset: u64,
+        #[serde(deserialize_with = "from_base64")]
+        bytes: Vec<u8>,
+    },
+    Delete { offset: u64, length: u64 },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileKind
+{
+    #[serde(rename = "isBinary")]
+    pub is_binary: bool,
+    /// Recognized container format, e.g. "png" or "elf".
+    pub format: Option<String>,
+    /// Detected text encoding when the file looks like text.
+    pub encoding: Option<String>,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Reads up to `len` raw bytes starting at `offset`.
+#[tauri::command]
+pub async fn read_bytes(path: String, offset: u64, len: u64) -> Result<ByteChunk, FileError>
+{
+    check_access(&path)?;
+    let mut file = File::open(&path)
+        .map_err(|e| FileError::from_io(&path, "open", &e))?;
+    let file_size = file.metadata()
+        .map_err(|e| FileError::from_io(&path, "read", &e))?
+        .len();
+
+    let start = offset.min(file_size);
+    let length = len.min(MAX_READ_LENGTH).min(file_size - start);
+    let mut bytes = vec![0u8; length as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut bytes))
+        .map_err(|e| FileError::from_io(&path, "read", &e))?;
+
+    Ok(ByteChunk { offset: start, bytes, file_size })
+}
+
+/// Applies a set of byte edits and returns the new file size. With
+/// `inPlace`, overwrite-only patches skip the copy and write straight
+/// into the file, which is not crash-safe.
+#[tauri::command]
+pub async fn write_bytes_patch(path: String, edits: Vec<ByteEdit>, in_place: Option<bool>) -> Result<u64, FileError>
+{
+    check_access(&path)?;
+    apply_patch(Path::new(&path), edits, in_place.unwrap_or(false))
+}
+
+/// Reports whether a file should open in the hex editor or as text.
+#[tauri::command]
+pub async fn sniff_file_kind(path: String) -> Result<FileKind, FileError>
+{
+    check_access(&path)?;
+    let mut sample = Vec::new();
+    File::open(&path)
+        .and_then(|file| file.take(SNIFF_LENGTH).read_to_end(&mut sample))
+        .map_err(|e| FileError::from_io(&path, "read", &e))?;
+
+    Ok(classify_bytes(&sample))
+}
+
+// ─── Patching ───────────────────────────────────────────────────
+
+/// Validates every edit before touching the file, then streams the
+/// patched contents through `atomic_write_with`. Only with `in_place` are
+/// overwrite-only patches written through the existing inode, so
+/// multi-gigabyte images are not copied.
+pub fn apply_patch(path: &Path, mut edits: Vec<ByteEdit>, in_place: bool) -> Result<u64, FileError>
+{
+    let display = path.to_string_lossy();
+    let size = fs::metadata(path)
+        .map_err(|e| FileError::from_io(&display, "read", &e))?
+        .len();
+
+    edits.sort_by_key(|edit| edit_range(edit).0);
+    validate_edits(&display, &edits, size)?;
+
+    if edits.is_empty()
+    {
+        return Ok(size);
+    }
+
+    backup_before_save(path).map_err(|e| {
+        FileError::new(&display, "backup-failed", format!("Failed to back up '{}' before patching: {}", display, e))
+    })?;
+
+    if in_place && edits.iter().all(|edit| matches!(edit, ByteEdit::Overwrite { .. }))
+    {
+        overwrite_in_place(path, &edits).map_err(|e| FileError::from_io(&display, "write", &e))?;
+    }
+    else
+    {
+        let mut original = File::open(path).map_err(|e| FileError::from_io(&display, "read", &e))?;
+        atomic_write_with(path, &mut |out| write_spliced(&mut original, &edits, out))
+            .map_err(|e| FileError::from_io(&display, "write", &e))?;
+    }
+
+    record_own_write(path);
+    refresh_version(path);
+    Ok(patched_size(size, &edits))
+}
+
+/// The `[start, end)` range of the original file an edit consumes.
+fn edit_range(edit: &ByteEdit) -> (u64, u64)
+{
+    match edit
+    {
+        ByteEdit::Overwrite { offset, bytes } => (*offset, offset.saturating_add(bytes.len() as u64)),
+        ByteEdit::Insert { offset, .. } => (*offset, *offset),
+        ByteEdit::Delete { offset, length } => (*offset, offset.saturating_add(*length)),
+    }
+}
+
+/// Edits must be sorted by start offset.
+fn validate_edits(path: &str, edits: &[ByteEdit], size: u64) -> Result<(), FileError>
+{
+    let mut previous_end = 0;
+    for edit in edits
+    {
+        let (start, end) = edit_range(edit);
+        if end > size
+        {
+            return Err(FileError::new(path, "out-of-range", format!(
+                "Edit at offset {} extends past the end of the file ({} bytes)", start, size
+            )));
+        }
+        if start < previous_end
+        {
+            return Err(FileError::new(path, "overlapping-edits", format!(
+                "Edit at offset {} overlaps the previous edit", start
+            )));
+        }
+        previous_end = end;
+    }
+    Ok(())
+}
+
+fn overwrite_in_place(path: &Path, edits: &[ByteEdit]) -> std::io::Result<()>
+{
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    for edit in edits
+    {
+        if let ByteEdit::Overwrite { offset, bytes } = edit
+        {
+            file.seek(SeekFrom::Start(*offset))?;
+            file.write_all(bytes)?;
+        }
+    }
+    file.sync_all()
+}
+
+/// Streams the patched contents from sorted, validated edits, reading
+/// the original a chunk at a time.
+fn write_spliced(original: &mut File, edits: &[ByteEdit], out: &mut dyn Write) -> io::Result<()>
+{
+    original.seek(SeekFrom::Start(0))?;
+    let mut cursor = 0u64;
+
+    for edit in edits
+    {
+        let (start, end) = edit_range(edit);
+        copy_exactly(original, start - cursor, out)?;
+        match edit
+        {
+            ByteEdit::Overwrite { bytes, .. } | ByteEdit::Insert { bytes, .. } => out.write_all(bytes)?,
+            ByteEdit::Delete { .. } => {},
+        }
+        original.seek(SeekFrom::Start(end))?;
+        cursor = end;
+    }
+
+    io::copy(original, out)?;
+    Ok(())
+}
+
+/// Copies `length` bytes, failing if the file shrank underneath us.
+fn copy_exactly(source: &mut File, length: u64, out: &mut dyn Write) -> io::Result<()>
+{
+    let copied = io::copy(&mut Read::take(&mut *source, length), out)?;
+    if copied < length
+    {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file changed while patching"));
+    }
+    Ok(())
+}
+
+fn patched_size(size: u64, edits: &[ByteEdit]) -> u64
+{
+    edits.iter().fold(size, |size, edit| match edit
+    {
+        ByteEdit::Overwrite { .. } => size,
+        ByteEdit::Insert { bytes, .. } => size + bytes.len() as u64,
+        ByteEdit::Delete { length, .. } => size - length,
+    })
+}
+
+fn as_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+{
+    serializer.serialize_str(&general_purpose::STANDARD.encode(bytes))
+}
+
+fn from_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error>
+{
+    let text = String::deserialize(deserializer)?;
+    general_purpose::STANDARD.decode(text).map_err(serde::de::Error::custom)
+}
+
+// ─── Sniffing ───────────────────────────────────────────────────
+
+/// Classifies a sample from the start of a file:
+/// 1. Known magic numbers are binary
+/// 2. A text BOM or BOM-less UTF-16 is text
+/// 3. Any NUL byte, or more than 10% control characters, is binary
+pub fn classify_bytes(sample: &[u8]) -> FileKind
+{
+    if let Some((_, format)) = MAGIC_NUMBERS.iter().find(|(magic, _)| sample.starts_with(magic))
+    {
+        return FileKind { is_binary: true, format: Some(format.to_string()), encoding: None };
+    }
+
+    let text = |encoding: TextEncoding| FileKind { is_binary: false, format: None, encoding: Some(encoding.label()) };
+
+    if let Some(encoding) = sniff_bom(sample)
+    {
+        return text(encoding);
+    }
+
+    let detected = detect_encoding(sample);
+    if matches!(detected.encoding, TextEncoding::Utf16Le | TextEncoding::Utf16Be)
+    {
+        return text(detected.encoding);
+    }
+
+    let control = sample.iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    if sample.contains(&0) || control * 10 > sample.len()
+    {
+        return FileKind { is_binary: true, format: None, encoding: None };
+    }
+
+    text(detected.encoding)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_temp(dir: &TempDir, bytes: &[u8]) -> String
+    {
+        let path = dir.path().join("blob.bin");
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    // ── read_bytes ──────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn read_bytes_returns_requested_window()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = write_temp(&dir, &[0, 1, 2, 3, 0xFF, 5]);
+
+        let chunk = read_bytes(path.clone(), 2, 3).await.unwrap();
+        assert_eq!(chunk.bytes, vec![2, 3, 0xFF]);
+        assert_eq!(chunk.file_size, 6);
+
+        let tail = read_bytes(path.clone(), 4, 100).await.unwrap();
+        assert_eq!(tail.bytes, vec![0xFF, 5]);
+        assert!(read_bytes(path, 10, 4).await.unwrap().bytes.is_empty());
+    }
+
+    #[test]
+    fn byte_chunk_serializes_bytes_as_base64()
+    {
+        let chunk = ByteChunk { offset: 0, bytes: vec![0, 0xFF, b'A'], file_size: 3 };
+        let json = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(json["bytes"], "AP9B");
+    }
+
+    #[tokio::test]
+    async fn read_bytes_reports_missing_file()
+    {
+        let err = read_bytes("/nonexistent/blob.bin".to_string(), 0, 4).await.unwrap_err();
+        assert_eq!(err.kind, "not-found");
+    }
+
+    // ── write_bytes_patch ───────────────────────────────────────
+
+    #[tokio::test]
+    async fn patch_overwrites_in_place()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = write_temp(&dir, &[0u8; 8]);
+
+        let size = write_bytes_patch(path.clone(), vec![
+            ByteEdit::Overwrite { offset: 6, bytes: vec![0xBE, 0xEF] },
+            ByteEdit::Overwrite { offset: 0, bytes: vec![0xDE, 0xAD] },
+        ], Some(true)).await.unwrap();
+
+        assert_eq!(size, 8);
+        assert_eq!(fs::read(&path).unwrap(), vec![0xDE, 0xAD, 0, 0, 0, 0, 0xBE, 0xEF]);
+    }
+
+    #[test]
+    fn patch_refreshes_the_merge_base()
+    {
+        use crate::commands::merge_operations::{DiskCheck, check_disk_changes, remember_version};
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "one\n").unwrap();
+        remember_version(&path, b"one\n", "one\n");
+
+        apply_patch(&path, vec![ByteEdit::Insert { offset: 4, bytes: b"two\n".to_vec() }], false).unwrap();
+        let check = check_disk_changes(&path.to_string_lossy(), "one\ntwo\n", None).unwrap();
+        assert!(matches!(check, DiskCheck::Unchanged));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn patch_replaces_the_file_unless_in_place_is_asked_for()
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = TempDir::new().unwrap();
+        let path = write_temp(&dir, b"abcd");
+        let inode = fs::metadata(&path).unwrap().ino();
+
+        write_bytes_patch(path.clone(), vec![ByteEdit::Overwrite { offset: 1, bytes: b"X".to_vec() }], None).await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"aXcd");
+        assert_ne!(fs::metadata(&path).unwrap().ino(), inode);
+    }
+
+    #[tokio::test]
+    async fn patch_inserts_and_deletes_using_original_offsets()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = write_temp(&dir, b"0123456789");
+
+        let size = write_bytes_patch(path.clone(), vec![
+            ByteEdit::Delete { offset: 7, length: 3 },
+            ByteEdit::Insert { offset: 2, bytes: b"ab".to_vec() },
+            ByteEdit::Overwrite { offset: 4, bytes: b"X".to_vec() },
+        ], None).await.unwrap();
+
+        assert_eq!(size, 9);
+        assert_eq!(fs::read(&path).unwrap(), b"01ab23X56");
+    }
+
+    #[tokio::test]
+    async fn patch_rejects_invalid_edits_without_writing()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = write_temp(&dir, b"abcdef");
+
+        let err = write_bytes_patch(path.clone(), vec![
+            ByteEdit::Overwrite { offset: 0, bytes: b"Z".to_vec() },
+            ByteEdit::Delete { offset: 4, length: 5 },
+        ], None).await.unwrap_err();
+        assert_eq!(err.kind, "out-of-range");
+
+        let err = write_bytes_patch(path.clone(), vec![
+            ByteEdit::Delete { offset: 1, length: 3 },
+            ByteEdit::Overwrite { offset: 2, bytes: b"Z".to_vec() },
+        ], None).await.unwrap_err();
+        assert_eq!(err.kind, "overlapping-edits");
+
+        assert_eq!(fs::read(&path).unwrap(), b"abcdef");
+    }
+
+    #[test]
+    fn byte_edit_deserializes_from_tagged_json()
+    {
+        let edit: ByteEdit = serde_json::from_str(r#"{"kind":"delete","offset":3,"length":2}"#).unwrap();
+        assert_eq!(edit, ByteEdit::Delete { offset: 3, length: 2 });
+    }
+
+    #[test]
+    fn byte_edit_bytes_deserialize_from_base64()
+    {
+        let edit: ByteEdit = serde_json::from_str(r#"{"kind":"insert","offset":1,"bytes":"AP9B"}"#).unwrap();
+        assert_eq!(edit, ByteEdit::Insert { offset: 1, bytes: vec![0, 0xFF, b'A'] });
+        assert!(serde_json::from_str::<ByteEdit>(r#"{"kind":"insert","offset":1,"bytes":[0,1]}"#).is_err());
+    }
+
+    // ── classify_bytes ──────────────────────────────────────────
+
+    #[test]
+    fn classify_recognizes_magic_numbers()
+    {
+        let kind = classify_bytes(b"\x7FELF\x02\x01\x01\0\0\0");
+        assert!(kind.is_binary);
+        assert_eq!(kind.format.as_deref(), Some("elf"));
+    }
+
+    #[test]
+    fn classify_treats_nul_bytes_as_binary()
+    {
+        assert!(classify_bytes(&[0x12, 0x00, 0x34, 0x56, 0x00, 0x00, 0x78, 0x9A]).is_binary);
+    }
+
+    #[test]
+    fn classify_accepts_text_encodings()
+    {
+        assert_eq!(classify_bytes(b"hello\r\nworld\t!").encoding.as_deref(), Some("utf-8"));
+
+        let utf16: Vec<u8> = "plain text".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let kind = classify_bytes(&utf16);
+        assert!(!kind.is_binary);
+        assert_eq!(kind.encoding.as_deref(), Some("utf-16le"));
+    }
+}
@@ -1,10 +1,18 @@
 use serde::Serialize;
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Emitter};
 use tauri_plugin_dialog::DialogExt;
 
-use super::file_operations::{FileTreeNode, build_file_tree_public};
+use super::access_operations::{GrantSource, grant};
+use super::file_operations::{
+    FileError, FileTreeNode, SaveOptions, read_text_file, write_text_file,
+};
+use super::quick_open_operations::index_workspace;
+use super::recent_operations::{RecentKind, record_recent, remove_recent};
+use super::save_operations::SaveStrategy;
+use super::tree_operations::{IgnoreStack, TreeFilter, cancel_all_scans, list_children, load_workspace_settings};
+use super::trigram_operations::load_workspace_index;
+use super::watcher_operations::watch_workspace;
 
 // ─── Types ──────────────────────────────────────────────────────
 
@@ -14,6 +22,12 @@ pub struct OpenedFileData
     pub path: String,
     pub content: String,
     pub name: String,
+    pub encoding: String,
+    #[serde(rename = "hasBom")]
+    pub has_bom: bool,
+    /// Dominant line ending ("LF", "CRLF" or "CR"), so saves can preserve it.
+    #[serde(rename = "lineEnding")]
+    pub line_ending: String,
 }
 
 #[derive(Serialize, Clone)]
@@ -28,6 +42,7 @@ pub struct FileSavedData
 {
     pub path: String,
     pub name: String,
+    pub strategy: SaveStrategy,
 }
 
 // ─── Commands ───────────────────────────────────────────────────
@@ -48,34 +63,9 @@ pub async fn open_file_dialog(app: AppHandle) -> Result<(), String>
         for file_path in paths
         {
             let path_str = file_path.to_string();
-            // Remove file:// prefix if present
-            let clean_path = if path_str.starts_with("file://")
-            {
-                &path_str[7..]
-            }
-            else
-            {
-                &path_str
-            };
-
-            match fs::read_to_string(clean_path)
-            {
-                Ok(content) => {
-                    let name = Path::new(clean_path)
-                        .file_name()
-                        .map(|n| n.to_string_lossy().into_owned())
-                        .unwrap_or_else(|| "untitled".to_string());
-
-                    let _ = app.emit("file-opened", OpenedFileData {
-                        path: clean_path.to_string(),
-                        content,
-                        name,
-                    });
-                },
-                Err(e) => {
-                    eprintln!("Failed to read file '{}': {}", clean_path, e);
-                }
-            }
+            let clean_path = clean_file_path(&path_str);
+            grant(Path::new(clean_path), GrantSource::Dialog, false);
+            open_file(&app, clean_path);
         }
     }
 
@@ -92,22 +82,9 @@ pub async fn open_folder_dialog(app: AppHandle) -> Result<(), String>
     if let Some(folder_path) = folder
     {
         let path_str = folder_path.to_string();
-        let clean_path = if path_str.starts_with("file://")
-        {
-            &path_str[7..]
-        }
-        else
-        {
-            &path_str
-        };
-
-        let tree = build_file_tree_public(Path::new(clean_path))
-            .map_err(|e| format!("Failed to read folder: {}", e))?;
-
-        let _ = app.emit("folder-opened", FolderData {
-            path: clean_path.to_string(),
-            tree,
-        });
+        let clean_path = clean_file_path(&path_str);
+        grant(Path::new(clean_path), GrantSource::Workspace, true);
+        open_workspace(&app, clean_path).await?;
     }
 
     Ok(())
@@ -118,7 +95,8 @@ pub async fn save_file_dialog(
     app: AppHandle,
     content: String,
     suggested_name: String,
-) -> Result<(), String>
+    options: Option<SaveOptions>,
+) -> Result<(), FileError>
 {
     let default_name = if suggested_name.is_empty()
     {
@@ -139,17 +117,10 @@ pub async fn save_file_dialog(
     if let Some(file_path) = save_path
     {
         let path_str = file_path.to_string();
-        let clean_path = if path_str.starts_with("file://")
-        {
-            &path_str[7..]
-        }
-        else
-        {
-            &path_str
-        };
+        let clean_path = clean_file_path(&path_str);
+        grant(Path::new(clean_path), GrantSource::Dialog, false);
 
-        fs::write(clean_path, &content)
-            .map_err(|e| format!("Failed to save file: {}", e))?;
+        let strategy = write_text_file(clean_path, &content, &options.unwrap_or_default())?;
 
         let name = Path::new(clean_path)
             .file_name()
@@ -159,28 +130,123 @@ pub async fn save_file_dialog(
         let _ = app.emit("file-saved", FileSavedData {
             path: clean_path.to_string(),
             name,
+            strategy,
         });
     }
 
     Ok(())
 }
 
-// ─── Path Cleaning Helper ───────────────────────────────────────
+// ─── Opening ────────────────────────────────────────────────────
 
-/// Strips the file:// protocol prefix from a path string if present.
-/// Used by dialog operations to normalize file paths from OS dialogs.
-pub fn clean_file_path(path: &str) -> &str
+/// Reads an already granted file and hands it to the frontend as
+/// "file-opened", remembering it in the recent files. Returns false if
+/// the file could not be read ("file-open-failed" is emitted instead).
+pub fn open_file(app: &AppHandle, clean_path: &str) -> bool
 {
-    if path.starts_with("file://")
+    match read_text_file(clean_path, None)
     {
-        &path[7..]
+        Ok(decoded) => {
+            let name = Path::new(clean_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "untitled".to_string());
+
+            let _ = app.emit("file-opened", OpenedFileData {
+                path: clean_path.to_string(),
+                content: decoded.content,
+                name,
+                encoding: decoded.encoding,
+                has_bom: decoded.has_bom,
+                line_ending: decoded.line_ending,
+            });
+            record_recent(app, clean_path, RecentKind::File);
+            true
+        },
+        Err(e) => {
+            let _ = app.emit("file-open-failed", e);
+            false
+        }
     }
-    else
+}
+
+/// Opens an already granted folder as the workspace: sends its top level
+/// as "folder-opened", starts watching and indexing it, and remembers it
+/// in the recent folders.
+pub async fn open_workspace(app: &AppHandle, clean_path: &str) -> Result<(), String>
+{
+    // Only the top level is sent; subfolders load through read_dir_children
+    cancel_all_scans();
+    let root = PathBuf::from(clean_path);
+    load_workspace_settings(&root);
+    let filter = TreeFilter::current().within(&root);
+    let tree = tauri::async_runtime::spawn_blocking(move || list_children(&root, &filter, &IgnoreStack::for_dir(&root)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read folder: {}", e))?;
+
+    let _ = app.emit("folder-opened", FolderData {
+        path: clean_path.to_string(),
+        tree,
+    });
+    record_recent(app, clean_path, RecentKind::Folder);
+
+    if let Err(e) = watch_workspace(app, clean_path)
+    {
+        eprintln!("{}", e);
+    }
+    index_workspace(app, Path::new(clean_path));
+    load_workspace_index(app, Path::new(clean_path));
+    Ok(())
+}
+
+/// Reopens an Open Recent entry. Picking it counts as the same consent
+/// as picking it in a dialog; entries whose path has disappeared are
+/// dropped from the list.
+pub async fn open_recent(app: &AppHandle, path: &str, kind: RecentKind)
+{
+    let opened = match kind
+    {
+        RecentKind::File => {
+            grant(Path::new(path), GrantSource::Dialog, false);
+            open_file(app, path)
+        },
+        RecentKind::Folder => {
+            if Path::new(path).is_dir()
+            {
+                grant(Path::new(path), GrantSource::Workspace, true);
+                match open_workspace(app, path).await
+                {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        false
+                    }
+                }
+            }
+            else
+            {
+                let _ = app.emit("file-open-failed", FileError::new(path, "not-found", format!("Folder '{}' no longer exists", path)));
+                false
+            }
+        },
+    };
+
+    if !opened && !Path::new(path).exists()
     {
-        path
+        let _ = remove_recent(app.clone(), path.to_string()).await;
     }
 }
 
+// ─── Path Cleaning Helper ───────────────────────────────────────
+
+/// Strips the file:// protocol prefix from a path string if present.
+/// Used by dialog operations to normalize file paths from OS dialogs.
+pub fn clean_file_path(path: &str) -> &str
+{
+    path.strip_prefix("file://").unwrap_or(path)
+}
+
 // ─── Tests ─────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -241,12 +307,18 @@ mod tests
             path: "/tmp/test.txt".into(),
             content: "hello world".into(),
             name: "test.txt".into(),
+            encoding: "utf-16le".into(),
+            has_bom: true,
+            line_ending: "CRLF".into(),
         };
 
         let json = serde_json::to_value(&data).unwrap();
         assert_eq!(json["path"], "/tmp/test.txt");
         assert_eq!(json["content"], "hello world");
         assert_eq!(json["name"], "test.txt");
+        assert_eq!(json["encoding"], "utf-16le");
+        assert_eq!(json["hasBom"], true);
+        assert_eq!(json["lineEnding"], "CRLF");
     }
 
     #[test]
@@ -259,12 +331,18 @@ mod tests
                     name: "src".into(),
                     path: "/tmp/project/src".into(),
                     is_directory: true,
+                    is_symlink: false,
+                    link_target: None,
+                    is_broken: false,
                     children: Some(vec![]),
                 },
                 FileTreeNode {
                     name: "README.md".into(),
                     path: "/tmp/project/README.md".into(),
                     is_directory: false,
+                    is_symlink: false,
+                    link_target: None,
+                    is_broken: false,
                     children: None,
                 },
             ],
@@ -284,11 +362,13 @@ mod tests
         let data = FileSavedData {
             path: "/tmp/saved.txt".into(),
             name: "saved.txt".into(),
+            strategy: SaveStrategy::InPlace,
         };
 
         let json = serde_json::to_value(&data).unwrap();
         assert_eq!(json["path"], "/tmp/saved.txt");
         assert_eq!(json["name"], "saved.txt");
+        assert_eq!(json["strategy"], "in-place");
     }
 
     #[test]
@@ -298,6 +378,9 @@ mod tests
             path: "/tmp/test.txt".into(),
             content: "hello".into(),
             name: "test.txt".into(),
+            encoding: "utf-8".into(),
+            has_bom: false,
+            line_ending: "LF".into(),
         };
 
         let cloned = data.clone();
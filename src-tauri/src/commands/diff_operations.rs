@@ -0,0 +1,814 @@
+use ignore::WalkState;
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffOp, DiffTag, capture_diff_slices, capture_diff_slices_deadline, group_diff_ops};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use super::access_operations::check_access;
+use super::file_operations::FileError;
+use super::search_operations::{SearchQuery, read_searchable, walk_files};
+use super::tree_operations::{TreeSettings, current_settings};
+
+const DEFAULT_CONTEXT_LINES: usize = 3;
+/// After this long the line diff settles for a correct but possibly
+/// longer-than-minimal result instead of searching on.
+const LINE_DIFF_DEADLINE: Duration = Duration::from_secs(2);
+/// Longer lines are reported as changed without intra-line detail.
+const MAX_INLINE_LINE_LENGTH: usize = 10_000;
+/// Block size for comparing files byte for byte.
+const COMPARE_CHUNK_SIZE: usize = 64 * 1024;
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct DiffOptions
+{
+    /// Lines that differ only in spaces and tabs compare equal.
+    #[serde(rename = "ignoreWhitespace")]
+    pub ignore_whitespace: bool,
+    #[serde(rename = "ignoreCase")]
+    pub ignore_case: bool,
+    /// CRLF, LF, CR and a missing final newline compare equal.
+    #[serde(rename = "ignoreEol")]
+    pub ignore_eol: bool,
+    /// Unchanged lines shown around each hunk (default 3).
+    #[serde(rename = "contextLines")]
+    pub context_lines: Option<usize>,
+    pub inline: InlineMode,
+    /// Folder compares skip what the project tree hides (ignore files and
+    /// exclusions). Off by default, so every file is compared.
+    #[serde(rename = "useTreeFilters")]
+    pub use_tree_filters: bool,
+}
+
+/// Granularity of the changes highlighted inside modified lines.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineMode
+{
+    #[default]
+    Word,
+    Char,
+    None,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind
+{
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A line of a hunk. Modified lines appear as a delete followed by the
+/// matching insert, each carrying the ranges that actually changed.
+#[derive(Serialize, Clone, Debug)]
+pub struct DiffLine
+{
+    pub kind: DiffLineKind,
+    /// 1-based line number on the left, absent for inserts.
+    #[serde(rename = "leftLine")]
+    pub left_line: Option<usize>,
+    #[serde(rename = "rightLine")]
+    pub right_line: Option<usize>,
+    /// Line text without its line ending.
+    pub text: String,
+    pub changes: Vec<InlineChange>,
+}
+
+/// A changed span of a modified line, in characters.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InlineChange
+{
+    /// 1-based character column.
+    pub column: usize,
+    pub length: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiffHunk
+{
+    /// 1-based first line of the hunk on each side.
+    #[serde(rename = "leftStart")]
+    pub left_start: usize,
+    #[serde(rename = "leftCount")]
+    pub left_count: usize,
+    #[serde(rename = "rightStart")]
+    pub right_start: usize,
+    #[serde(rename = "rightCount")]
+    pub right_count: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiffResult
+{
+    pub hunks: Vec<DiffHunk>,
+    #[serde(rename = "leftLineCount")]
+    pub left_line_count: usize,
+    #[serde(rename = "rightLineCount")]
+    pub right_line_count: usize,
+    #[serde(rename = "deletedLines")]
+    pub deleted_lines: usize,
+    #[serde(rename = "insertedLines")]
+    pub inserted_lines: usize,
+    pub identical: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FolderEntryStatus
+{
+    Identical,
+    Different,
+    LeftOnly,
+    RightOnly,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FolderDiffEntry
+{
+    /// '/'-separated path below both roots.
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+    pub status: FolderEntryStatus,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn compare_files(left_path: String, right_path: String, options: DiffOptions) -> Result<DiffResult, FileError>
+{
+    check_access(&left_path)?;
+    check_access(&right_path)?;
+
+    let error_path = left_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let left = read_text(&left_path)?;
+        let right = read_text(&right_path)?;
+        Ok(diff_texts(&left, &right, &options))
+    })
+    .await
+    .map_err(|e| FileError::new(&error_path, "io", e.to_string()))?
+}
+
+/// Compares the saved file (left) with the editor's unsaved text (right).
+#[tauri::command]
+pub async fn compare_buffer_with_file(path: String, content: String, options: DiffOptions) -> Result<DiffResult, FileError>
+{
+    check_access(&path)?;
+
+    let error_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let saved = read_text(&path)?;
+        Ok(diff_texts(&saved, &content, &options))
+    })
+    .await
+    .map_err(|e| FileError::new(&error_path, "io", e.to_string()))?
+}
+
+/// Lists every file under either root with whether it exists on one side
+/// only, or on both with the same or different contents. The ignore
+/// options apply to text files, so whitespace-only edits can be hidden.
+#[tauri::command]
+pub async fn compare_folders(left_root: String, right_root: String, options: DiffOptions) -> Result<Vec<FolderDiffEntry>, FileError>
+{
+    check_access(&left_root)?;
+    check_access(&right_root)?;
+
+    let error_path = left_root.clone();
+    tauri::async_runtime::spawn_blocking(move || compare_trees(Path::new(&left_root), Path::new(&right_root), &options))
+        .await
+        .map_err(|e| FileError::new(&error_path, "io", e.to_string()))?
+}
+
+// ─── Diffing ────────────────────────────────────────────────────
+
+/// Line diff of two texts with intra-line changes for modified lines.
+pub fn diff_texts(left: &str, right: &str, options: &DiffOptions) -> DiffResult
+{
+    let left_lines = split_with_endings(left);
+    let right_lines = split_with_endings(right);
+
+    // Compare small integer IDs rather than strings; equal IDs mean the
+    // lines are equal under the chosen ignore options
+    let mut ids: HashMap<String, u32> = HashMap::new();
+    let mut intern = |line: &(&str, &str)| {
+        let key = normalize_line(line, options);
+        let next = ids.len() as u32;
+        *ids.entry(key).or_insert(next)
+    };
+    let left_ids: Vec<u32> = left_lines.iter().map(&mut intern).collect();
+    let right_ids: Vec<u32> = right_lines.iter().map(&mut intern).collect();
+
+    let deadline = Instant::now() + LINE_DIFF_DEADLINE;
+    let ops = capture_diff_slices_deadline(Algorithm::Patience, &left_ids, &right_ids, Some(deadline));
+
+    let mut deleted_lines = 0;
+    let mut inserted_lines = 0;
+    for op in &ops
+    {
+        let (tag, left_range, right_range) = op.as_tag_tuple();
+        if tag != DiffTag::Equal
+        {
+            deleted_lines += left_range.len();
+            inserted_lines += right_range.len();
+        }
+    }
+
+    let context = options.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES);
+    let hunks = if deleted_lines + inserted_lines == 0
+    {
+        Vec::new()
+    }
+    else
+    {
+        group_diff_ops(ops, context).iter()
+            .map(|group| build_hunk(group, &left_lines, &right_lines, options))
+            .collect()
+    };
+
+    DiffResult {
+        identical: hunks.is_empty(),
+        hunks,
+        left_line_count: left_lines.len(),
+        right_line_count: right_lines.len(),
+        deleted_lines,
+        inserted_lines,
+    }
+}
+
+fn build_hunk(group: &[DiffOp], left: &[(&str, &str)], right: &[(&str, &str)], options: &DiffOptions) -> DiffHunk
+{
+    let (_, first_left, first_right) = group[0].as_tag_tuple();
+    let (_, last_left, last_right) = group[group.len() - 1].as_tag_tuple();
+    let mut lines = Vec::new();
+
+    for op in group
+    {
+        let (tag, left_range, right_range) = op.as_tag_tuple();
+        match tag
+        {
+            DiffTag::Equal => {
+                for (l, r) in left_range.zip(right_range)
+                {
+                    lines.push(diff_line(DiffLineKind::Equal, Some(l), Some(r), left[l].0, Vec::new()));
+                }
+            },
+            DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                // Pair lines up in order so each pair gets intra-line detail
+                let paired: Vec<_> = left_range.clone().zip(right_range.clone())
+                    .map(|(l, r)| inline_changes(left[l].0, right[r].0, options))
+                    .collect();
+                let mut deletes = Vec::new();
+                let mut inserts = Vec::new();
+                for (i, l) in left_range.enumerate()
+                {
+                    let changes = paired.get(i).map(|pair| pair.0.clone()).unwrap_or_default();
+                    deletes.push(diff_line(DiffLineKind::Delete, Some(l), None, left[l].0, changes));
+                }
+                for (i, r) in right_range.enumerate()
+                {
+                    let changes = paired.get(i).map(|pair| pair.1.clone()).unwrap_or_default();
+                    inserts.push(diff_line(DiffLineKind::Insert, None, Some(r), right[r].0, changes));
+                }
+                lines.extend(deletes);
+                lines.extend(inserts);
+            },
+        }
+    }
+
+    DiffHunk {
+        left_start: first_left.start + 1,
+        left_count: last_left.end - first_left.start,
+        right_start: first_right.start + 1,
+        right_count: last_right.end - first_right.start,
+        lines,
+    }
+}
+
+fn diff_line(kind: DiffLineKind, left: Option<usize>, right: Option<usize>, text: &str, changes: Vec<InlineChange>) -> DiffLine
+{
+    DiffLine {
+        kind,
+        left_line: left.map(|l| l + 1),
+        right_line: right.map(|r| r + 1),
+        text: text.to_string(),
+        changes,
+    }
+}
+
+/// The changed spans of a modified line on each side. Tokens are words
+/// (or single characters) compared under the same ignore options as the
+/// lines, so e.g. a case-only edit is not highlighted with `ignoreCase`.
+pub fn inline_changes(left: &str, right: &str, options: &DiffOptions) -> (Vec<InlineChange>, Vec<InlineChange>)
+{
+    if options.inline == InlineMode::None
+    {
+        return (Vec::new(), Vec::new());
+    }
+    if left.len() > MAX_INLINE_LINE_LENGTH || right.len() > MAX_INLINE_LINE_LENGTH
+    {
+        return (whole_line(left), whole_line(right));
+    }
+
+    let left_tokens = tokenize(left, options.inline);
+    let right_tokens = tokenize(right, options.inline);
+    let key = |token: &&str| {
+        let mut key = if options.ignore_case { token.to_lowercase() } else { token.to_string() };
+        if options.ignore_whitespace && key.chars().all(is_blank)
+        {
+            key.clear();
+        }
+        key
+    };
+    let left_keys: Vec<String> = left_tokens.iter().map(key).collect();
+    let right_keys: Vec<String> = right_tokens.iter().map(key).collect();
+
+    let mut left_changes = Vec::new();
+    let mut right_changes = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &left_keys, &right_keys)
+    {
+        let (tag, left_range, right_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal
+        {
+            continue;
+        }
+        push_change(&mut left_changes, &left_tokens, left_range, options);
+        push_change(&mut right_changes, &right_tokens, right_range, options);
+    }
+    (left_changes, right_changes)
+}
+
+/// Records the character span of `tokens[range]`, merging it into the
+/// previous span when they touch. Whitespace-only spans are dropped when
+/// whitespace is ignored.
+fn push_change(changes: &mut Vec<InlineChange>, tokens: &[&str], range: std::ops::Range<usize>, options: &DiffOptions)
+{
+    if range.is_empty()
+    {
+        return;
+    }
+    let changed: String = tokens[range.clone()].concat();
+    if options.ignore_whitespace && changed.chars().all(is_blank)
+    {
+        return;
+    }
+
+    let column = tokens[..range.start].iter().map(|t| t.chars().count()).sum::<usize>() + 1;
+    let length = changed.chars().count();
+    match changes.last_mut()
+    {
+        Some(last) if last.column + last.length == column => last.length += length,
+        _ => changes.push(InlineChange { column, length }),
+    }
+}
+
+fn whole_line(text: &str) -> Vec<InlineChange>
+{
+    match text.chars().count()
+    {
+        0 => Vec::new(),
+        length => vec![InlineChange { column: 1, length }],
+    }
+}
+
+/// Splits a line into words, runs of blanks and single other characters,
+/// or into single characters for `InlineMode::Char`.
+fn tokenize(line: &str, mode: InlineMode) -> Vec<&str>
+{
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next()
+    {
+        let mut end = start + c.len_utf8();
+        if mode == InlineMode::Word
+        {
+            let same_class = |other: char| {
+                (is_word(c) && is_word(other)) || (is_blank(c) && is_blank(other))
+            };
+            while let Some(&(next_start, next)) = chars.peek()
+            {
+                if !same_class(next)
+                {
+                    break;
+                }
+                end = next_start + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(&line[start..end]);
+    }
+    tokens
+}
+
+/// The comparison key of a line under the ignore options.
+fn normalize_line(line: &(&str, &str), options: &DiffOptions) -> String
+{
+    let (text, ending) = *line;
+    let mut key: String = if options.ignore_whitespace
+    {
+        text.chars().filter(|c| !is_blank(*c)).collect()
+    }
+    else
+    {
+        text.to_string()
+    };
+    if options.ignore_case
+    {
+        key = key.to_lowercase();
+    }
+    if !options.ignore_eol
+    {
+        key.push_str(ending);
+    }
+    key
+}
+
+/// Lines paired with their terminator (LF, CRLF, lone CR, or "" for a
+/// last line without one). Text ending in a newline has no extra empty line.
+pub fn split_with_endings(text: &str) -> Vec<(&str, &str)>
+{
+    let mut lines = Vec::new();
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len()
+    {
+        match bytes[i]
+        {
+            b'\n' => {
+                lines.push((&text[start..i], "\n"));
+                start = i + 1;
+            },
+            b'\r' => {
+                if bytes.get(i + 1) == Some(&b'\n')
+                {
+                    lines.push((&text[start..i], "\r\n"));
+                    i += 1;
+                }
+                else
+                {
+                    lines.push((&text[start..i], "\r"));
+                }
+                start = i + 1;
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if start < text.len()
+    {
+        lines.push((&text[start..], ""));
+    }
+    lines
+}
+
+fn is_word(c: char) -> bool
+{
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_blank(c: char) -> bool
+{
+    c == ' ' || c == '\t' || c == '\u{a0}' || c == '\u{feff}'
+}
+
+// ─── Folders ────────────────────────────────────────────────────
+
+/// Compares two trees file by file. Only with `use_tree_filters` are the
+/// project tree's exclusions and ignore files applied on both sides.
+pub fn compare_trees(left_root: &Path, right_root: &Path, options: &DiffOptions) -> Result<Vec<FolderDiffEntry>, FileError>
+{
+    let settings = if options.use_tree_filters
+    {
+        current_settings()
+    }
+    else
+    {
+        TreeSettings { respect_ignore_files: false, exclude: Vec::new(), ..TreeSettings::default() }
+    };
+    let left = list_tree(left_root, &settings)?;
+    let right = list_tree(right_root, &settings)?;
+
+    let mut statuses: BTreeMap<&String, FolderEntryStatus> = BTreeMap::new();
+    for relative in left.keys()
+    {
+        statuses.insert(relative, FolderEntryStatus::LeftOnly);
+    }
+    for relative in right.keys()
+    {
+        let status = if let Some(&left_size) = left.get(relative)
+        {
+            let sizes = (left_size, right[relative]);
+            if same_contents(&left_root.join(relative), &right_root.join(relative), sizes, options)
+            {
+                FolderEntryStatus::Identical
+            }
+            else
+            {
+                FolderEntryStatus::Different
+            }
+        }
+        else
+        {
+            FolderEntryStatus::RightOnly
+        };
+        statuses.insert(relative, status);
+    }
+
+    Ok(statuses.into_iter()
+        .map(|(relative, status)| FolderDiffEntry { relative_path: relative.clone(), status })
+        .collect())
+}
+
+/// Relative path → size of every file under `root`.
+fn list_tree(root: &Path, settings: &TreeSettings) -> Result<BTreeMap<String, u64>, FileError>
+{
+    let display = root.to_string_lossy();
+    if !root.is_dir()
+    {
+        return Err(FileError::new(&display, "not-a-directory", format!("'{}' is not a folder", display)));
+    }
+
+    let files = Mutex::new(BTreeMap::new());
+    walk_files(root, &SearchQuery::default(), settings, &AtomicBool::new(false), |path| {
+        if let Ok(relative) = path.strip_prefix(root)
+        {
+            let relative: Vec<String> = relative.components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            if let Ok(mut files) = files.lock()
+            {
+                files.insert(relative.join("/"), size);
+            }
+        }
+        WalkState::Continue
+    })
+    .map_err(|e| FileError::new(&display, "io", e))?;
+
+    Ok(files.into_inner().unwrap_or_default())
+}
+
+/// Files of different sizes differ unless an ignore option could make
+/// them equal; equal sizes are compared a block at a time.
+fn same_contents(left: &Path, right: &Path, sizes: (u64, u64), options: &DiffOptions) -> bool
+{
+    let ignoring = options.ignore_whitespace || options.ignore_case || options.ignore_eol;
+    if sizes.0 == sizes.1 && same_bytes(left, right).unwrap_or(false)
+    {
+        return true;
+    }
+    if !ignoring
+    {
+        return false;
+    }
+
+    match (read_searchable(left), read_searchable(right))
+    {
+        (Ok(Some(left)), Ok(Some(right))) => {
+            let left_lines = split_with_endings(&left);
+            let right_lines = split_with_endings(&right);
+            left_lines.len() == right_lines.len()
+                && left_lines.iter().zip(&right_lines).all(|(l, r)| normalize_line(l, options) == normalize_line(r, options))
+        },
+        _ => false,
+    }
+}
+
+fn same_bytes(left: &Path, right: &Path) -> io::Result<bool>
+{
+    let (mut left, mut right) = (File::open(left)?, File::open(right)?);
+    let mut left_block = vec![0u8; COMPARE_CHUNK_SIZE];
+    let mut right_block = vec![0u8; COMPARE_CHUNK_SIZE];
+    loop
+    {
+        let read = read_block(&mut left, &mut left_block)?;
+        if read != read_block(&mut right, &mut right_block)? || left_block[..read] != right_block[..read]
+        {
+            return Ok(false);
+        }
+        if read == 0
+        {
+            return Ok(true);
+        }
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+/// Fills `block` unless the file ends first.
+fn read_block(file: &mut File, block: &mut [u8]) -> io::Result<usize>
+{
+    let mut filled = 0;
+    while filled < block.len()
+    {
+        match file.read(&mut block[filled..])?
+        {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Decoded text of a file to compare; binary and oversized files are refused.
+fn read_text(path: &str) -> Result<String, FileError>
+{
+    match read_searchable(Path::new(path))
+    {
+        Ok(Some(text)) => Ok(text),
+        Ok(None) => Err(FileError::new(path, "binary", format!("Cannot compare '{}': binary or too large", path))),
+        Err(e) => Err(FileError::from_io(path, "read", &e)),
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn options() -> DiffOptions
+    {
+        DiffOptions::default()
+    }
+
+    fn kinds(result: &DiffResult) -> Vec<(DiffLineKind, Option<usize>, Option<usize>)>
+    {
+        result.hunks.iter()
+            .flat_map(|hunk| hunk.lines.iter().map(|l| (l.kind.clone(), l.left_line, l.right_line)))
+            .collect()
+    }
+
+    // ── Lines ───────────────────────────────────────────────────
+
+    #[test]
+    fn identical_texts_have_no_hunks()
+    {
+        let result = diff_texts("a\nb\n", "a\nb\n", &options());
+        assert!(result.identical);
+        assert_eq!(result.left_line_count, 2);
+    }
+
+    #[test]
+    fn hunks_carry_context_and_line_numbers()
+    {
+        let left = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let right = "1\n2\n3\n4\nfive\n6\n7\n8\n9\nten\n";
+        let result = diff_texts(left, right, &DiffOptions { context_lines: Some(1), ..options() });
+
+        assert_eq!(result.hunks.len(), 2);
+        let first = &result.hunks[0];
+        assert_eq!((first.left_start, first.left_count, first.right_start, first.right_count), (4, 3, 4, 3));
+        assert_eq!(kinds(&result)[..4], [
+            (DiffLineKind::Equal, Some(4), Some(4)),
+            (DiffLineKind::Delete, Some(5), None),
+            (DiffLineKind::Insert, None, Some(5)),
+            (DiffLineKind::Equal, Some(6), Some(6)),
+        ]);
+        assert_eq!(result.hunks[1].lines.last().unwrap().text, "ten");
+        assert_eq!((result.deleted_lines, result.inserted_lines), (1, 2));
+    }
+
+    #[test]
+    fn ignore_modes_hide_matching_differences()
+    {
+        assert!(!diff_texts("a b\r\n", "a  b\n", &options()).identical);
+        assert!(diff_texts("a b\r\n", "ab\n", &DiffOptions { ignore_whitespace: true, ignore_eol: true, ..options() }).identical);
+        assert!(diff_texts("Hello\n", "hELLO\n", &DiffOptions { ignore_case: true, ..options() }).identical);
+        assert!(diff_texts("end\n", "end", &DiffOptions { ignore_eol: true, ..options() }).identical);
+        assert!(!diff_texts("end\n", "end", &options()).identical);
+    }
+
+    #[test]
+    fn large_inputs_diff_quickly()
+    {
+        let left: String = (0..100_000).map(|i| format!("line {}\n", i)).collect();
+        let right = left.replace("line 500\n", "line 500 changed\n").replace("line 90000\n", "");
+
+        let started = Instant::now();
+        let result = diff_texts(&left, &right, &options());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!((result.deleted_lines, result.inserted_lines), (2, 1));
+    }
+
+    // ── Inline ──────────────────────────────────────────────────
+
+    #[test]
+    fn word_changes_cover_whole_words()
+    {
+        let (left, right) = inline_changes("let total = price * 2;", "let total = cost * 2;", &options());
+        assert_eq!(left, vec![InlineChange { column: 13, length: 5 }]);
+        assert_eq!(right, vec![InlineChange { column: 13, length: 4 }]);
+    }
+
+    #[test]
+    fn char_changes_are_precise_and_count_characters()
+    {
+        let char_mode = DiffOptions { inline: InlineMode::Char, ..options() };
+        let (left, right) = inline_changes("héllo wörld", "héllo world", &char_mode);
+        assert_eq!(left, vec![InlineChange { column: 8, length: 1 }]);
+        assert_eq!(right, vec![InlineChange { column: 8, length: 1 }]);
+    }
+
+    #[test]
+    fn ignored_whitespace_is_not_highlighted()
+    {
+        let ignore = DiffOptions { ignore_whitespace: true, ..options() };
+        let (left, right) = inline_changes("a = b;  x", "a=b; y", &ignore);
+        assert_eq!(left, vec![InlineChange { column: 9, length: 1 }]);
+        assert_eq!(right, vec![InlineChange { column: 6, length: 1 }]);
+    }
+
+    #[test]
+    fn split_keeps_each_line_ending()
+    {
+        assert_eq!(split_with_endings("a\r\nb\rc\nd"), vec![("a", "\r\n"), ("b", "\r"), ("c", "\n"), ("d", "")]);
+        assert!(split_with_endings("").is_empty());
+    }
+
+    // ── Folders ─────────────────────────────────────────────────
+
+    #[test]
+    fn folders_report_each_file_status()
+    {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        fs::create_dir(left.path().join("sub")).unwrap();
+        fs::create_dir(right.path().join("sub")).unwrap();
+        fs::write(left.path().join("same.txt"), "same").unwrap();
+        fs::write(right.path().join("same.txt"), "same").unwrap();
+        fs::write(left.path().join("sub/crlf.txt"), "x\r\n").unwrap();
+        fs::write(right.path().join("sub/crlf.txt"), "x\n").unwrap();
+        fs::write(left.path().join("old.txt"), "").unwrap();
+        fs::write(right.path().join("new.txt"), "").unwrap();
+
+        let statuses = |options: &DiffOptions| -> Vec<(String, FolderEntryStatus)> {
+            compare_trees(left.path(), right.path(), options).unwrap()
+                .into_iter()
+                .map(|entry| (entry.relative_path, entry.status))
+                .collect()
+        };
+
+        assert_eq!(statuses(&options()), vec![
+            ("new.txt".to_string(), FolderEntryStatus::RightOnly),
+            ("old.txt".to_string(), FolderEntryStatus::LeftOnly),
+            ("same.txt".to_string(), FolderEntryStatus::Identical),
+            ("sub/crlf.txt".to_string(), FolderEntryStatus::Different),
+        ]);
+        assert_eq!(statuses(&DiffOptions { ignore_eol: true, ..options() })[3].1, FolderEntryStatus::Identical);
+    }
+
+    #[test]
+    fn folders_ignore_tree_filters_unless_asked()
+    {
+        let left = TempDir::new().unwrap();
+        let right = TempDir::new().unwrap();
+        for root in [left.path(), right.path()]
+        {
+            fs::create_dir(root.join("node_modules")).unwrap();
+            fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        }
+        fs::write(left.path().join("node_modules/dep.js"), "1").unwrap();
+        fs::write(left.path().join("build.log"), "x").unwrap();
+
+        let paths = |options: &DiffOptions| -> Vec<String> {
+            compare_trees(left.path(), right.path(), options).unwrap()
+                .into_iter()
+                .filter(|entry| entry.status == FolderEntryStatus::LeftOnly)
+                .map(|entry| entry.relative_path)
+                .collect()
+        };
+
+        assert_eq!(paths(&options()), vec!["build.log", "node_modules/dep.js"]);
+        assert!(paths(&DiffOptions { use_tree_filters: true, ..options() }).is_empty());
+    }
+
+    #[test]
+    fn large_files_are_compared_block_by_block()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut contents = vec![b'a'; COMPARE_CHUNK_SIZE * 2 + 10];
+        fs::write(dir.path().join("left"), &contents).unwrap();
+        fs::write(dir.path().join("same"), &contents).unwrap();
+        *contents.last_mut().unwrap() = b'b';
+        fs::write(dir.path().join("right"), &contents).unwrap();
+
+        assert!(same_bytes(&dir.path().join("left"), &dir.path().join("same")).unwrap());
+        assert!(!same_bytes(&dir.path().join("left"), &dir.path().join("right")).unwrap());
+    }
+}
@@ -0,0 +1,820 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+use std::fs;
+
+use super::access_operations::check_access;
+
+/// How many leading bytes are inspected by the heuristic detectors.
+const SNIFF_SAMPLE_SIZE: usize = 64 * 1024;
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// A text encoding the backend knows how to decode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEncoding
+{
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Strict ISO 8859-1. encoding_rs folds this label into Windows-1252,
+    /// so it is handled separately to keep 0x80–0x9F as C1 controls.
+    Latin1,
+    /// Any other WHATWG encoding (Windows-1252, Shift_JIS, GBK, ...).
+    Legacy(&'static Encoding),
+}
+
+/// Result of sniffing a byte buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedEncoding
+{
+    pub encoding: TextEncoding,
+    pub has_bom: bool,
+    pub confidence: f32,
+}
+
+/// Decoded text plus the facts the frontend needs to round-trip it.
+#[derive(Serialize, Clone, Debug)]
+pub struct DecodedText
+{
+    pub content: String,
+    pub encoding: String,
+    #[serde(rename = "hasBom")]
+    pub has_bom: bool,
+    pub confidence: f32,
+    /// True when malformed sequences were replaced with U+FFFD.
+    #[serde(rename = "hadErrors")]
+    pub had_errors: bool,
+    /// Dominant line ending ("LF", "CRLF" or "CR"), so saves can preserve it.
+    #[serde(rename = "lineEnding")]
+    pub line_ending: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EncodingInfo
+{
+    pub encoding: String,
+    #[serde(rename = "hasBom")]
+    pub has_bom: bool,
+    pub confidence: f32,
+}
+
+/// Line ending styles, matching the values of `MenuEventAction::LineEnding`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding
+{
+    Lf,
+    Crlf,
+    Cr,
+}
+
+/// A character the target encoding cannot represent.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct UnmappableChar
+{
+    pub character: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+/// Upper bound on reported unmappable characters, to keep IPC payloads small.
+const MAX_UNMAPPABLE_REPORTED: usize = 100;
+
+impl LineEnding
+{
+    /// Parses "LF", "CRLF" or "CR" (case-insensitive).
+    pub fn from_label(label: &str) -> Option<LineEnding>
+    {
+        match label.trim().to_uppercase().as_str()
+        {
+            "LF" => Some(LineEnding::Lf),
+            "CRLF" => Some(LineEnding::Crlf),
+            "CR" => Some(LineEnding::Cr),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str
+    {
+        match self
+        {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+impl TextEncoding
+{
+    /// Parses an encoding label. Accepts the Encoding menu values
+    /// ("utf-8", "utf-16le", "iso-8859-1", ...) and any WHATWG label.
+    pub fn from_label(label: &str) -> Option<TextEncoding>
+    {
+        parse_encoding_label(label).map(|(encoding, _)| encoding)
+    }
+
+    /// Canonical lowercase label, matching the Encoding menu IDs.
+    pub fn label(&self) -> String
+    {
+        match self
+        {
+            TextEncoding::Utf8 => "utf-8".to_string(),
+            TextEncoding::Utf16Le => "utf-16le".to_string(),
+            TextEncoding::Utf16Be => "utf-16be".to_string(),
+            TextEncoding::Latin1 => "iso-8859-1".to_string(),
+            TextEncoding::Legacy(encoding) => encoding.name().to_lowercase(),
+        }
+    }
+
+    /// Byte order mark for Unicode encodings, empty for everything else.
+    pub fn bom(&self) -> &'static [u8]
+    {
+        match self
+        {
+            TextEncoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            TextEncoding::Utf16Le => &[0xFF, 0xFE],
+            TextEncoding::Utf16Be => &[0xFE, 0xFF],
+            _ => &[],
+        }
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Lists characters in `content` that `encoding` cannot represent,
+/// so the UI can warn before a save is attempted.
+#[tauri::command]
+pub async fn check_encodable(content: String, encoding: String) -> Result<Vec<UnmappableChar>, String>
+{
+    let target = TextEncoding::from_label(&encoding)
+        .ok_or_else(|| format!("Unsupported encoding '{}'", encoding))?;
+
+    match encode_text(&content, target, false)
+    {
+        Ok(_) => Ok(Vec::new()),
+        Err(EncodeError::Unmappable(chars)) => Ok(chars),
+        Err(EncodeError::Unsupported) => Err(format!("Encoding '{}' cannot be used for saving", encoding)),
+    }
+}
+
+#[tauri::command]
+pub async fn detect_file_encoding(path: String) -> Result<EncodingInfo, String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    let bytes = fs::read(&path)
+        .map_err(|e| format!("Failed to read file '{}': {}", path, e))?;
+    let detected = detect_encoding(&bytes);
+
+    Ok(EncodingInfo {
+        encoding: detected.encoding.label(),
+        has_bom: detected.has_bom,
+        confidence: detected.confidence,
+    })
+}
+
+// ─── Detection ──────────────────────────────────────────────────
+
+/// Parses an encoding label into an encoding and a "write a BOM" flag.
+/// "utf-8-bom" is the Encoding menu's spelling of UTF-8 with signature,
+/// and the menu's item IDs ("encoding-utf-8") are accepted as they are.
+pub fn parse_encoding_label(label: &str) -> Option<(TextEncoding, bool)>
+{
+    let lowered = label.trim().to_lowercase();
+    let normalized = lowered.strip_prefix("encoding-").unwrap_or(&lowered);
+
+    if let Some(base) = normalized.strip_suffix("-bom")
+    {
+        return parse_encoding_label(base)
+            .filter(|(encoding, _)| !encoding.bom().is_empty())
+            .map(|(encoding, _)| (encoding, true));
+    }
+
+    let encoding = match normalized
+    {
+        "utf-8" | "utf8" => TextEncoding::Utf8,
+        "utf-16le" | "utf-16" | "ucs-2" => TextEncoding::Utf16Le,
+        "utf-16be" => TextEncoding::Utf16Be,
+        "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1" => TextEncoding::Latin1,
+        other => {
+            let encoding = Encoding::for_label(other.as_bytes())?;
+            from_encoding_rs(encoding)
+        }
+    };
+
+    Some((encoding, false))
+}
+
+/// Returns the encoding announced by a byte order mark, if any.
+pub fn sniff_bom(bytes: &[u8]) -> Option<TextEncoding>
+{
+    [TextEncoding::Utf8, TextEncoding::Utf16Le, TextEncoding::Utf16Be]
+        .into_iter()
+        .find(|encoding| bytes.starts_with(encoding.bom()))
+}
+
+/// Detects the encoding of a buffer:
+/// 1. A byte order mark wins outright
+/// 2. BOM-less UTF-16 is recognized by its NUL byte pattern
+/// 3. Valid UTF-8 (including pure ASCII) is taken as UTF-8. A sequence
+///    cut short by the end of the buffer still counts, since callers often
+///    pass the first few kilobytes of a file.
+/// 4. Everything else goes through chardetng's legacy heuristics
+pub fn detect_encoding(bytes: &[u8]) -> DetectedEncoding
+{
+    if let Some(encoding) = sniff_bom(bytes)
+    {
+        return DetectedEncoding { encoding, has_bom: true, confidence: 1.0 };
+    }
+
+    // NUL-interleaved ASCII is valid UTF-8, so UTF-16 must be ruled out first
+    let sample = &bytes[..bytes.len().min(SNIFF_SAMPLE_SIZE)];
+    if let Some(encoding) = detect_utf16_without_bom(sample)
+    {
+        return DetectedEncoding { encoding, has_bom: false, confidence: 0.8 };
+    }
+
+    let utf8 = match std::str::from_utf8(bytes)
+    {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if utf8
+    {
+        return DetectedEncoding { encoding: TextEncoding::Utf8, has_bom: false, confidence: 1.0 };
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(sample, sample.len() == bytes.len());
+    let (encoding, confident) = detector.guess_assess(None, false);
+
+    DetectedEncoding {
+        encoding: from_encoding_rs(encoding),
+        has_bom: false,
+        confidence: if confident { 0.75 } else { 0.4 },
+    }
+}
+
+/// BOM-less UTF-16 text that is mostly ASCII has a NUL in every other byte.
+fn detect_utf16_without_bom(sample: &[u8]) -> Option<TextEncoding>
+{
+    let pairs = sample.len() / 2;
+    if pairs < 2
+    {
+        return None;
+    }
+
+    let mut even_zeros = 0;
+    let mut odd_zeros = 0;
+    for chunk in sample.chunks_exact(2)
+    {
+        if chunk[0] == 0 { even_zeros += 1; }
+        if chunk[1] == 0 { odd_zeros += 1; }
+    }
+
+    // Require a strong, one-sided pattern so binary data is not mistaken for text
+    let threshold = pairs * 2 / 5;
+    if odd_zeros > threshold && even_zeros * 10 < pairs
+    {
+        Some(TextEncoding::Utf16Le)
+    }
+    else if even_zeros > threshold && odd_zeros * 10 < pairs
+    {
+        Some(TextEncoding::Utf16Be)
+    }
+    else
+    {
+        None
+    }
+}
+
+fn from_encoding_rs(encoding: &'static Encoding) -> TextEncoding
+{
+    if encoding == UTF_8
+    {
+        TextEncoding::Utf8
+    }
+    else if encoding == UTF_16LE
+    {
+        TextEncoding::Utf16Le
+    }
+    else if encoding == UTF_16BE
+    {
+        TextEncoding::Utf16Be
+    }
+    else
+    {
+        TextEncoding::Legacy(encoding)
+    }
+}
+
+// ─── Decoding ───────────────────────────────────────────────────
+
+/// Decodes bytes with the given encoding, stripping its BOM if present.
+/// Returns the text and whether any malformed sequences were replaced.
+pub fn decode_with(bytes: &[u8], encoding: TextEncoding) -> (String, bool)
+{
+    let body = bytes.strip_prefix(encoding.bom()).unwrap_or(bytes);
+
+    match encoding
+    {
+        TextEncoding::Latin1 => (body.iter().map(|&b| b as char).collect(), false),
+        TextEncoding::Utf8 => decode_encoding_rs(body, UTF_8),
+        TextEncoding::Utf16Le => decode_encoding_rs(body, UTF_16LE),
+        TextEncoding::Utf16Be => decode_encoding_rs(body, UTF_16BE),
+        TextEncoding::Legacy(encoding) => decode_encoding_rs(body, encoding),
+    }
+}
+
+fn decode_encoding_rs(body: &[u8], encoding: &'static Encoding) -> (String, bool)
+{
+    let (text, had_errors) = encoding.decode_without_bom_handling(body);
+    (text.into_owned(), had_errors)
+}
+
+/// Decodes a file's bytes. With no override the encoding is detected;
+/// with one (e.g. "Reopen with Encoding") it is trusted as-is.
+pub fn decode_bytes(bytes: &[u8], override_encoding: Option<TextEncoding>) -> DecodedText
+{
+    let detected = match override_encoding
+    {
+        Some(encoding) => DetectedEncoding {
+            encoding,
+            has_bom: !encoding.bom().is_empty() && bytes.starts_with(encoding.bom()),
+            confidence: 1.0,
+        },
+        None => detect_encoding(bytes),
+    };
+
+    let (content, had_errors) = decode_with(bytes, detected.encoding);
+
+    let line_ending = detect_line_ending(&content).label().to_string();
+
+    DecodedText {
+        content,
+        encoding: detected.encoding.label(),
+        has_bom: detected.has_bom,
+        confidence: if had_errors { detected.confidence.min(0.2) } else { detected.confidence },
+        had_errors,
+        line_ending,
+    }
+}
+
+// ─── Encoding ───────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+pub enum EncodeError
+{
+    /// The encoding can decode but not encode (e.g. "replacement").
+    Unsupported,
+    Unmappable(Vec<UnmappableChar>),
+}
+
+/// Encodes text for writing. Fails without producing partial output if any
+/// character cannot be represented, so nothing lossy ever reaches the disk.
+pub fn encode_text(text: &str, encoding: TextEncoding, with_bom: bool) -> Result<Vec<u8>, EncodeError>
+{
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if with_bom
+    {
+        bytes.extend_from_slice(encoding.bom());
+    }
+
+    match encoding
+    {
+        TextEncoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+        TextEncoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes())),
+        TextEncoding::Utf16Be => bytes.extend(text.encode_utf16().flat_map(|u| u.to_be_bytes())),
+        TextEncoding::Latin1 => {
+            if text.chars().any(|c| c as u32 > 0xFF)
+            {
+                return Err(EncodeError::Unmappable(find_unmappable(text, |c| c as u32 <= 0xFF)));
+            }
+            bytes.extend(text.chars().map(|c| c as u8));
+        },
+        TextEncoding::Legacy(target) => {
+            // encoding_rs only encodes to an encoding's output encoding
+            if target.output_encoding() != target
+            {
+                return Err(EncodeError::Unsupported);
+            }
+
+            let (encoded, _, had_errors) = target.encode(text);
+            if had_errors
+            {
+                return Err(EncodeError::Unmappable(find_unmappable(text, |c| {
+                    let mut buffer = [0u8; 4];
+                    !target.encode(c.encode_utf8(&mut buffer)).2
+                })));
+            }
+            bytes.extend_from_slice(&encoded);
+        },
+    }
+
+    Ok(bytes)
+}
+
+/// Collects the positions of characters rejected by `is_mappable`.
+fn find_unmappable<F>(text: &str, is_mappable: F) -> Vec<UnmappableChar>
+where
+    F: Fn(char) -> bool,
+{
+    let mut found = Vec::new();
+    for (line_index, line) in text.split('\n').enumerate()
+    {
+        for (column_index, c) in line.chars().enumerate()
+        {
+            if !c.is_ascii() && !is_mappable(c)
+            {
+                found.push(UnmappableChar {
+                    character: c.to_string(),
+                    line: line_index + 1,
+                    column: column_index + 1,
+                });
+                if found.len() == MAX_UNMAPPABLE_REPORTED
+                {
+                    return found;
+                }
+            }
+        }
+    }
+    found
+}
+
+// ─── Line Endings ───────────────────────────────────────────────
+
+/// Returns the most frequent line ending, defaulting to LF.
+pub fn detect_line_ending(text: &str) -> LineEnding
+{
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut i = 0;
+    while i < bytes.len()
+    {
+        match bytes[i]
+        {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            },
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if crlf > lf && crlf >= cr
+    {
+        LineEnding::Crlf
+    }
+    else if cr > lf && cr > crlf
+    {
+        LineEnding::Cr
+    }
+    else
+    {
+        LineEnding::Lf
+    }
+}
+
+/// Rewrites every line break (LF, CRLF or lone CR) to `ending`.
+pub fn normalize_line_endings(text: &str, ending: LineEnding) -> String
+{
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    match ending
+    {
+        LineEnding::Lf => unified,
+        _ => unified.replace('\n', ending.as_str()),
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    fn utf16le(text: &str) -> Vec<u8>
+    {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8>
+    {
+        text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+    }
+
+    // ── Labels ───────────────────────────────────────────────────
+
+    #[test]
+    fn parses_menu_encoding_labels()
+    {
+        assert_eq!(parse_encoding_label("utf-8"), Some((TextEncoding::Utf8, false)));
+        assert_eq!(parse_encoding_label("utf-8-bom"), Some((TextEncoding::Utf8, true)));
+        assert_eq!(parse_encoding_label("utf-16le"), Some((TextEncoding::Utf16Le, false)));
+        assert_eq!(parse_encoding_label("utf-16be"), Some((TextEncoding::Utf16Be, false)));
+        assert_eq!(parse_encoding_label("iso-8859-1"), Some((TextEncoding::Latin1, false)));
+        assert_eq!(
+            parse_encoding_label("windows-1252"),
+            Some((TextEncoding::Legacy(WINDOWS_1252), false))
+        );
+    }
+
+    #[test]
+    fn parses_whatwg_labels_case_insensitively()
+    {
+        assert_eq!(TextEncoding::from_label("Shift_JIS"), Some(TextEncoding::Legacy(SHIFT_JIS)));
+        assert_eq!(TextEncoding::from_label(" UTF-8 "), Some(TextEncoding::Utf8));
+    }
+
+    #[test]
+    fn accepts_encoding_menu_ids()
+    {
+        assert_eq!(parse_encoding_label("encoding-utf-8-bom"), Some((TextEncoding::Utf8, true)));
+        assert_eq!(TextEncoding::from_label("encoding-utf-16be"), Some(TextEncoding::Utf16Be));
+        assert_eq!(parse_encoding_label("encoding-klingon-8"), None);
+    }
+
+    #[test]
+    fn rejects_unknown_labels()
+    {
+        assert_eq!(parse_encoding_label("klingon-8"), None);
+        assert_eq!(parse_encoding_label("windows-1252-bom"), None);
+    }
+
+    #[test]
+    fn labels_round_trip()
+    {
+        for label in ["utf-8", "utf-16le", "utf-16be", "iso-8859-1", "windows-1252", "shift_jis"]
+        {
+            assert_eq!(TextEncoding::from_label(label).unwrap().label(), label);
+        }
+    }
+
+    // ── Detection ────────────────────────────────────────────────
+
+    #[test]
+    fn detects_utf8_bom()
+    {
+        let detected = detect_encoding(b"\xEF\xBB\xBFhello");
+        assert_eq!(detected.encoding, TextEncoding::Utf8);
+        assert!(detected.has_bom);
+        assert_eq!(detected.confidence, 1.0);
+    }
+
+    #[test]
+    fn detects_utf16_boms()
+    {
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(utf16le("hi"));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(utf16be("hi"));
+
+        assert_eq!(detect_encoding(&le).encoding, TextEncoding::Utf16Le);
+        assert_eq!(detect_encoding(&be).encoding, TextEncoding::Utf16Be);
+        assert!(detect_encoding(&be).has_bom);
+    }
+
+    #[test]
+    fn detects_plain_utf8_and_empty_input()
+    {
+        assert_eq!(detect_encoding("日本語".as_bytes()).encoding, TextEncoding::Utf8);
+        assert_eq!(detect_encoding(b"").encoding, TextEncoding::Utf8);
+        assert!(!detect_encoding(b"ascii").has_bom);
+    }
+
+    #[test]
+    fn detects_utf8_cut_off_mid_character()
+    {
+        let text = "caf\u{e9}".repeat(2000);
+        let sample = &text.as_bytes()[..text.len() - 1];
+
+        let detected = detect_encoding(sample);
+        assert_eq!(detected.encoding, TextEncoding::Utf8);
+        assert_eq!(detected.confidence, 1.0);
+    }
+
+    #[test]
+    fn detects_utf16_without_bom()
+    {
+        let le = utf16le("Plain ASCII text stored as UTF-16");
+        let be = utf16be("Plain ASCII text stored as UTF-16");
+
+        assert_eq!(detect_encoding(&le).encoding, TextEncoding::Utf16Le);
+        assert_eq!(detect_encoding(&be).encoding, TextEncoding::Utf16Be);
+    }
+
+    #[test]
+    fn detects_windows_1252_fallback()
+    {
+        let (bytes, _, _) = WINDOWS_1252.encode("Café au lait, naïve façade — «déjà vu»");
+        let detected = detect_encoding(&bytes);
+
+        assert_eq!(detected.encoding, TextEncoding::Legacy(WINDOWS_1252));
+        assert!(detected.confidence < 1.0);
+    }
+
+    // ── Decoding ─────────────────────────────────────────────────
+
+    #[test]
+    fn decode_strips_bom()
+    {
+        let decoded = decode_bytes(b"\xEF\xBB\xBFhello", None);
+        assert_eq!(decoded.content, "hello");
+        assert_eq!(decoded.encoding, "utf-8");
+        assert!(decoded.has_bom);
+    }
+
+    #[test]
+    fn decode_utf16le_with_bom()
+    {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("héllo 🚀"));
+
+        let decoded = decode_bytes(&bytes, None);
+        assert_eq!(decoded.content, "héllo 🚀");
+        assert_eq!(decoded.encoding, "utf-16le");
+        assert!(!decoded.had_errors);
+    }
+
+    #[test]
+    fn decode_latin1_keeps_c1_controls()
+    {
+        let (text, had_errors) = decode_with(&[0x41, 0x80, 0xE9], TextEncoding::Latin1);
+        assert_eq!(text, "A\u{80}é");
+        assert!(!had_errors);
+    }
+
+    #[test]
+    fn decode_override_is_trusted()
+    {
+        let decoded = decode_bytes(&[0x80], Some(TextEncoding::Legacy(WINDOWS_1252)));
+        assert_eq!(decoded.content, "€");
+        assert_eq!(decoded.encoding, "windows-1252");
+        assert_eq!(decoded.confidence, 1.0);
+    }
+
+    #[test]
+    fn decode_reports_malformed_input()
+    {
+        let decoded = decode_bytes(&[0xC3], Some(TextEncoding::Utf8));
+        assert!(decoded.had_errors);
+        assert_eq!(decoded.content, "\u{FFFD}");
+        assert!(decoded.confidence <= 0.2);
+    }
+
+    // ── Serialization ────────────────────────────────────────────
+
+    #[test]
+    fn decoded_text_serializes_camel_case()
+    {
+        let json = serde_json::to_value(decode_bytes(b"x", None)).unwrap();
+        assert_eq!(json["content"], "x");
+        assert_eq!(json["encoding"], "utf-8");
+        assert_eq!(json["hasBom"], false);
+        assert_eq!(json["hadErrors"], false);
+        assert_eq!(json["lineEnding"], "LF");
+    }
+
+    // ── Encoding ─────────────────────────────────────────────────
+
+    #[test]
+    fn encode_utf16_with_bom_round_trips()
+    {
+        for encoding in [TextEncoding::Utf16Le, TextEncoding::Utf16Be]
+        {
+            let bytes = encode_text("héllo 🚀", encoding, true).unwrap();
+            assert!(bytes.starts_with(encoding.bom()));
+            assert_eq!(decode_bytes(&bytes, None).content, "héllo 🚀");
+        }
+    }
+
+    #[test]
+    fn encode_utf8_bom_only_when_requested()
+    {
+        assert_eq!(encode_text("a", TextEncoding::Utf8, false).unwrap(), b"a");
+        assert_eq!(encode_text("a", TextEncoding::Utf8, true).unwrap(), b"\xEF\xBB\xBFa");
+    }
+
+    #[test]
+    fn encode_legacy_never_writes_bom()
+    {
+        let bytes = encode_text("€", TextEncoding::Legacy(WINDOWS_1252), true).unwrap();
+        assert_eq!(bytes, vec![0x80]);
+    }
+
+    #[test]
+    fn encode_latin1_maps_code_points_directly()
+    {
+        assert_eq!(encode_text("é\u{80}", TextEncoding::Latin1, false).unwrap(), vec![0xE9, 0x80]);
+    }
+
+    #[test]
+    fn encode_reports_unmappable_positions()
+    {
+        let result = encode_text("ok\nab日c€", TextEncoding::Latin1, false);
+        assert_eq!(
+            result,
+            Err(EncodeError::Unmappable(vec![
+                UnmappableChar { character: "日".into(), line: 2, column: 3 },
+                UnmappableChar { character: "€".into(), line: 2, column: 5 },
+            ]))
+        );
+    }
+
+    #[test]
+    fn encode_reports_unmappable_for_legacy_encodings()
+    {
+        let result = encode_text("naïve 🚀", TextEncoding::Legacy(WINDOWS_1252), false);
+        match result
+        {
+            Err(EncodeError::Unmappable(chars)) => {
+                assert_eq!(chars.len(), 1);
+                assert_eq!(chars[0].character, "🚀");
+                assert_eq!(chars[0].column, 7);
+            },
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encode_rejects_decode_only_encodings()
+    {
+        let replacement = Encoding::for_label(b"iso-2022-kr").unwrap();
+        assert_eq!(
+            encode_text("x", TextEncoding::Legacy(replacement), false),
+            Err(EncodeError::Unsupported)
+        );
+    }
+
+    // ── Line endings ─────────────────────────────────────────────
+
+    #[test]
+    fn line_ending_labels_match_menu_values()
+    {
+        assert_eq!(LineEnding::from_label("LF"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::from_label("crlf"), Some(LineEnding::Crlf));
+        assert_eq!(LineEnding::from_label("CR"), Some(LineEnding::Cr));
+        assert_eq!(LineEnding::from_label("NEL"), None);
+    }
+
+    #[test]
+    fn detects_dominant_line_ending()
+    {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("a\rb\rc"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("a\nb"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("single line"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn normalizes_mixed_line_endings()
+    {
+        let mixed = "a\r\nb\rc\nd";
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Lf), "a\nb\nc\nd");
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Crlf), "a\r\nb\r\nc\r\nd");
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Cr), "a\rb\rc\rd");
+    }
+
+    // ── check_encodable ──────────────────────────────────────────
+
+    #[tokio::test]
+    async fn check_encodable_lists_unmappable_characters()
+    {
+        let chars = check_encodable("a日b".into(), "windows-1252".into()).await.unwrap();
+        assert_eq!(chars.len(), 1);
+        assert_eq!(chars[0].character, "日");
+
+        assert!(check_encodable("abc".into(), "windows-1252".into()).await.unwrap().is_empty());
+        assert!(check_encodable("abc".into(), "klingon".into()).await.is_err());
+    }
+
+    // ── detect_file_encoding ─────────────────────────────────────
+
+    #[tokio::test]
+    async fn detect_file_encoding_reports_bom()
+    {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("bom.txt");
+        fs::write(&path, b"\xFF\xFEh\x00").unwrap();
+
+        let info = detect_file_encoding(path.to_string_lossy().into_owned()).await.unwrap();
+        assert_eq!(info.encoding, "utf-16le");
+        assert!(info.has_bom);
+    }
+}
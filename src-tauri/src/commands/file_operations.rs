@@ -1,138 +1,450 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
+use super::access_operations::{check_access, check_entry_access};
+use super::encoding_operations::{
+    DecodedText, EncodeError, LineEnding, TextEncoding,
+    decode_bytes, encode_text, normalize_line_endings, parse_encoding_label,
+};
+use super::backup_operations::backup_before_save;
+use super::merge_operations::remember_version;
+use super::save_operations::{SaveStrategy, atomic_write};
+use super::tree_operations::{DirIdentity, IgnoreStack, TreeFilter, dir_identity, list_children};
+use super::watcher_operations::record_own_write;
+
 // ─── Types ──────────────────────────────────────────────────────
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Debug)]
 pub struct FileTreeNode
 {
     pub name: String,
     pub path: String,
     #[serde(rename = "isDirectory")]
     pub is_directory: bool,
+    #[serde(rename = "isSymlink")]
+    pub is_symlink: bool,
+    /// Raw target of a symlink, as stored in the link.
+    #[serde(rename = "linkTarget", skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    /// A symlink whose target does not exist.
+    #[serde(rename = "isBroken")]
+    pub is_broken: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<FileTreeNode>>,
 }
 
+/// Structured file error returned to the frontend instead of a bare string,
+/// so the UI can tell "not-found" from "permission-denied" from the rest.
+#[derive(Serialize, Clone, Debug)]
+pub struct FileError
+{
+    pub path: String,
+    pub kind: String,
+    pub message: String,
+}
+
+impl FileError
+{
+    pub fn new(path: &str, kind: &str, message: String) -> Self
+    {
+        FileError {
+            path: path.to_string(),
+            kind: kind.to_string(),
+            message,
+        }
+    }
+
+    /// Wraps an I/O error; `action` reads like "Failed to <action> file".
+    pub fn from_io(path: &str, action: &str, error: &io::Error) -> Self
+    {
+        let kind = match error.kind()
+        {
+            io::ErrorKind::NotFound => "not-found",
+            io::ErrorKind::PermissionDenied => "permission-denied",
+            io::ErrorKind::IsADirectory => "is-directory",
+            io::ErrorKind::AlreadyExists => "already-exists",
+            _ => "io",
+        };
+        FileError::new(path, kind, format!("Failed to {} file '{}': {}", action, path, error))
+    }
+}
+
+/// Target format for a save. Every field is optional: omitted fields keep
+/// the previous behavior (UTF-8, no BOM, line endings untouched).
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SaveOptions
+{
+    /// Encoding label, e.g. "utf-8", "utf-8-bom", "utf-16be", "windows-1252".
+    pub encoding: Option<String>,
+    /// Overrides the BOM implied by the encoding label.
+    #[serde(rename = "hasBom")]
+    pub has_bom: Option<bool>,
+    /// "LF", "CRLF" or "CR".
+    #[serde(rename = "lineEnding")]
+    pub line_ending: Option<String>,
+}
+
+impl std::fmt::Display for FileError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str(&self.message)
+    }
+}
+
 // ─── Commands ───────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<String, String>
+pub async fn read_file(path: String) -> Result<String, FileError>
 {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file '{}': {}", path, e))
+    check_access(&path)?;
+    read_text_file(&path, None).map(|decoded| decoded.content)
 }
 
+/// Reads a text file, detecting its encoding unless `encoding` is given
+/// (the Encoding menu's "encoding-*" values are accepted as labels).
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<bool, String>
+pub async fn read_file_with_encoding(
+    path: String,
+    encoding: Option<String>,
+) -> Result<DecodedText, FileError>
 {
-    // Ensure parent directory exists
-    if let Some(parent) = Path::new(&path).parent()
-    {
-        if !parent.exists()
-        {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        }
-    }
+    check_access(&path)?;
+    read_text_file(&path, encoding.as_deref())
+}
 
-    fs::write(&path, &content)
-        .map_err(|e| format!("Failed to write file '{}': {}", path, e))?;
+#[tauri::command]
+pub async fn write_file(
+    path: String,
+    content: String,
+    options: Option<SaveOptions>,
+) -> Result<bool, FileError>
+{
+    check_access(&path)?;
+    write_text_file(&path, &content, &options.unwrap_or_default())?;
     Ok(true)
 }
 
 #[tauri::command]
 pub async fn read_dir(path: String) -> Result<Vec<FileTreeNode>, String>
 {
+    check_access(&path).map_err(|e| e.message)?;
     let dir_path = PathBuf::from(&path);
-    build_file_tree(&dir_path, 0)
+    let filter = TreeFilter::current();
+    tauri::async_runtime::spawn_blocking(move || build_file_tree(&dir_path, 0, &filter))
+        .await
+        .map_err(|e| e.to_string())?
         .map_err(|e| format!("Failed to read directory '{}': {}", path, e))
 }
 
+/// Renames a file or folder within its directory. `new_name` must be a
+/// single path component; an existing target is only replaced with
+/// `overwrite`. Case-only renames work on case-insensitive volumes.
 #[tauri::command]
-pub async fn rename_file(old_path: String, new_name: String) -> Result<String, String>
+pub async fn rename_file(
+    old_path: String,
+    new_name: String,
+    overwrite: Option<bool>,
+) -> Result<String, FileError>
 {
+    validate_file_name(&new_name)
+        .map_err(|message| FileError::new(&old_path, "invalid-name", message))?;
+
     let old = PathBuf::from(&old_path);
     let parent = old.parent()
-        .ok_or_else(|| "Cannot determine parent directory".to_string())?;
+        .ok_or_else(|| FileError::new(&old_path, "invalid-name", "Cannot determine parent directory".to_string()))?;
     let new_path = parent.join(&new_name);
+    let new_display = new_path.to_string_lossy().into_owned();
+    check_entry_access(&old_path)?;
+    check_entry_access(&new_display)?;
 
-    fs::rename(&old, &new_path)
-        .map_err(|e| format!("Failed to rename file: {}", e))?;
+    fs::symlink_metadata(&old)
+        .map_err(|e| FileError::from_io(&old_path, "rename", &e))?;
 
-    Ok(new_path.to_string_lossy().into_owned())
+    if old.file_name() == Some(new_name.as_ref())
+    {
+        return Ok(new_display);
+    }
+
+    if fs::symlink_metadata(&new_path).is_ok()
+    {
+        let same_entry = is_same_entry(&old, &new_path);
+        if same_entry && differs_only_in_case(&old, &new_name)
+        {
+            // Case-insensitive volume: the "target" is the source itself
+            rename_via_temp(&old, &new_path)
+                .map_err(|e| FileError::from_io(&old_path, "rename", &e))?;
+            return Ok(new_display);
+        }
+        if !overwrite.unwrap_or(false)
+        {
+            return Err(FileError::new(&new_display, "already-exists", format!("'{}' already exists", new_display)));
+        }
+        // rename(2) does nothing when both names are hard links to one file
+        let result = if same_entry { fs::remove_file(&old) } else { fs::rename(&old, &new_path) };
+        result.map_err(|e| FileError::from_io(&old_path, "rename", &e))?;
+        return Ok(new_display);
+    }
+
+    rename_no_replace(&old, &new_path)
+        .map_err(|e| FileError::from_io(&old_path, "rename", &e))?;
+    Ok(new_display)
 }
 
 #[tauri::command]
 pub async fn file_exists(path: String) -> Result<bool, String>
 {
+    check_access(&path).map_err(|e| e.message)?;
     Ok(Path::new(&path).exists())
 }
 
 // ─── Helper ─────────────────────────────────────────────────────
 
-/// Public entry point for building file tree from other modules.
-pub fn build_file_tree_public(dir_path: &Path) -> Result<Vec<FileTreeNode>, std::io::Error>
+/// Accepts exactly one normal path component: no separators, no "." or
+/// "..", nothing that would leave the parent directory.
+fn validate_file_name(name: &str) -> Result<(), String>
 {
-    build_file_tree(dir_path, 0)
+    let mut components = Path::new(name).components();
+    let single = matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(part)), None) if part == name
+    );
+
+    if !single || name.contains('/') || name.contains('\\') || name.contains('\0')
+    {
+        return Err(format!("'{}' is not a valid file name", name));
+    }
+    Ok(())
 }
 
-/// Recursively builds a file tree, matching the Electron buildFileTree() logic:
-/// - Max depth 5
-/// - Excludes dotfiles and node_modules
-/// - Directories sorted before files, then alphabetical
-fn build_file_tree(dir_path: &Path, depth: u32) -> Result<Vec<FileTreeNode>, std::io::Error>
+/// Whether two paths name the same directory entry, e.g. "readme.md" and
+/// "README.md" on a case-insensitive volume. Links are not followed.
+#[cfg(unix)]
+fn is_same_entry(a: &Path, b: &Path) -> bool
 {
-    if depth > 5
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b))
     {
-        return Ok(Vec::new());
+        (Ok(a), Ok(b)) => (a.dev(), a.ino()) == (b.dev(), b.ino()),
+        _ => false,
     }
+}
 
-    let mut entries: Vec<FileTreeNode> = Vec::new();
-    let read_dir = fs::read_dir(dir_path)?;
+#[cfg(not(unix))]
+fn is_same_entry(a: &Path, b: &Path) -> bool
+{
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
 
-    let mut raw_entries: Vec<(String, PathBuf, bool)> = Vec::new();
-    for entry in read_dir
+/// Whether `new_name` is the current name of `old` in another case.
+/// Unicode lowercasing stands in for a full case fold.
+fn differs_only_in_case(old: &Path, new_name: &str) -> bool
+{
+    old.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name != new_name && name.to_lowercase() == new_name.to_lowercase())
+}
+
+/// Some case-insensitive filesystems ignore a rename that only changes
+/// case, so the entry takes a detour through a unique temporary name.
+fn rename_via_temp(old: &Path, new: &Path) -> io::Result<()>
+{
+    let parent = old.parent().unwrap_or(Path::new("."));
+    let temp = parent.join(format!(".notemac-rename-{}-{}", std::process::id(), now_nanos()));
+    fs::rename(old, &temp)?;
+    if let Err(e) = fs::rename(&temp, new)
     {
-        let entry = entry?;
-        let name = entry.file_name().to_string_lossy().into_owned();
+        let _ = fs::rename(&temp, old);
+        return Err(e);
+    }
+    Ok(())
+}
 
-        // Skip dotfiles and node_modules (matching Electron logic)
-        if name.starts_with('.') || name == "node_modules"
-        {
-            continue;
-        }
+fn now_nanos() -> u128
+{
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// Renames without ever replacing an existing target. Linux does this
+/// atomically; elsewhere (or on filesystems without RENAME_NOREPLACE)
+/// the target is checked first.
+#[cfg(target_os = "linux")]
+fn rename_no_replace(old: &Path, new: &Path) -> io::Result<()>
+{
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let to_c = |p: &Path| CString::new(p.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Path contains a NUL byte"));
+    let (old_c, new_c) = (to_c(old)?, to_c(new)?);
+
+    let status = unsafe {
+        libc::renameat2(libc::AT_FDCWD, old_c.as_ptr(), libc::AT_FDCWD, new_c.as_ptr(), libc::RENAME_NOREPLACE)
+    };
+    if status == 0
+    {
+        return Ok(());
+    }
+
+    let error = io::Error::last_os_error();
+    match error.raw_os_error()
+    {
+        Some(libc::EINVAL) | Some(libc::ENOSYS) => rename_checked(old, new),
+        _ => Err(error),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rename_no_replace(old: &Path, new: &Path) -> io::Result<()>
+{
+    rename_checked(old, new)
+}
 
-        let path = entry.path();
-        let is_dir = entry.file_type()?.is_dir();
-        raw_entries.push((name, path, is_dir));
+fn rename_checked(old: &Path, new: &Path) -> io::Result<()>
+{
+    if fs::symlink_metadata(new).is_ok()
+    {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("'{}' already exists", new.display())));
     }
+    fs::rename(old, new)
+}
 
-    // Sort: directories first, then alphabetical
-    raw_entries.sort_by(|a, b| {
-        if a.2 && !b.2 { return std::cmp::Ordering::Less; }
-        if !a.2 && b.2 { return std::cmp::Ordering::Greater; }
-        a.0.to_lowercase().cmp(&b.0.to_lowercase())
-    });
+/// Converts text to the on-disk bytes described by `options`. Unmappable
+/// characters are reported here, before anything touches the disk.
+pub fn encode_for_save(path: &str, content: &str, options: &SaveOptions) -> Result<Vec<u8>, FileError>
+{
+    let (encoding, label_bom) = match options.encoding.as_deref()
+    {
+        Some(label) => parse_encoding_label(label).ok_or_else(|| {
+            FileError::new(path, "unsupported-encoding", format!("Unsupported encoding '{}'", label))
+        })?,
+        None => (TextEncoding::Utf8, false),
+    };
 
-    for (name, path, is_dir) in raw_entries
+    let text = match options.line_ending.as_deref()
+    {
+        Some(label) => {
+            let ending = LineEnding::from_label(label).ok_or_else(|| {
+                FileError::new(path, "unsupported-line-ending", format!("Unsupported line ending '{}'", label))
+            })?;
+            normalize_line_endings(content, ending)
+        },
+        None => content.to_string(),
+    };
+
+    encode_text(&text, encoding, options.has_bom.unwrap_or(label_bom)).map_err(|e| match e
     {
-        let children = if is_dir
+        EncodeError::Unsupported => FileError::new(
+            path,
+            "unsupported-encoding",
+            format!("Encoding '{}' cannot be used for saving", encoding.label()),
+        ),
+        EncodeError::Unmappable(chars) => {
+            let listed: Vec<String> = chars.iter()
+                .take(5)
+                .map(|c| format!("'{}' at {}:{}", c.character, c.line, c.column))
+                .collect();
+            FileError::new(
+                path,
+                "unmappable-characters",
+                format!(
+                    "Cannot save '{}' as {}: {} character(s) cannot be encoded ({})",
+                    path,
+                    encoding.label(),
+                    chars.len(),
+                    listed.join(", ")
+                ),
+            )
+        },
+    })
+}
+
+/// Encodes, backs up and atomically writes a text file. Shared by
+/// `write_file` and the save dialog. Returns how the file was written.
+pub fn write_text_file(path: &str, content: &str, options: &SaveOptions) -> Result<SaveStrategy, FileError>
+{
+    let bytes = encode_for_save(path, content, options)?;
+
+    // Ensure parent directory exists
+    if let Some(parent) = Path::new(path).parent()
+    {
+        if !parent.exists()
         {
-            Some(build_file_tree(&path, depth + 1)?)
+            fs::create_dir_all(parent).map_err(|e| {
+                FileError::new(path, "io", format!("Failed to create directory: {}", e))
+            })?;
         }
-        else
-        {
-            None
-        };
+    }
+
+    backup_before_save(Path::new(path)).map_err(|e| {
+        FileError::new(path, "backup-failed", format!("Failed to back up '{}' before saving: {}", path, e))
+    })?;
+
+    let strategy = atomic_write(Path::new(path), &bytes)
+        .map_err(|e| FileError::from_io(path, "write", &e))?;
+
+    record_own_write(Path::new(path));
+    remember_version(Path::new(path), &bytes, content);
+    Ok(strategy)
+}
 
-        entries.push(FileTreeNode {
-            name,
-            path: path.to_string_lossy().into_owned(),
-            is_directory: is_dir,
-            children,
-        });
+/// Reads and decodes a text file. Shared by `read_file` and the open dialog.
+pub fn read_text_file(path: &str, encoding: Option<&str>) -> Result<DecodedText, FileError>
+{
+    let override_encoding = match encoding
+    {
+        Some(label) => Some(TextEncoding::from_label(label).ok_or_else(|| {
+            FileError::new(path, "unsupported-encoding", format!("Unsupported encoding '{}'", label))
+        })?),
+        None => None,
+    };
+
+    let bytes = fs::read(path)
+        .map_err(|e| FileError::from_io(path, "read", &e))?;
+
+    let decoded = decode_bytes(&bytes, override_encoding);
+    remember_version(Path::new(path), &bytes, &decoded.content);
+    Ok(decoded)
+}
+
+/// Recursively builds a file tree, to a maximum depth of 5. Entries are
+/// filtered by `filter` and the ignore files along the way; directories
+/// come before files, then alphabetical. Folders that are not descended
+/// into (unfollowed links, symlink cycles) are left without `children`.
+fn build_file_tree(dir_path: &Path, depth: u32, filter: &TreeFilter) -> Result<Vec<FileTreeNode>, std::io::Error>
+{
+    let ancestors: Vec<DirIdentity> = dir_identity(dir_path).into_iter().collect();
+    build_tree_level(dir_path, depth, filter, &IgnoreStack::for_dir(dir_path), &ancestors)
+}
+
+fn build_tree_level(
+    dir_path: &Path,
+    depth: u32,
+    filter: &TreeFilter,
+    ignores: &IgnoreStack,
+    ancestors: &[DirIdentity],
+) -> Result<Vec<FileTreeNode>, std::io::Error>
+{
+    if depth > 5
+    {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = list_children(dir_path, filter, ignores)?;
+    for node in entries.iter_mut()
+    {
+        if let Some(identity) = filter.descend_into(node, ancestors)
+        {
+            let path = PathBuf::from(&node.path);
+            let mut chain = ancestors.to_vec();
+            chain.push(identity);
+            node.children = Some(build_tree_level(&path, depth + 1, filter, &ignores.child(&path), &chain)?);
+        }
     }
 
     Ok(entries)
@@ -163,9 +475,9 @@ mod tests
     #[tokio::test]
     async fn read_file_returns_error_for_missing_file()
     {
-        let result = read_file("/nonexistent/path/file.txt".into()).await;
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Failed to read file"));
+        let err = read_file("/nonexistent/path/file.txt".into()).await.unwrap_err();
+        assert_eq!(err.kind, "not-found");
+        assert!(err.message.contains("Failed to read file"));
     }
 
     #[tokio::test]
@@ -190,6 +502,69 @@ mod tests
         assert_eq!(result.unwrap(), "");
     }
 
+    #[tokio::test]
+    async fn read_file_decodes_windows_1252()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("legacy.txt");
+        fs::write(&path, b"caf\xE9 cr\xE8me br\xFBl\xE9e").unwrap();
+
+        let result = read_file(path.to_string_lossy().into_owned()).await;
+        assert_eq!(result.unwrap(), "café crème brûlée");
+    }
+
+    #[tokio::test]
+    async fn read_file_decodes_utf16_with_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("utf16.txt");
+        fs::write(&path, b"\xFF\xFEh\x00i\x00").unwrap();
+
+        let result = read_file(path.to_string_lossy().into_owned()).await;
+        assert_eq!(result.unwrap(), "hi");
+    }
+
+    // ── read_file_with_encoding ──────────────────────────────────
+
+    #[tokio::test]
+    async fn read_file_with_encoding_reports_detected_encoding()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bom.txt");
+        fs::write(&path, b"\xEF\xBB\xBFhello").unwrap();
+
+        let decoded = read_file_with_encoding(path.to_string_lossy().into_owned(), None)
+            .await
+            .unwrap();
+        assert_eq!(decoded.content, "hello");
+        assert_eq!(decoded.encoding, "utf-8");
+        assert!(decoded.has_bom);
+    }
+
+    #[tokio::test]
+    async fn read_file_with_encoding_honors_override()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("latin.txt");
+        fs::write(&path, b"\x80").unwrap();
+
+        let decoded = read_file_with_encoding(
+            path.to_string_lossy().into_owned(),
+            Some("windows-1252".into()),
+        ).await.unwrap();
+        assert_eq!(decoded.content, "€");
+    }
+
+    #[tokio::test]
+    async fn read_file_with_encoding_returns_structured_errors()
+    {
+        let missing = read_file_with_encoding("/nonexistent/file.txt".into(), None).await;
+        assert_eq!(missing.unwrap_err().kind, "not-found");
+
+        let unsupported = read_file_with_encoding("/nonexistent/file.txt".into(), Some("klingon".into())).await;
+        assert_eq!(unsupported.unwrap_err().kind, "unsupported-encoding");
+    }
+
     // ── write_file ───────────────────────────────────────────────
 
     #[tokio::test]
@@ -201,6 +576,7 @@ mod tests
         let result = write_file(
             path.to_string_lossy().into_owned(),
             "Written by test".into(),
+            None,
         ).await;
 
         assert!(result.unwrap());
@@ -216,6 +592,7 @@ mod tests
         let result = write_file(
             path.to_string_lossy().into_owned(),
             "nested content".into(),
+            None,
         ).await;
 
         assert!(result.unwrap());
@@ -232,11 +609,100 @@ mod tests
         write_file(
             path.to_string_lossy().into_owned(),
             "replaced".into(),
+            None,
         ).await.unwrap();
 
         assert_eq!(fs::read_to_string(&path).unwrap(), "replaced");
     }
 
+    #[tokio::test]
+    async fn write_file_encodes_utf16be_with_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("utf16be.txt");
+
+        let options = SaveOptions {
+            encoding: Some("utf-16be".into()),
+            has_bom: Some(true),
+            line_ending: None,
+        };
+        write_file(path.to_string_lossy().into_owned(), "hé".into(), Some(options)).await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), vec![0xFE, 0xFF, 0x00, b'h', 0x00, 0xE9]);
+    }
+
+    #[tokio::test]
+    async fn write_file_uses_bom_from_encoding_label()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bom.txt");
+
+        let options = SaveOptions { encoding: Some("utf-8-bom".into()), ..Default::default() };
+        write_file(path.to_string_lossy().into_owned(), "x".into(), Some(options)).await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFx");
+    }
+
+    #[tokio::test]
+    async fn write_file_converts_line_endings()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("crlf.txt");
+
+        let options = SaveOptions { line_ending: Some("CRLF".into()), ..Default::default() };
+        write_file(path.to_string_lossy().into_owned(), "a\nb\r\nc".into(), Some(options)).await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"a\r\nb\r\nc");
+    }
+
+    #[tokio::test]
+    async fn write_file_round_trips_windows_1252()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("legacy.txt");
+        fs::write(&path, b"caf\xE9\r\n").unwrap();
+        let path_str = path.to_string_lossy().into_owned();
+
+        let decoded = read_file_with_encoding(path_str.clone(), None).await.unwrap();
+        let options = SaveOptions {
+            encoding: Some(decoded.encoding),
+            has_bom: Some(decoded.has_bom),
+            line_ending: Some(decoded.line_ending),
+        };
+        write_file(path_str, decoded.content, Some(options)).await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"caf\xE9\r\n");
+    }
+
+    #[tokio::test]
+    async fn write_file_rejects_unmappable_without_touching_disk()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("keep.txt");
+        fs::write(&path, "original").unwrap();
+
+        let options = SaveOptions { encoding: Some("windows-1252".into()), ..Default::default() };
+        let result = write_file(path.to_string_lossy().into_owned(), "日本".into(), Some(options)).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(error.kind, "unmappable-characters");
+        assert!(error.message.contains("2 character(s)"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+    }
+
+    #[tokio::test]
+    async fn write_file_rejects_unknown_line_ending()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("x.txt");
+
+        let options = SaveOptions { line_ending: Some("NEL".into()), ..Default::default() };
+        let result = write_file(path.to_string_lossy().into_owned(), "x".into(), Some(options)).await;
+
+        assert_eq!(result.unwrap_err().kind, "unsupported-line-ending");
+        assert!(!path.exists());
+    }
+
     // ── file_exists ──────────────────────────────────────────────
 
     #[tokio::test]
@@ -277,6 +743,7 @@ mod tests
         let result = rename_file(
             old_path.to_string_lossy().into_owned(),
             "new.txt".into(),
+            None,
         ).await;
 
         let new_path = dir.path().join("new.txt");
@@ -295,10 +762,113 @@ mod tests
         let result = rename_file(
             old_path.to_string_lossy().into_owned(),
             "new.txt".into(),
+            None,
         ).await;
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Failed to rename file"));
+        let err = result.unwrap_err();
+        assert_eq!(err.kind, "not-found");
+        assert!(err.message.contains("Failed to rename file"));
+    }
+
+    #[tokio::test]
+    async fn rename_file_rejects_names_that_leave_the_directory()
+    {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old.txt");
+        fs::write(&old_path, "content").unwrap();
+
+        for name in ["../../etc/x", "sub/new.txt", "..", ".", "", "a\\b"]
+        {
+            let err = rename_file(old_path.to_string_lossy().into_owned(), name.into(), None)
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind, "invalid-name", "accepted {:?}", name);
+        }
+        assert!(old_path.exists());
+    }
+
+    #[tokio::test]
+    async fn rename_file_refuses_to_overwrite_without_flag()
+    {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old.txt");
+        let taken = dir.path().join("taken.txt");
+        fs::write(&old_path, "new content").unwrap();
+        fs::write(&taken, "keep me").unwrap();
+
+        let err = rename_file(old_path.to_string_lossy().into_owned(), "taken.txt".into(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, "already-exists");
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "keep me");
+
+        rename_file(old_path.to_string_lossy().into_owned(), "taken.txt".into(), Some(true))
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "new content");
+        assert!(!old_path.exists());
+    }
+
+    #[tokio::test]
+    async fn rename_file_changes_only_case()
+    {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("readme.md");
+        fs::write(&old_path, "docs").unwrap();
+
+        let result = rename_file(old_path.to_string_lossy().into_owned(), "README.md".into(), None)
+            .await
+            .unwrap();
+
+        assert!(result.ends_with("README.md"));
+        let names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["README.md"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rename_file_onto_a_hard_link_is_not_a_case_change()
+    {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("draft.txt");
+        let link = dir.path().join("final.txt");
+        fs::write(&old_path, "text").unwrap();
+        fs::hard_link(&old_path, &link).unwrap();
+
+        let err = rename_file(old_path.to_string_lossy().into_owned(), "final.txt".into(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, "already-exists");
+        assert!(old_path.exists());
+
+        rename_file(old_path.to_string_lossy().into_owned(), "final.txt".into(), Some(true))
+            .await
+            .unwrap();
+        assert!(!old_path.exists());
+        assert_eq!(fs::read_to_string(&link).unwrap(), "text");
+    }
+
+    #[test]
+    fn case_only_detection_folds_non_ascii()
+    {
+        assert!(differs_only_in_case(Path::new("/x/ÉTÉ.txt"), "été.txt"));
+        assert!(!differs_only_in_case(Path::new("/x/draft.txt"), "final.txt"));
+        assert!(!differs_only_in_case(Path::new("/x/same.txt"), "same.txt"));
+    }
+
+    #[test]
+    fn rename_via_temp_renames_same_entry()
+    {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("a.txt");
+        fs::write(&old_path, "x").unwrap();
+
+        rename_via_temp(&old_path, &dir.path().join("A.txt")).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("A.txt")).unwrap(), "x");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
     }
 
     // ── read_dir / build_file_tree ───────────────────────────────
@@ -324,19 +894,33 @@ mod tests
     }
 
     #[tokio::test]
-    async fn read_dir_excludes_dotfiles_and_node_modules()
+    async fn read_dir_excludes_git_and_node_modules_by_default()
     {
         let dir = TempDir::new().unwrap();
         fs::write(dir.path().join("visible.txt"), "yes").unwrap();
-        fs::write(dir.path().join(".hidden"), "no").unwrap();
+        fs::write(dir.path().join(".hidden"), "shown").unwrap();
         fs::create_dir(dir.path().join(".git")).unwrap();
         fs::create_dir(dir.path().join("node_modules")).unwrap();
 
         let result = read_dir(dir.path().to_string_lossy().into_owned()).await;
         let entries = result.unwrap();
 
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].name, "visible.txt");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, ".hidden");
+        assert_eq!(entries[1].name, "visible.txt");
+    }
+
+    #[tokio::test]
+    async fn read_dir_honors_gitignore()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+
+        let entries = read_dir(dir.path().to_string_lossy().into_owned()).await.unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["src", ".gitignore"]);
     }
 
     #[tokio::test]
@@ -359,7 +943,7 @@ mod tests
         }
         fs::write(current.join("deep.txt"), "deep").unwrap();
 
-        let tree = build_file_tree(dir.path(), 0).unwrap();
+        let tree = build_file_tree(dir.path(), 0, &TreeFilter::default()).unwrap();
         // Traverse into the tree — at depth 5 should stop
         let mut node = &tree[0]; // "0"
         for _ in 0..5
@@ -376,6 +960,21 @@ mod tests
         assert!(children.is_empty());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn build_file_tree_stops_at_symlink_cycles()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("loop")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("loop/parent")).unwrap();
+
+        let tree = build_file_tree(dir.path(), 0, &TreeFilter::default()).unwrap();
+        let parent = &tree[0].children.as_ref().unwrap()[0];
+        assert_eq!(parent.name, "parent");
+        assert!(parent.is_symlink);
+        assert!(parent.children.is_none());
+    }
+
     #[test]
     fn build_file_tree_sorts_dirs_before_files()
     {
@@ -384,7 +983,7 @@ mod tests
         fs::create_dir(dir.path().join("alpha_dir")).unwrap();
         fs::write(dir.path().join("apple.txt"), "").unwrap();
 
-        let tree = build_file_tree(dir.path(), 0).unwrap();
+        let tree = build_file_tree(dir.path(), 0, &TreeFilter::default()).unwrap();
         assert_eq!(tree[0].name, "alpha_dir");
         assert!(tree[0].is_directory);
         assert_eq!(tree[1].name, "apple.txt");
@@ -400,7 +999,7 @@ mod tests
         fs::write(dir.path().join("apple.txt"), "").unwrap();
         fs::write(dir.path().join("Cherry.txt"), "").unwrap();
 
-        let tree = build_file_tree(dir.path(), 0).unwrap();
+        let tree = build_file_tree(dir.path(), 0, &TreeFilter::default()).unwrap();
         assert_eq!(tree[0].name, "apple.txt");
         assert_eq!(tree[1].name, "Banana.txt");
         assert_eq!(tree[2].name, "Cherry.txt");
@@ -415,6 +1014,9 @@ mod tests
             name: "test.txt".into(),
             path: "/tmp/test.txt".into(),
             is_directory: false,
+            is_symlink: false,
+            link_target: None,
+            is_broken: false,
             children: None,
         };
 
@@ -422,6 +1024,9 @@ mod tests
         assert_eq!(json["name"], "test.txt");
         assert_eq!(json["path"], "/tmp/test.txt");
         assert_eq!(json["isDirectory"], false);
+        assert_eq!(json["isSymlink"], false);
+        assert_eq!(json["isBroken"], false);
+        assert!(json.get("linkTarget").is_none());
         assert!(json.get("children").is_none()); // skip_serializing_if
     }
 
@@ -432,11 +1037,17 @@ mod tests
             name: "src".into(),
             path: "/tmp/src".into(),
             is_directory: true,
+            is_symlink: false,
+            link_target: None,
+            is_broken: false,
             children: Some(vec![
                 FileTreeNode {
                     name: "main.rs".into(),
                     path: "/tmp/src/main.rs".into(),
                     is_directory: false,
+                    is_symlink: false,
+                    link_target: None,
+                    is_broken: false,
                     children: None,
                 },
             ]),
@@ -0,0 +1,879 @@
+use encoding_rs::{DecoderResult, Encoding};
+use memchr::memchr2;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::check_access;
+use super::encoding_operations::{TextEncoding, decode_with, detect_encoding};
+
+/// Every Nth line start is stored, keeping the index a few MB even for
+/// files with tens of millions of lines.
+const CHECKPOINT_INTERVAL: u64 = 256;
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+/// Cap on lines returned by one `read_lines` call.
+const MAX_LINES_PER_READ: u64 = 10_000;
+/// Cap on bytes returned by one `read_lines` call, so a few enormous
+/// lines cannot pull hundreds of MB across the bridge.
+const MAX_LINES_LENGTH: u64 = 16 * 1024 * 1024;
+/// Cap on bytes returned by one `read_byte_range` call.
+const MAX_RANGE_LENGTH: u64 = 16 * 1024 * 1024;
+/// How far back a multi-byte legacy encoding is searched for a byte that
+/// decoding can safely resume from.
+const SYNC_WINDOW: u64 = 4096;
+
+/// Line indexes of files opened in large-file mode.
+static INDEXES: OnceLock<Mutex<HashMap<PathBuf, Arc<IndexHandle>>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LargeFileInfo
+{
+    pub path: String,
+    pub size: u64,
+    pub encoding: String,
+    #[serde(rename = "hasBom")]
+    pub has_bom: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LinesChunk
+{
+    /// 0-based number of the first returned line.
+    #[serde(rename = "startLine")]
+    pub start_line: u64,
+    /// Byte offset of the first returned line.
+    #[serde(rename = "startOffset")]
+    pub start_offset: u64,
+    /// Lines without their terminators. Fewer than asked for when the
+    /// byte cap was reached.
+    pub lines: Vec<String>,
+    /// Byte offset just past the returned text.
+    #[serde(rename = "endOffset")]
+    pub end_offset: u64,
+    /// The only line was longer than the byte cap and has been cut at a
+    /// character boundary; `read_byte_range` from `endOffset` continues it.
+    #[serde(rename = "partialLine")]
+    pub partial_line: bool,
+    /// Known once indexing has finished.
+    #[serde(rename = "totalLines")]
+    pub total_lines: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ByteRangeText
+{
+    /// Offset actually read, after aligning to a character boundary.
+    pub offset: u64,
+    pub length: u64,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct IndexProgressPayload
+{
+    path: String,
+    #[serde(rename = "bytesIndexed")]
+    bytes_indexed: u64,
+    #[serde(rename = "totalBytes")]
+    total_bytes: u64,
+    #[serde(rename = "linesIndexed")]
+    lines_indexed: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct IndexCompletePayload
+{
+    path: String,
+    #[serde(rename = "totalLines")]
+    total_lines: u64,
+}
+
+/// Sparse line index: `checkpoints[i]` is the byte offset of line
+/// `i * CHECKPOINT_INTERVAL`.
+#[derive(Debug, Default, Clone)]
+pub struct LineIndex
+{
+    pub checkpoints: Vec<u64>,
+    /// Line breaks seen so far; the line count is this plus one.
+    pub breaks: u64,
+    pub indexed_bytes: u64,
+    pub complete: bool,
+}
+
+pub struct IndexHandle
+{
+    pub path: PathBuf,
+    pub encoding: TextEncoding,
+    pub bom_len: u64,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub index: RwLock<LineIndex>,
+    pub cancel: AtomicBool,
+}
+
+/// Finds line starts in a stream of chunks. LF, CRLF and lone CR all end
+/// a line; a CR at the end of one chunk is resolved by the next.
+struct LineScanner
+{
+    unit: usize,
+    big_endian: bool,
+    pending_cr: bool,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Opens a file in large-file mode and starts indexing it in the
+/// background. Progress arrives as `large-file-index-progress` events.
+#[tauri::command]
+pub async fn open_large_file(app: AppHandle, path: String) -> Result<LargeFileInfo, String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    let handle = Arc::new(IndexHandle::open(Path::new(&path))
+        .map_err(|e| format!("Failed to open '{}': {}", path, e))?);
+
+    if let Some(previous) = indexes().lock()
+        .map_err(|_| "Index registry lock poisoned".to_string())?
+        .insert(PathBuf::from(&path), handle.clone())
+    {
+        previous.cancel.store(true, Ordering::SeqCst);
+    }
+
+    let info = LargeFileInfo {
+        path: path.clone(),
+        size: handle.size,
+        encoding: handle.encoding.label(),
+        has_bom: handle.bom_len > 0,
+    };
+
+    std::thread::spawn(move || {
+        let mut last_progress = Instant::now();
+        let result = handle.build(|index| {
+            if last_progress.elapsed() >= PROGRESS_INTERVAL
+            {
+                last_progress = Instant::now();
+                let _ = app.emit("large-file-index-progress", IndexProgressPayload {
+                    path: path.clone(),
+                    bytes_indexed: index.indexed_bytes,
+                    total_bytes: handle.size,
+                    lines_indexed: index.breaks + 1,
+                });
+            }
+        });
+
+        match result
+        {
+            Ok(true) => {
+                let total_lines = handle.index.read().map(|i| i.breaks + 1).unwrap_or(0);
+                let _ = app.emit("large-file-index-complete", IndexCompletePayload { path, total_lines });
+            },
+            Ok(false) => {},
+            Err(e) => eprintln!("Failed to index '{}': {}", path, e),
+        }
+    });
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn close_large_file(path: String) -> Result<(), String>
+{
+    if let Some(handle) = indexes().lock()
+        .map_err(|_| "Index registry lock poisoned".to_string())?
+        .remove(Path::new(&path))
+    {
+        handle.cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Reads `count` lines starting at 0-based line `start`. Used for paging
+/// and for Go to Line, which only needs the checkpoint before line N.
+#[tauri::command]
+pub async fn read_lines(path: String, start: u64, count: u64) -> Result<LinesChunk, String>
+{
+    let handle = lookup(&path)?;
+    handle.read_lines(start, count.min(MAX_LINES_PER_READ), MAX_LINES_LENGTH)
+        .map_err(|e| format!("Failed to read lines from '{}': {}", path, e))
+}
+
+/// Reads and decodes a byte range, aligned to character boundaries.
+#[tauri::command]
+pub async fn read_byte_range(path: String, offset: u64, length: u64) -> Result<ByteRangeText, String>
+{
+    let handle = match lookup(&path)
+    {
+        Ok(handle) => handle,
+        Err(_) => {
+            check_access(&path).map_err(|e| e.message)?;
+            Arc::new(IndexHandle::open(Path::new(&path))
+                .map_err(|e| format!("Failed to open '{}': {}", path, e))?)
+        },
+    };
+    handle.read_byte_range(offset, length.min(MAX_RANGE_LENGTH))
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))
+}
+
+// ─── Indexing ───────────────────────────────────────────────────
+
+impl IndexHandle
+{
+    pub fn open(path: &Path) -> io::Result<Self>
+    {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if metadata.is_dir()
+        {
+            return Err(io::Error::other("Cannot open a directory as a file"));
+        }
+
+        let mut head = Vec::new();
+        (&mut file).take(64 * 1024).read_to_end(&mut head)?;
+        let detected = detect_encoding(&head);
+        let bom_len = if detected.has_bom { detected.encoding.bom().len() as u64 } else { 0 };
+
+        Ok(IndexHandle {
+            path: path.to_path_buf(),
+            encoding: detected.encoding,
+            bom_len,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            index: RwLock::new(LineIndex {
+                checkpoints: vec![bom_len],
+                ..Default::default()
+            }),
+            cancel: AtomicBool::new(false),
+        })
+    }
+
+    /// Scans the whole file once. Returns `Ok(false)` if cancelled.
+    pub fn build<F>(&self, mut on_progress: F) -> io::Result<bool>
+    where
+        F: FnMut(&LineIndex),
+    {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.bom_len))?;
+
+        let mut scanner = LineScanner::new(self.encoding);
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        let mut offset = self.bom_len;
+
+        loop
+        {
+            if self.cancel.load(Ordering::SeqCst)
+            {
+                return Ok(false);
+            }
+
+            let read = read_full(&mut file, &mut buffer)?;
+            let mut starts = Vec::new();
+            if read == 0
+            {
+                scanner.finish(offset, |start| starts.push(start));
+            }
+            else
+            {
+                scanner.feed(&buffer[..read], offset, |start| starts.push(start));
+                offset += read as u64;
+            }
+
+            let mut index = self.index.write().map_err(|_| io::Error::other("Index lock poisoned"))?;
+            for start in starts
+            {
+                index.breaks += 1;
+                if index.breaks % CHECKPOINT_INTERVAL == 0
+                {
+                    index.checkpoints.push(start);
+                }
+            }
+            index.indexed_bytes = offset;
+            if read == 0
+            {
+                index.complete = true;
+            }
+            on_progress(&index);
+
+            if read == 0
+            {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Reads whole lines until `count` lines or `max_bytes` bytes. A
+    /// first line longer than `max_bytes` comes back cut short.
+    pub fn read_lines(&self, start: u64, count: u64, max_bytes: u64) -> io::Result<LinesChunk>
+    {
+        self.ensure_unchanged()?;
+
+        let (checkpoint_offset, checkpoint_line, total_lines, indexed_lines) = {
+            let index = self.index.read().map_err(|_| io::Error::other("Index lock poisoned"))?;
+            let slot = ((start / CHECKPOINT_INTERVAL) as usize).min(index.checkpoints.len() - 1);
+            (
+                index.checkpoints[slot],
+                slot as u64 * CHECKPOINT_INTERVAL,
+                if index.complete { Some(index.breaks + 1) } else { None },
+                index.breaks + 1,
+            )
+        };
+
+        if total_lines.is_none() && start >= indexed_lines
+        {
+            return Err(io::Error::other(format!("Line {} has not been indexed yet", start + 1)));
+        }
+        let empty = |offset| LinesChunk {
+            start_line: start,
+            start_offset: offset,
+            lines: Vec::new(),
+            end_offset: offset,
+            partial_line: false,
+            total_lines,
+        };
+        if total_lines.is_some_and(|total| start >= total)
+        {
+            return Ok(empty(self.size));
+        }
+
+        let mut file = File::open(&self.path)?;
+        let Some(first_offset) = self.skip_lines(&mut file, checkpoint_offset, start - checkpoint_line)?
+        else
+        {
+            return Ok(empty(self.size));
+        };
+
+        // Collect line boundaries from the first wanted line on
+        // Text appended since indexing is left for the next reopen
+        file.seek(SeekFrom::Start(first_offset))?;
+        let mut file = Read::take(file, self.size - first_offset);
+        let mut scanner = LineScanner::new(self.encoding);
+        let mut bounds = vec![first_offset];
+        let mut data = Vec::new();
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        let mut offset = first_offset;
+
+        while (bounds.len() as u64) <= count && (data.len() as u64) < max_bytes
+        {
+            let read = read_full(&mut file, &mut buffer)?;
+            if read == 0
+            {
+                scanner.finish(offset, |s| bounds.push(s));
+                // End of the last line, which is empty after a trailing break
+                bounds.push(offset);
+                break;
+            }
+            scanner.feed(&buffer[..read], offset, |s| bounds.push(s));
+            data.extend_from_slice(&buffer[..read]);
+            offset += read as u64;
+        }
+
+        let within_cap = |end: u64| end - first_offset <= max_bytes;
+        let mut lines = Vec::new();
+        let mut end_offset = first_offset;
+        for pair in bounds.windows(2).take(count as usize).take_while(|pair| within_cap(pair[1]))
+        {
+            let from = (pair[0] - first_offset) as usize;
+            let to = (pair[1] - first_offset) as usize;
+            let (text, _) = decode_with(&data[from..to], self.encoding);
+            lines.push(trim_line_break(text));
+            end_offset = pair[1];
+        }
+
+        let partial_line = lines.is_empty() && count > 0 && bounds.get(1).is_none_or(|&end| !within_cap(end));
+        if partial_line
+        {
+            let cut = complete_prefix(&data[..data.len().min(max_bytes as usize)], self.encoding);
+            let (text, _) = decode_with(&data[..cut], self.encoding);
+            lines.push(text);
+            end_offset = first_offset + cut as u64;
+        }
+
+        Ok(LinesChunk { start_line: start, start_offset: first_offset, lines, end_offset, partial_line, total_lines })
+    }
+
+    /// Walks `skip` lines forward from a checkpoint without keeping the
+    /// bytes. `None` when the file ends first.
+    fn skip_lines(&self, file: &mut File, from: u64, skip: u64) -> io::Result<Option<u64>>
+    {
+        if skip == 0
+        {
+            return Ok(Some(from));
+        }
+
+        file.seek(SeekFrom::Start(from))?;
+        let mut file = Read::take(file, self.size - from);
+        let mut scanner = LineScanner::new(self.encoding);
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        let mut offset = from;
+        let mut seen = 0;
+        let mut found = None;
+
+        while found.is_none()
+        {
+            let read = read_full(&mut file, &mut buffer)?;
+            let mut on_start = |start| {
+                seen += 1;
+                if seen == skip { found = Some(start); }
+            };
+            if read == 0
+            {
+                scanner.finish(offset, &mut on_start);
+                break;
+            }
+            scanner.feed(&buffer[..read], offset, &mut on_start);
+            offset += read as u64;
+        }
+        Ok(found)
+    }
+
+    pub fn read_byte_range(&self, offset: u64, length: u64) -> io::Result<ByteRangeText>
+    {
+        self.ensure_unchanged()?;
+
+        let mut file = File::open(&self.path)?;
+        let size = file.metadata()?.len();
+        let unit = if matches!(self.encoding, TextEncoding::Utf16Le | TextEncoding::Utf16Be) { 2 } else { 1 };
+
+        // Never split the BOM or a UTF-16 code unit
+        let start = offset.max(self.bom_len).min(size);
+        let start = start - (start - self.bom_len) % unit;
+        let end = (start + length).min(size);
+        let end = end - (end - start) % unit;
+
+        // Multi-byte legacy encodings need some context to find where a character starts
+        let lead = if multi_byte_legacy(self.encoding).is_some() { (start - self.bom_len).min(SYNC_WINDOW) } else { 0 };
+        file.seek(SeekFrom::Start(start - lead))?;
+        let mut bytes = vec![0u8; (end - start + lead) as usize];
+        file.read_exact(&mut bytes)?;
+
+        let mut from = lead as usize;
+        match self.encoding
+        {
+            // Drop continuation bytes at the start
+            TextEncoding::Utf8 => from = bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count(),
+            TextEncoding::Legacy(encoding) if !encoding.is_single_byte() => {
+                from = legacy_boundary(&bytes, from, encoding, true);
+            },
+            _ => {},
+        }
+        // And a partial character at the end
+        let to = from + complete_prefix(&bytes[from..], self.encoding);
+
+        let (text, _) = decode_with(&bytes[from..to], self.encoding);
+        Ok(ByteRangeText {
+            offset: start - lead + from as u64,
+            length: (to - from) as u64,
+            text,
+        })
+    }
+
+    /// Fails when the indexed bytes may have changed. A file that only
+    /// grew, like a log being appended to, keeps its index.
+    fn ensure_unchanged(&self) -> io::Result<()>
+    {
+        let metadata = fs::metadata(&self.path)?;
+        let rewritten = metadata.len() == self.size && metadata.modified().ok() != self.modified;
+        if metadata.len() < self.size || rewritten
+        {
+            return Err(io::Error::other("File changed on disk since it was indexed; reopen it"));
+        }
+        Ok(())
+    }
+}
+
+impl LineScanner
+{
+    fn new(encoding: TextEncoding) -> Self
+    {
+        LineScanner {
+            unit: if matches!(encoding, TextEncoding::Utf16Le | TextEncoding::Utf16Be) { 2 } else { 1 },
+            big_endian: encoding == TextEncoding::Utf16Be,
+            pending_cr: false,
+        }
+    }
+
+    /// Calls `on_line_start` with the absolute offset of each new line.
+    fn feed<F>(&mut self, chunk: &[u8], base: u64, mut on_line_start: F)
+    where
+        F: FnMut(u64),
+    {
+        if self.unit == 1
+        {
+            let mut i = 0;
+            if self.pending_cr
+            {
+                self.pending_cr = false;
+                if chunk.first() == Some(&b'\n') { i = 1; }
+                on_line_start(base + i as u64);
+            }
+            while let Some(found) = memchr2(b'\n', b'\r', &chunk[i..])
+            {
+                let p = i + found;
+                if chunk[p] == b'\r' && p + 1 == chunk.len()
+                {
+                    self.pending_cr = true;
+                    return;
+                }
+                let after = if chunk[p] == b'\r' && chunk[p + 1] == b'\n' { p + 2 } else { p + 1 };
+                on_line_start(base + after as u64);
+                i = after;
+            }
+        }
+        else
+        {
+            let unit_at = |i: usize| -> u16 {
+                let pair = [chunk[i], chunk[i + 1]];
+                if self.big_endian { u16::from_be_bytes(pair) } else { u16::from_le_bytes(pair) }
+            };
+            let units = chunk.len() / 2;
+            let mut i = 0;
+            if self.pending_cr
+            {
+                self.pending_cr = false;
+                if units > 0 && unit_at(0) == 0x0A { i = 1; }
+                on_line_start(base + (i * 2) as u64);
+            }
+            while i < units
+            {
+                match unit_at(i * 2)
+                {
+                    0x0A => on_line_start(base + ((i + 1) * 2) as u64),
+                    0x0D if i + 1 == units => self.pending_cr = true,
+                    0x0D if unit_at((i + 1) * 2) == 0x0A => {
+                        i += 1;
+                        on_line_start(base + ((i + 1) * 2) as u64);
+                    },
+                    0x0D => on_line_start(base + ((i + 1) * 2) as u64),
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+    }
+
+    fn finish<F>(&mut self, end: u64, mut on_line_start: F)
+    where
+        F: FnMut(u64),
+    {
+        if self.pending_cr
+        {
+            self.pending_cr = false;
+            on_line_start(end);
+        }
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn indexes() -> &'static Mutex<HashMap<PathBuf, Arc<IndexHandle>>>
+{
+    INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lookup(path: &str) -> Result<Arc<IndexHandle>, String>
+{
+    indexes().lock()
+        .map_err(|_| "Index registry lock poisoned".to_string())?
+        .get(Path::new(path))
+        .cloned()
+        .ok_or_else(|| format!("'{}' is not open in large-file mode", path))
+}
+
+/// Fills as much of `buffer` as possible; short only at end of file.
+/// Keeps chunk sizes even so UTF-16 code units are never split.
+fn read_full(file: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize>
+{
+    let mut filled = 0;
+    while filled < buffer.len()
+    {
+        match file.read(&mut buffer[filled..])?
+        {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// How much of `bytes`, which starts on a character boundary, holds
+/// whole characters.
+fn complete_prefix(bytes: &[u8], encoding: TextEncoding) -> usize
+{
+    match encoding
+    {
+        TextEncoding::Utf8 => match std::str::from_utf8(bytes)
+        {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => bytes.len(),
+        },
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => bytes.len() & !1,
+        TextEncoding::Legacy(encoding) if !encoding.is_single_byte() => legacy_boundary(bytes, bytes.len(), encoding, false),
+        _ => bytes.len(),
+    }
+}
+
+/// Shift_JIS, GBK, Big5, EUC and the like, where a byte on its own does
+/// not say whether it starts a character.
+fn multi_byte_legacy(encoding: TextEncoding) -> Option<&'static Encoding>
+{
+    match encoding
+    {
+        TextEncoding::Legacy(encoding) if !encoding.is_single_byte() => Some(encoding),
+        _ => None,
+    }
+}
+
+/// The character boundary nearest `target` in `bytes`: the first at or
+/// after it when `forward`, otherwise the last at or before it. Decoding
+/// resumes after the last byte below 0x30 before `target`, since such a
+/// byte is never part of a multi-byte character in these encodings.
+fn legacy_boundary(bytes: &[u8], target: usize, encoding: &'static Encoding, forward: bool) -> usize
+{
+    let sync = bytes[..target].iter().rposition(|&b| b < 0x30).map_or(0, |p| p + 1);
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut out = [0u16; 8];
+    let mut boundary = sync;
+
+    for at in sync..bytes.len()
+    {
+        if boundary >= target
+        {
+            break;
+        }
+        let (result, _, written) = decoder.decode_to_utf16_without_replacement(&bytes[at..at + 1], &mut out, false);
+        if written > 0 || matches!(result, DecoderResult::Malformed(..))
+        {
+            if !forward && at + 1 > target
+            {
+                break;
+            }
+            boundary = at + 1;
+        }
+    }
+
+    if forward { boundary.max(target).min(bytes.len()) } else { boundary.min(target) }
+}
+
+fn trim_line_break(mut line: String) -> String
+{
+    if line.ends_with('\n') { line.pop(); }
+    if line.ends_with('\r') { line.pop(); }
+    line
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn indexed(dir: &TempDir, name: &str, bytes: &[u8]) -> IndexHandle
+    {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        let handle = IndexHandle::open(&path).unwrap();
+        assert!(handle.build(|_| {}).unwrap());
+        handle
+    }
+
+    fn numbered_lines(count: usize, ending: &str) -> String
+    {
+        (0..count).map(|i| format!("line {}{}", i, ending)).collect()
+    }
+
+    // ── Indexing ────────────────────────────────────────────────
+
+    #[test]
+    fn index_counts_lines_and_stores_checkpoints()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "big.txt", numbered_lines(1000, "\n").as_bytes());
+
+        let index = handle.index.read().unwrap();
+        assert!(index.complete);
+        assert_eq!(index.breaks + 1, 1001);
+        assert_eq!(index.checkpoints.len(), 4);
+        assert_eq!(index.indexed_bytes, handle.size);
+    }
+
+    #[test]
+    fn index_handles_crlf_and_lone_cr_across_chunks()
+    {
+        let mut scanner = LineScanner::new(TextEncoding::Utf8);
+        let mut starts = Vec::new();
+        scanner.feed(b"a\r", 0, |s| starts.push(s));
+        scanner.feed(b"\nb\rc", 2, |s| starts.push(s));
+        scanner.feed(b"\r", 6, |s| starts.push(s));
+        scanner.finish(7, |s| starts.push(s));
+        assert_eq!(starts, vec![3, 5, 7]);
+    }
+
+    #[test]
+    fn cancelled_index_stops_early()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("f.txt");
+        fs::write(&path, "a\nb\n").unwrap();
+        let handle = IndexHandle::open(&path).unwrap();
+        handle.cancel.store(true, Ordering::SeqCst);
+        assert!(!handle.build(|_| {}).unwrap());
+    }
+
+    // ── read_lines ──────────────────────────────────────────────
+
+    #[test]
+    fn read_lines_jumps_past_checkpoints()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "big.txt", numbered_lines(1000, "\r\n").as_bytes());
+
+        let chunk = handle.read_lines(700, 3, MAX_LINES_LENGTH).unwrap();
+        assert_eq!(chunk.lines, vec!["line 700", "line 701", "line 702"]);
+        assert_eq!(chunk.total_lines, Some(1001));
+        assert_eq!(chunk.start_offset, numbered_lines(700, "\r\n").len() as u64);
+    }
+
+    #[test]
+    fn read_lines_includes_trailing_empty_line_and_stops_at_end()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "short.txt", b"a\nb\n");
+
+        assert_eq!(handle.read_lines(0, 10, MAX_LINES_LENGTH).unwrap().lines, vec!["a", "b", ""]);
+        assert!(handle.read_lines(5, 10, MAX_LINES_LENGTH).unwrap().lines.is_empty());
+    }
+
+    #[test]
+    fn read_lines_decodes_utf16_with_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "één\ntwee\r\ndrie".encode_utf16()
+        {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let handle = indexed(&dir, "utf16.txt", &bytes);
+
+        assert_eq!(handle.read_lines(0, 3, MAX_LINES_LENGTH).unwrap().lines, vec!["één", "twee", "drie"]);
+        assert_eq!(handle.read_lines(1, 1, MAX_LINES_LENGTH).unwrap().start_offset, 10);
+    }
+
+    #[test]
+    fn read_lines_stops_at_the_byte_cap()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "lines.txt", b"aaaa\nbbbb\ncccc\n");
+
+        let chunk = handle.read_lines(0, 3, 11).unwrap();
+        assert_eq!(chunk.lines, vec!["aaaa", "bbbb"]);
+        assert_eq!(chunk.end_offset, 10);
+        assert!(!chunk.partial_line);
+
+        let next = handle.read_lines(2, 3, 11).unwrap();
+        assert_eq!(next.start_offset, 10);
+        assert_eq!(next.lines, vec!["cccc", ""]);
+    }
+
+    #[test]
+    fn read_lines_cuts_an_overlong_line_at_a_character()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "long.txt", "ab€€€\nshort".as_bytes());
+
+        // The cap falls inside the second '€'
+        let chunk = handle.read_lines(0, 2, 6).unwrap();
+        assert!(chunk.partial_line);
+        assert_eq!(chunk.lines, vec!["ab€"]);
+        assert_eq!(chunk.end_offset, 5);
+        assert_eq!(handle.read_byte_range(chunk.end_offset, 6).unwrap().text, "€€");
+    }
+
+    #[test]
+    fn read_lines_rejects_file_changed_since_indexing()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "f.txt", b"one\ntwo");
+        fs::write(&handle.path, "one").unwrap();
+        assert!(handle.read_lines(0, 1, MAX_LINES_LENGTH).is_err());
+    }
+
+    #[test]
+    fn read_lines_keeps_serving_a_file_that_only_grew()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "f.log", b"one\ntwo");
+        fs::write(&handle.path, "one\ntwo\nthree").unwrap();
+
+        let chunk = handle.read_lines(0, 10, MAX_LINES_LENGTH).unwrap();
+        assert_eq!(chunk.lines, vec!["one", "two"]);
+        assert_eq!(chunk.total_lines, Some(2));
+    }
+
+    // ── read_byte_range ─────────────────────────────────────────
+
+    #[test]
+    fn read_byte_range_aligns_to_utf8_boundaries()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("utf8.txt");
+        fs::write(&path, "aé€b").unwrap();
+        let handle = IndexHandle::open(&path).unwrap();
+
+        // Offset 2 is inside 'é', length cuts '€' in half
+        let range = handle.read_byte_range(2, 4).unwrap();
+        assert_eq!(range.offset, 3);
+        assert_eq!(range.text, "€");
+
+        let all = handle.read_byte_range(0, 100).unwrap();
+        assert_eq!(all.text, "aé€b");
+    }
+
+    #[test]
+    fn read_byte_range_aligns_to_shift_jis_characters()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sjis.txt");
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("日本語のテキストです。日本語のテキストです。");
+        fs::write(&path, &bytes).unwrap();
+        let handle = IndexHandle::open(&path).unwrap();
+        assert_eq!(handle.encoding, TextEncoding::Legacy(encoding_rs::SHIFT_JIS));
+
+        // Offset 3 is the second byte of '本', length ends inside '語'
+        let range = handle.read_byte_range(3, 4).unwrap();
+        assert_eq!(range.offset, 4);
+        assert_eq!(range.length, 2);
+        assert_eq!(range.text, "語");
+    }
+
+    #[test]
+    fn read_byte_range_rejects_file_shrunk_below_its_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let handle = indexed(&dir, "bom.txt", b"\xEF\xBB\xBFhello");
+        fs::write(&handle.path, b"\xEF").unwrap();
+        assert!(handle.read_byte_range(0, 10).is_err());
+    }
+
+    #[test]
+    fn read_byte_range_skips_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bom.txt");
+        fs::write(&path, b"\xEF\xBB\xBFhello").unwrap();
+        let handle = IndexHandle::open(&path).unwrap();
+
+        let range = handle.read_byte_range(0, 3).unwrap();
+        assert_eq!(range.offset, 3);
+        assert_eq!(range.text, "hel");
+        assert_eq!(handle.read_byte_range(3, 5).unwrap().text, "hello");
+    }
+}
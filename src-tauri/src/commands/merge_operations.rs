@@ -0,0 +1,548 @@
+use serde::Serialize;
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use super::access_operations::check_access;
+use super::encoding_operations::{LineEnding, decode_bytes, detect_line_ending, normalize_line_endings, parse_encoding_label};
+use super::file_operations::{FileError, SaveOptions, write_text_file};
+use super::save_operations::{SaveStrategy, content_hash};
+
+/// Larger files are tracked by hash only; if one changes on disk under
+/// unsaved edits the save is refused instead of merged.
+const MAX_MERGE_BASE_SIZE: usize = 16 * 1024 * 1024;
+
+/// What each open file looked like on disk when it was last read or saved.
+static LOADED: OnceLock<Mutex<HashMap<PathBuf, LoadedVersion>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Clone, Debug)]
+struct LoadedVersion
+{
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: u64,
+    /// Decoded text, kept as the common ancestor for a merge.
+    base: Option<String>,
+}
+
+/// A stretch of merged text. Concatenating the regions, taking one side of
+/// every conflict, gives the whole file.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum MergeRegion
+{
+    Clean
+    {
+        text: String,
+    },
+    Conflict
+    {
+        /// The lines as they were when the file was loaded.
+        base: String,
+        /// The editor's version.
+        buffer: String,
+        /// The version another program wrote.
+        disk: String,
+    },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MergeResult
+{
+    pub regions: Vec<MergeRegion>,
+    pub conflicts: usize,
+}
+
+/// Result of `save_with_merge`. `strategy` says whether the file was
+/// replaced atomically or had to be rewritten in place.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SaveOutcome
+{
+    Saved
+    {
+        strategy: SaveStrategy,
+    },
+    /// The file changed on disk and the edits merged cleanly; `content` was
+    /// written and should replace the buffer.
+    Merged
+    {
+        content: String,
+        strategy: SaveStrategy,
+    },
+    /// Nothing was written. Once resolved, save again with `overwrite`.
+    Conflict
+    {
+        regions: Vec<MergeRegion>,
+        conflicts: usize,
+    },
+}
+
+/// How a save must proceed given what is on disk now.
+pub enum DiskCheck
+{
+    Unchanged,
+    Merged(String),
+    Conflict(MergeResult),
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Saves a buffer like `write_file`, but if another program changed the
+/// file since it was read or last saved, the edits are merged with that
+/// change first. A clean merge is written and returned so the buffer can
+/// take it; conflicting edits are returned for the UI to resolve and
+/// nothing is written. `overwrite` skips the check, e.g. to save a
+/// resolved conflict.
+#[tauri::command]
+pub async fn save_with_merge(
+    path: String,
+    content: String,
+    options: Option<SaveOptions>,
+    overwrite: Option<bool>,
+) -> Result<SaveOutcome, FileError>
+{
+    check_access(&path)?;
+    let options = options.unwrap_or_default();
+
+    if !overwrite.unwrap_or(false)
+    {
+        match check_disk_changes(&path, &content, options.encoding.as_deref())?
+        {
+            DiskCheck::Unchanged => {},
+            DiskCheck::Merged(merged) => {
+                let strategy = write_text_file(&path, &merged, &options)?;
+                return Ok(SaveOutcome::Merged { content: merged, strategy });
+            },
+            DiskCheck::Conflict(result) => {
+                return Ok(SaveOutcome::Conflict { regions: result.regions, conflicts: result.conflicts });
+            },
+        }
+    }
+
+    let strategy = write_text_file(&path, &content, &options)?;
+    Ok(SaveOutcome::Saved { strategy })
+}
+
+/// Stops tracking a file's loaded version, e.g. when its tab closes.
+#[tauri::command]
+pub async fn forget_loaded_file(path: String) -> Result<(), String>
+{
+    if let Ok(mut loaded) = loaded_lock().lock()
+    {
+        loaded.remove(&version_key(Path::new(&path)));
+    }
+    Ok(())
+}
+
+// ─── Tracking ───────────────────────────────────────────────────
+
+/// Records the bytes and decoded text just read from or written to `path`.
+pub fn remember_version(path: &Path, bytes: &[u8], text: &str)
+{
+    let metadata = fs::metadata(path).ok();
+    let version = LoadedVersion {
+        size: metadata.as_ref().map_or(bytes.len() as u64, |m| m.len()),
+        modified: metadata.and_then(|m| m.modified().ok()),
+        hash: content_hash(bytes),
+        base: (text.len() <= MAX_MERGE_BASE_SIZE).then(|| text.to_string()),
+    };
+    if let Ok(mut loaded) = loaded_lock().lock()
+    {
+        loaded.insert(version_key(path), version);
+    }
+}
+
+/// Re-reads a tracked file after the backend changed it by other means than
+/// a text save, such as a hex patch, so later merges start from what is
+/// actually on disk. Untracked files are left alone.
+pub fn refresh_version(path: &Path)
+{
+    let tracked = loaded_lock().lock().is_ok_and(|loaded| loaded.contains_key(&version_key(path)));
+    if !tracked
+    {
+        return;
+    }
+    match fs::read(path)
+    {
+        Ok(bytes) => remember_version(path, &bytes, &decode_bytes(&bytes, None).content),
+        Err(e) => eprintln!("Failed to re-read '{}' after a patch: {}", path.display(), e),
+    }
+}
+
+/// Compares `path` on disk with the version the buffer was loaded from and,
+/// if another program changed it, merges `content` with that change. The
+/// disk version is decoded with the buffer's `encoding` when it is known.
+/// Untracked and deleted files are `Unchanged`: saving simply writes them.
+pub fn check_disk_changes(path: &str, content: &str, encoding: Option<&str>) -> Result<DiskCheck, FileError>
+{
+    let version = match loaded_lock().lock()
+    {
+        Ok(loaded) => loaded.get(&version_key(Path::new(path))).cloned(),
+        Err(_) => None,
+    };
+    let Some(version) = version
+    else
+    {
+        return Ok(DiskCheck::Unchanged);
+    };
+
+    let metadata = match fs::metadata(path)
+    {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(DiskCheck::Unchanged),
+        Err(e) => return Err(FileError::from_io(path, "inspect", &e)),
+    };
+    if metadata.len() == version.size && metadata.modified().ok() == version.modified
+    {
+        return Ok(DiskCheck::Unchanged);
+    }
+
+    // A newer timestamp alone (touch, checkout of identical content) is not a change
+    let bytes = fs::read(path).map_err(|e| FileError::from_io(path, "read", &e))?;
+    if content_hash(&bytes) == version.hash
+    {
+        return Ok(DiskCheck::Unchanged);
+    }
+
+    let Some(base) = version.base
+    else
+    {
+        return Err(FileError::new(
+            path,
+            "changed-on-disk",
+            format!("'{}' was changed by another program and is too large to merge; reload it or overwrite", path),
+        ));
+    };
+
+    let buffer_encoding = encoding.and_then(parse_encoding_label).map(|(encoding, _)| encoding);
+    let disk = decode_bytes(&bytes, buffer_encoding).content;
+    let result = merge_texts(&base, content, &disk);
+    Ok(match merged_text(&result)
+    {
+        Some(merged) => DiskCheck::Merged(merged),
+        None => DiskCheck::Conflict(result),
+    })
+}
+
+// ─── Merging ────────────────────────────────────────────────────
+
+/// Line-based three-way merge of the editor's `buffer` and the `disk`
+/// version against their common `base`. Lines changed on one side only
+/// are taken from that side; identical changes on both sides merge; other
+/// overlapping changes become conflicts. Line endings are compared
+/// loosely and the result uses the buffer's dominant ending.
+pub fn merge_texts(base: &str, buffer: &str, disk: &str) -> MergeResult
+{
+    let ending = detect_line_ending(buffer);
+    let base = normalize_line_endings(base, LineEnding::Lf);
+    let buffer = normalize_line_endings(buffer, LineEnding::Lf);
+    let disk = normalize_line_endings(disk, LineEnding::Lf);
+
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let buffer_lines: Vec<&str> = buffer.split_inclusive('\n').collect();
+    let disk_lines: Vec<&str> = disk.split_inclusive('\n').collect();
+
+    let to_buffer = matching_lines(&base_lines, &buffer_lines);
+    let to_disk = matching_lines(&base_lines, &disk_lines);
+
+    let mut regions: Vec<MergeRegion> = Vec::new();
+    let mut conflicts = 0;
+    let (mut o, mut a, mut b) = (0, 0, 0);
+
+    loop
+    {
+        // Lines all three versions share
+        let stable_start = o;
+        while o < base_lines.len() && to_buffer[o] == Some(a) && to_disk[o] == Some(b)
+        {
+            o += 1;
+            a += 1;
+            b += 1;
+        }
+        if o > stable_start
+        {
+            push_clean(&mut regions, &base_lines[stable_start..o]);
+        }
+        if o == base_lines.len() && a == buffer_lines.len() && b == disk_lines.len()
+        {
+            break;
+        }
+
+        // The next base line both sides kept ends the unstable chunk
+        let next = (o..base_lines.len()).find_map(|i| Some((i, to_buffer[i]?, to_disk[i]?)));
+        let (next_o, next_a, next_b) = next.unwrap_or((base_lines.len(), buffer_lines.len(), disk_lines.len()));
+
+        let base_chunk = &base_lines[o..next_o];
+        let buffer_chunk = &buffer_lines[a..next_a];
+        let disk_chunk = &disk_lines[b..next_b];
+
+        if buffer_chunk == base_chunk || buffer_chunk == disk_chunk
+        {
+            push_clean(&mut regions, disk_chunk);
+        }
+        else if disk_chunk == base_chunk
+        {
+            push_clean(&mut regions, buffer_chunk);
+        }
+        else
+        {
+            conflicts += 1;
+            regions.push(MergeRegion::Conflict {
+                base: base_chunk.concat(),
+                buffer: buffer_chunk.concat(),
+                disk: disk_chunk.concat(),
+            });
+        }
+
+        o = next_o;
+        a = next_a;
+        b = next_b;
+    }
+
+    if ending != LineEnding::Lf
+    {
+        for region in regions.iter_mut()
+        {
+            match region
+            {
+                MergeRegion::Clean { text } => *text = normalize_line_endings(text, ending),
+                MergeRegion::Conflict { base, buffer, disk } => {
+                    for text in [base, buffer, disk]
+                    {
+                        *text = normalize_line_endings(text, ending);
+                    }
+                },
+            }
+        }
+    }
+
+    MergeResult { regions, conflicts }
+}
+
+/// The merged text, if the merge has no conflicts.
+pub fn merged_text(result: &MergeResult) -> Option<String>
+{
+    let mut merged = String::new();
+    for region in &result.regions
+    {
+        match region
+        {
+            MergeRegion::Clean { text } => merged.push_str(text),
+            MergeRegion::Conflict { .. } => return None,
+        }
+    }
+    Some(merged)
+}
+
+/// For each base line, the index of the line it is kept as on the other
+/// side, or `None` if it was removed or changed there.
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>>
+{
+    let mut matches = vec![None; base.len()];
+    for op in capture_diff_slices(Algorithm::Myers, base, other)
+    {
+        let (tag, base_range, other_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal
+        {
+            for (i, j) in base_range.zip(other_range)
+            {
+                matches[i] = Some(j);
+            }
+        }
+    }
+    matches
+}
+
+fn push_clean(regions: &mut Vec<MergeRegion>, lines: &[&str])
+{
+    if lines.is_empty()
+    {
+        return;
+    }
+    match regions.last_mut()
+    {
+        Some(MergeRegion::Clean { text }) => text.push_str(&lines.concat()),
+        _ => regions.push(MergeRegion::Clean { text: lines.concat() }),
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn loaded_lock() -> &'static Mutex<HashMap<PathBuf, LoadedVersion>>
+{
+    LOADED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn version_key(path: &Path) -> PathBuf
+{
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::commands::file_operations::read_text_file;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn clean(text: &str) -> MergeRegion
+    {
+        MergeRegion::Clean { text: text.to_string() }
+    }
+
+    /// Writes `text` with a modification time clearly after the loaded one.
+    fn edit_on_disk(path: &Path, text: &str)
+    {
+        fs::write(path, text).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options().write(true).open(path).unwrap().set_modified(later).unwrap();
+    }
+
+    // ── merge_texts ─────────────────────────────────────────────
+
+    #[test]
+    fn separate_edits_merge_cleanly()
+    {
+        let base = "one\ntwo\nthree\nfour\n";
+        let buffer = "one\n2\nthree\nfour\n";
+        let disk = "one\ntwo\nthree\nfour\nfive\n";
+
+        let result = merge_texts(base, buffer, disk);
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(merged_text(&result).unwrap(), "one\n2\nthree\nfour\nfive\n");
+    }
+
+    #[test]
+    fn identical_edits_do_not_conflict()
+    {
+        let result = merge_texts("a\nb\n", "a\nB\n", "a\nB\n");
+        assert_eq!(merged_text(&result).unwrap(), "a\nB\n");
+    }
+
+    #[test]
+    fn overlapping_edits_become_conflict_regions()
+    {
+        let result = merge_texts("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(result.regions, vec![
+            clean("a\n"),
+            MergeRegion::Conflict { base: "b\n".into(), buffer: "mine\n".into(), disk: "theirs\n".into() },
+            clean("c\n"),
+        ]);
+        assert!(merged_text(&result).is_none());
+    }
+
+    #[test]
+    fn merge_keeps_the_buffer_line_ending()
+    {
+        let result = merge_texts("a\r\nb\r\nc\r\n", "a\r\nB\r\nc\r\n", "a\nb\nc\nd\n");
+        assert_eq!(merged_text(&result).unwrap(), "a\r\nB\r\nc\r\nd\r\n");
+    }
+
+    #[test]
+    fn deletion_against_edit_conflicts()
+    {
+        let result = merge_texts("a\nb\nc\n", "a\nc\n", "a\nB\nc\n");
+        assert_eq!(result.conflicts, 1);
+    }
+
+    // ── check_disk_changes ──────────────────────────────────────
+
+    #[test]
+    fn untouched_and_untracked_files_are_unchanged()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "a\n").unwrap();
+        let path_str = path.to_string_lossy().into_owned();
+
+        assert!(matches!(check_disk_changes(&path_str, "b\n", None).unwrap(), DiskCheck::Unchanged));
+        remember_version(&path, b"a\n", "a\n");
+        assert!(matches!(check_disk_changes(&path_str, "b\n", None).unwrap(), DiskCheck::Unchanged));
+
+        // Only the timestamp moved
+        edit_on_disk(&path, "a\n");
+        assert!(matches!(check_disk_changes(&path_str, "b\n", None).unwrap(), DiskCheck::Unchanged));
+    }
+
+    #[test]
+    fn external_edits_are_merged_or_reported()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "1\n2\n3\n").unwrap();
+        remember_version(&path, b"1\n2\n3\n", "1\n2\n3\n");
+        let path_str = path.to_string_lossy().into_owned();
+
+        edit_on_disk(&path, "1\n2\n3\n4\n");
+        match check_disk_changes(&path_str, "one\n2\n3\n", None).unwrap()
+        {
+            DiskCheck::Merged(merged) => assert_eq!(merged, "one\n2\n3\n4\n"),
+            _ => panic!("expected a clean merge"),
+        }
+        match check_disk_changes(&path_str, "1\n2\n3\nfour\n", None).unwrap()
+        {
+            DiskCheck::Conflict(result) => assert_eq!(result.conflicts, 1),
+            _ => panic!("expected a conflict"),
+        }
+    }
+
+    #[test]
+    fn disk_version_is_decoded_with_the_buffer_encoding()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("latin.txt");
+        fs::write(&path, b"caf\xe9\nmid\nend\n").unwrap();
+        remember_version(&path, b"caf\xe9\nmid\nend\n", "café\nmid\nend\n");
+        let path_str = path.to_string_lossy().into_owned();
+
+        // Another program appends a line, still in Windows-1252
+        fs::write(&path, b"caf\xe9\nmid\nend\nnew\n").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+        match check_disk_changes(&path_str, "CAFÉ\nmid\nend\n", Some("windows-1252")).unwrap()
+        {
+            DiskCheck::Merged(merged) => assert_eq!(merged, "CAFÉ\nmid\nend\nnew\n"),
+            _ => panic!("expected a clean merge"),
+        }
+    }
+
+    // ── save_with_merge ─────────────────────────────────────────
+
+    #[tokio::test]
+    async fn saves_merge_external_changes()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("shared.txt");
+        let path_str = path.to_string_lossy().into_owned();
+        fs::write(&path, "a\nb\nc\n").unwrap();
+        read_text_file(&path_str, None).unwrap();
+
+        // Another program appends a line
+        edit_on_disk(&path, "a\nb\nc\nd\n");
+
+        let conflict = save_with_merge(path_str.clone(), "a\nb\nc\nmine\n".into(), None, None).await.unwrap();
+        assert!(matches!(conflict, SaveOutcome::Conflict { conflicts: 1, .. }));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\nc\nd\n");
+
+        let merged = save_with_merge(path_str.clone(), "A\nb\nc\n".into(), None, None).await.unwrap();
+        assert_eq!(merged, SaveOutcome::Merged { content: "A\nb\nc\nd\n".into(), strategy: SaveStrategy::Atomic });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "A\nb\nc\nd\n");
+
+        // The merged save is the new base, so the next save is plain
+        let saved = save_with_merge(path_str, "A\nb\n".into(), None, None).await.unwrap();
+        assert_eq!(saved, SaveOutcome::Saved { strategy: SaveStrategy::Atomic });
+    }
+}
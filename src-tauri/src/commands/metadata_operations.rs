@@ -0,0 +1,364 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::access_operations::{check_access, check_entry_access};
+use super::encoding_operations::decode_bytes;
+use super::file_operations::FileError;
+
+/// Files larger than this are not scanned for line/word/char counts;
+/// `stat_file` runs for every tab tooltip and must stay cheap.
+const MAX_COUNT_SIZE: u64 = 4 * 1024 * 1024;
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileStat
+{
+    pub path: String,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch; `None` where the platform has no such time.
+    pub created: Option<u64>,
+    pub modified: Option<u64>,
+    pub accessed: Option<u64>,
+    /// Symbolic form such as "rw-r--r--" (Unix only).
+    pub permissions: Option<String>,
+    pub mode: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    #[serde(rename = "isDirectory")]
+    pub is_directory: bool,
+    #[serde(rename = "isSymlink")]
+    pub is_symlink: bool,
+    #[serde(rename = "linkTarget")]
+    pub link_target: Option<String>,
+    /// Whether the current user is unable to write the file.
+    #[serde(rename = "isReadOnly")]
+    pub is_read_only: bool,
+    /// Omitted for directories and files over `MAX_COUNT_SIZE`.
+    pub counts: Option<TextCounts>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct TextCounts
+{
+    pub lines: u64,
+    pub words: u64,
+    pub chars: u64,
+}
+
+/// Counts text fed in arbitrary chunks. LF, CRLF and lone CR each count
+/// as one line break; words are runs of non-whitespace, like `wc -w`.
+#[derive(Default)]
+struct TextCounter
+{
+    counts: TextCounts,
+    in_word: bool,
+    after_cr: bool,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// File facts for the Summary dialog and tab tooltips.
+#[tauri::command]
+pub async fn stat_file(path: String) -> Result<FileStat, FileError>
+{
+    check_entry_access(&path)?;
+    let link_metadata = fs::symlink_metadata(&path)
+        .map_err(|e| FileError::from_io(&path, "read", &e))?;
+    let is_symlink = link_metadata.file_type().is_symlink();
+    let link_target = if is_symlink
+    {
+        fs::read_link(&path).ok().map(|target| target.to_string_lossy().into_owned())
+    }
+    else
+    {
+        None
+    };
+
+    // Describe what the link points at, or the link itself when it is broken
+    // or points outside the allowed roots
+    let follow = !is_symlink || check_access(&path).is_ok();
+    let metadata = if follow
+    {
+        fs::metadata(&path).unwrap_or(link_metadata)
+    }
+    else
+    {
+        link_metadata
+    };
+    let to_millis = |time: io::Result<SystemTime>| time.ok().and_then(epoch_millis);
+
+    let counts = if metadata.is_file() && metadata.len() <= MAX_COUNT_SIZE
+    {
+        Some(count_file(Path::new(&path)).map_err(|e| FileError::from_io(&path, "read", &e))?)
+    }
+    else
+    {
+        None
+    };
+
+    let (permissions, mode, owner, group) = ownership(&metadata);
+
+    Ok(FileStat {
+        size: metadata.len(),
+        created: to_millis(metadata.created()),
+        modified: to_millis(metadata.modified()),
+        accessed: to_millis(metadata.accessed()),
+        permissions,
+        mode,
+        owner,
+        group,
+        is_directory: metadata.is_dir(),
+        is_symlink,
+        link_target,
+        is_read_only: follow && is_read_only(Path::new(&path), &metadata),
+        counts,
+        path,
+    })
+}
+
+// ─── Counting ───────────────────────────────────────────────────
+
+/// Decodes the file with its detected encoding and counts it.
+pub fn count_file(path: &Path) -> io::Result<TextCounts>
+{
+    let mut counter = TextCounter::default();
+    counter.feed(&decode_bytes(&fs::read(path)?, None).content);
+    Ok(counter.finish())
+}
+
+impl TextCounter
+{
+    fn feed(&mut self, text: &str)
+    {
+        for c in text.chars()
+        {
+            self.counts.chars += 1;
+
+            match c
+            {
+                '\n' if self.after_cr => {},
+                '\n' | '\r' => self.counts.lines += 1,
+                _ => {}
+            }
+            self.after_cr = c == '\r';
+
+            if c.is_whitespace()
+            {
+                self.in_word = false;
+            }
+            else if !self.in_word
+            {
+                self.in_word = true;
+                self.counts.words += 1;
+            }
+        }
+    }
+
+    /// The last line has no terminator, so lines are breaks plus one.
+    fn finish(mut self) -> TextCounts
+    {
+        self.counts.lines += 1;
+        self.counts
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn epoch_millis(time: SystemTime) -> Option<u64>
+{
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+#[cfg(unix)]
+fn ownership(metadata: &fs::Metadata) -> (Option<String>, Option<u32>, Option<String>, Option<String>)
+{
+    use std::os::unix::fs::MetadataExt;
+
+    let mode = metadata.mode() & 0o7777;
+    let symbolic: String = [0o400, 0o200, 0o100, 0o040, 0o020, 0o010, 0o004, 0o002, 0o001]
+        .iter()
+        .zip("rwxrwxrwx".chars())
+        .map(|(bit, c)| if mode & bit != 0 { c } else { '-' })
+        .collect();
+
+    // Fall back to the numeric IDs for users and groups without a name
+    let owner = user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string());
+    let group = group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string());
+
+    (Some(symbolic), Some(mode), Some(owner), Some(group))
+}
+
+#[cfg(not(unix))]
+fn ownership(_metadata: &fs::Metadata) -> (Option<String>, Option<u32>, Option<String>, Option<String>)
+{
+    (None, None, None, None)
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String>
+{
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+
+    let status = unsafe { libc::getpwuid_r(uid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null()
+    {
+        return None;
+    }
+    Some(unsafe { std::ffi::CStr::from_ptr(entry.pw_name) }.to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> Option<String>
+{
+    let mut entry: libc::group = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+
+    let status = unsafe { libc::getgrgid_r(gid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null()
+    {
+        return None;
+    }
+    Some(unsafe { std::ffi::CStr::from_ptr(entry.gr_name) }.to_string_lossy().into_owned())
+}
+
+/// Asks the OS rather than reading mode bits, so ACLs, ownership and
+/// read-only mounts are all taken into account.
+#[cfg(unix)]
+fn is_read_only(path: &Path, _metadata: &fs::Metadata) -> bool
+{
+    use std::os::unix::ffi::OsStrExt;
+
+    match std::ffi::CString::new(path.as_os_str().as_bytes())
+    {
+        Ok(c_path) => unsafe { libc::access(c_path.as_ptr(), libc::W_OK) != 0 },
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_read_only(_path: &Path, metadata: &fs::Metadata) -> bool
+{
+    metadata.permissions().readonly()
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn counts_of(text: &str) -> TextCounts
+    {
+        let mut counter = TextCounter::default();
+        counter.feed(text);
+        counter.finish()
+    }
+
+    // ── Counting ────────────────────────────────────────────────
+
+    #[test]
+    fn counts_lines_words_and_chars()
+    {
+        assert_eq!(counts_of("hello world\nsecond line"), TextCounts { lines: 2, words: 4, chars: 23 });
+        assert_eq!(counts_of(""), TextCounts { lines: 1, words: 0, chars: 0 });
+        assert_eq!(counts_of("a\r\nb\rc\n"), TextCounts { lines: 4, words: 3, chars: 7 });
+    }
+
+    #[test]
+    fn counts_crlf_split_across_chunks_once()
+    {
+        let mut counter = TextCounter::default();
+        counter.feed("one\r");
+        counter.feed("\ntwo");
+        assert_eq!(counter.finish().lines, 2);
+    }
+
+    #[test]
+    fn count_file_decodes_utf16()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("utf16.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("héllo wörld".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(count_file(&path).unwrap(), TextCounts { lines: 1, words: 2, chars: 11 });
+    }
+
+    // ── stat_file ───────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn stat_file_reports_basic_facts()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "one two\nthree").unwrap();
+
+        let stat = stat_file(path.to_string_lossy().into_owned()).await.unwrap();
+        assert_eq!(stat.size, 13);
+        assert!(stat.modified.is_some());
+        assert!(!stat.is_directory);
+        assert!(!stat.is_symlink);
+        assert_eq!(stat.counts, Some(TextCounts { lines: 2, words: 3, chars: 13 }));
+    }
+
+    #[tokio::test]
+    async fn stat_file_skips_counts_for_directories()
+    {
+        let dir = TempDir::new().unwrap();
+        let stat = stat_file(dir.path().to_string_lossy().into_owned()).await.unwrap();
+        assert!(stat.is_directory);
+        assert!(stat.counts.is_none());
+    }
+
+    #[tokio::test]
+    async fn stat_file_reports_missing_file()
+    {
+        let err = stat_file("/nonexistent/notes.txt".to_string()).await.unwrap_err();
+        assert_eq!(err.kind, "not-found");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stat_file_describes_symlinks_and_permissions()
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let real = dir.path().join("real.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&real, "x").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink("real.txt", &link).unwrap();
+
+        let stat = stat_file(link.to_string_lossy().into_owned()).await.unwrap();
+        assert!(stat.is_symlink);
+        assert_eq!(stat.link_target.as_deref(), Some("real.txt"));
+        assert_eq!(stat.permissions.as_deref(), Some("rw-r-----"));
+        assert_eq!(stat.mode, Some(0o640));
+        assert!(stat.owner.is_some());
+        assert!(!stat.is_read_only);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stat_file_reports_broken_symlink()
+    {
+        let dir = TempDir::new().unwrap();
+        let link = dir.path().join("dangling");
+        std::os::unix::fs::symlink("missing.txt", &link).unwrap();
+
+        let stat = stat_file(link.to_string_lossy().into_owned()).await.unwrap();
+        assert!(stat.is_symlink);
+        assert!(stat.counts.is_none());
+    }
+}
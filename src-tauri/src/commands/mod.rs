@@ -3,3 +3,24 @@ pub mod dialog_operations;
 pub mod window_operations;
 pub mod crypto_operations;
 pub mod process_operations;
+pub mod encoding_operations;
+pub mod save_operations;
+pub mod backup_operations;
+pub mod watcher_operations;
+pub mod monitor_operations;
+pub mod large_file_operations;
+pub mod binary_operations;
+pub mod metadata_operations;
+pub mod trash_operations;
+pub mod tree_operations;
+pub mod path_operations;
+pub mod access_operations;
+pub mod search_operations;
+pub mod replace_operations;
+pub mod quick_open_operations;
+pub mod trigram_operations;
+pub mod diff_operations;
+pub mod merge_operations;
+pub mod recovery_operations;
+pub mod session_operations;
+pub mod recent_operations;
@@ -0,0 +1,512 @@
+use encoding_rs::{Decoder, UTF_8, UTF_16BE, UTF_16LE};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::check_access;
+use super::encoding_operations::{TextEncoding, detect_encoding};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Longest wait between polls while the file keeps failing to read.
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(8);
+/// Largest slice of appended data sent in one event.
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+/// Upper bound on bytes read per poll, so a burst cannot stall stop requests.
+const MAX_BYTES_PER_POLL: u64 = 16 * 1024 * 1024;
+
+static NEXT_MONITOR_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Stop flags of running monitors, keyed by monitor ID.
+static MONITORS: OnceLock<Mutex<HashMap<u64, Arc<AtomicBool>>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+struct MonitorDataPayload
+{
+    id: u64,
+    path: String,
+    text: String,
+    /// Byte offset just past the data in this event.
+    offset: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct MonitorStatusPayload
+{
+    id: u64,
+    path: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorUpdate
+{
+    Data { text: String, offset: u64 },
+    /// The file shrank below the read position; reading restarts at 0.
+    Truncated,
+    /// A different file now lives at the path (log rotation).
+    Rotated,
+    /// The path disappeared; monitoring resumes if it comes back.
+    Missing,
+}
+
+/// Follows one file. Only bytes past `offset` are ever read, so files
+/// that grow by gigabytes are never re-read from the start.
+pub struct TailState
+{
+    path: PathBuf,
+    file: File,
+    identity: Option<(u64, u64)>,
+    offset: u64,
+    encoding: TextEncoding,
+    decoder: StreamDecoder,
+    /// The odd trailing byte of a UTF-16 read, held back so every reported
+    /// offset falls between code units.
+    carry: Vec<u8>,
+    missing: bool,
+}
+
+/// Incremental decoder that carries partial multi-byte sequences over
+/// from one chunk to the next.
+enum StreamDecoder
+{
+    EncodingRs(Decoder),
+    Latin1,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Starts following `path` and returns a monitor ID for `stop_monitoring`.
+/// `from_offset` is how many bytes the frontend already has; by default
+/// monitoring starts at the current end of the file, like `tail -f`.
+#[tauri::command]
+pub async fn start_monitoring(
+    app: AppHandle,
+    path: String,
+    from_offset: Option<u64>,
+) -> Result<u64, String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    let mut state = TailState::open(Path::new(&path), from_offset)
+        .map_err(|e| format!("Failed to monitor '{}': {}", path, e))?;
+
+    let id = NEXT_MONITOR_ID.fetch_add(1, Ordering::SeqCst);
+    let stop = Arc::new(AtomicBool::new(false));
+    monitors().lock()
+        .map_err(|_| "Monitor registry lock poisoned".to_string())?
+        .insert(id, stop.clone());
+
+    std::thread::spawn(move || {
+        let mut delay = POLL_INTERVAL;
+        let mut last_error: Option<String> = None;
+        while !stop.load(Ordering::SeqCst)
+        {
+            match state.poll()
+            {
+                Ok(updates) => {
+                    if last_error.take().is_some()
+                    {
+                        eprintln!("Monitoring '{}' again", path);
+                    }
+                    delay = POLL_INTERVAL;
+                    for update in updates
+                    {
+                        emit_update(&app, id, &path, update);
+                    }
+                },
+                // A lasting failure is logged once and retried ever less often
+                Err(e) => {
+                    let message = e.to_string();
+                    if last_error.as_deref() != Some(message.as_str())
+                    {
+                        eprintln!("Failed to monitor '{}': {}", path, message);
+                    }
+                    if last_error.is_some()
+                    {
+                        delay = (delay * 2).min(MAX_RETRY_INTERVAL);
+                    }
+                    last_error = Some(message);
+                },
+            }
+
+            let mut waited = Duration::ZERO;
+            while waited < delay && !stop.load(Ordering::SeqCst)
+            {
+                std::thread::sleep(POLL_INTERVAL);
+                waited += POLL_INTERVAL;
+            }
+        }
+
+        if let Ok(mut registry) = monitors().lock()
+        {
+            registry.remove(&id);
+        }
+        let _ = app.emit("monitor-stopped", MonitorStatusPayload { id, path });
+    });
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn stop_monitoring(id: u64) -> Result<(), String>
+{
+    let registry = monitors().lock()
+        .map_err(|_| "Monitor registry lock poisoned".to_string())?;
+    let stop = registry.get(&id)
+        .ok_or_else(|| format!("No active monitor with ID {}", id))?;
+    stop.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+// ─── Tailing ────────────────────────────────────────────────────
+
+impl TailState
+{
+    pub fn open(path: &Path, from_offset: Option<u64>) -> io::Result<Self>
+    {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if metadata.is_dir()
+        {
+            return Err(io::Error::other("Cannot monitor a directory"));
+        }
+
+        // Sniff the encoding from the head of the file, without reading all of it
+        let mut head = Vec::new();
+        (&mut file).take(64 * 1024).read_to_end(&mut head)?;
+        let encoding = detect_encoding(&head).encoding;
+
+        let offset = from_offset.unwrap_or(metadata.len()).min(metadata.len());
+        let offset = offset - offset % code_unit(encoding);
+        Ok(TailState {
+            path: path.to_path_buf(),
+            file,
+            identity: file_identity(&metadata),
+            offset,
+            encoding,
+            decoder: StreamDecoder::new(encoding),
+            carry: Vec::new(),
+            missing: false,
+        })
+    }
+
+    /// Checks the file once and returns what happened since the last poll.
+    pub fn poll(&mut self) -> io::Result<Vec<MonitorUpdate>>
+    {
+        let mut updates = Vec::new();
+
+        // Drain the handle we hold first: after a rotation it still points at the old file
+        let held_len = self.file.metadata()?.len();
+        if held_len < self.offset
+        {
+            self.restart();
+            updates.push(MonitorUpdate::Truncated);
+        }
+        self.read_appended(held_len, &mut updates)?;
+
+        match fs::metadata(&self.path)
+        {
+            Ok(metadata) => {
+                if self.missing || file_identity(&metadata) != self.identity
+                {
+                    self.file = File::open(&self.path)?;
+                    self.identity = file_identity(&self.file.metadata()?);
+                    self.missing = false;
+                    self.restart();
+                    updates.push(MonitorUpdate::Rotated);
+
+                    let len = self.file.metadata()?.len();
+                    self.read_appended(len, &mut updates)?;
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if !self.missing
+                {
+                    self.missing = true;
+                    updates.push(MonitorUpdate::Missing);
+                }
+            },
+            Err(e) => return Err(e),
+        }
+
+        Ok(updates)
+    }
+
+    fn restart(&mut self)
+    {
+        self.offset = 0;
+        self.decoder = StreamDecoder::new(self.encoding);
+        self.carry.clear();
+    }
+
+    fn read_appended(&mut self, len: u64, updates: &mut Vec<MonitorUpdate>) -> io::Result<()>
+    {
+        let end = len.min(self.offset + MAX_BYTES_PER_POLL);
+        if end <= self.offset
+        {
+            return Ok(());
+        }
+
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        let mut buffer = vec![0u8; MAX_CHUNK_SIZE.min((end - self.offset) as usize)];
+        while self.offset < end
+        {
+            let want = buffer.len().min((end - self.offset) as usize);
+            let read = self.file.read(&mut buffer[..want])?;
+            if read == 0
+            {
+                break;
+            }
+            self.offset += read as u64;
+
+            let mut bytes = std::mem::take(&mut self.carry);
+            bytes.extend_from_slice(&buffer[..read]);
+            let whole = bytes.len() - bytes.len() % code_unit(self.encoding) as usize;
+            self.carry = bytes.split_off(whole);
+
+            let text = self.decoder.decode(&bytes);
+            if !text.is_empty()
+            {
+                updates.push(MonitorUpdate::Data { text, offset: self.offset - self.carry.len() as u64 });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StreamDecoder
+{
+    fn new(encoding: TextEncoding) -> Self
+    {
+        // BOMs only appear at offset 0, where the decoder strips them
+        match encoding
+        {
+            TextEncoding::Latin1 => StreamDecoder::Latin1,
+            TextEncoding::Utf8 => StreamDecoder::EncodingRs(UTF_8.new_decoder_with_bom_removal()),
+            TextEncoding::Utf16Le => StreamDecoder::EncodingRs(UTF_16LE.new_decoder_with_bom_removal()),
+            TextEncoding::Utf16Be => StreamDecoder::EncodingRs(UTF_16BE.new_decoder_with_bom_removal()),
+            TextEncoding::Legacy(encoding) => StreamDecoder::EncodingRs(encoding.new_decoder_with_bom_removal()),
+        }
+    }
+
+    fn decode(&mut self, bytes: &[u8]) -> String
+    {
+        match self
+        {
+            StreamDecoder::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+            StreamDecoder::EncodingRs(decoder) => {
+                let capacity = decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(bytes.len() * 3);
+                let mut text = String::with_capacity(capacity);
+                let _ = decoder.decode_to_string(bytes, &mut text, false);
+                text
+            },
+        }
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn emit_update(app: &AppHandle, id: u64, path: &str, update: MonitorUpdate)
+{
+    let status = |event: &str| {
+        let _ = app.emit(event, MonitorStatusPayload { id, path: path.to_string() });
+    };
+
+    match update
+    {
+        MonitorUpdate::Data { text, offset } => {
+            let _ = app.emit("monitor-data", MonitorDataPayload {
+                id,
+                path: path.to_string(),
+                text,
+                offset,
+            });
+        },
+        MonitorUpdate::Truncated => status("monitor-truncated"),
+        MonitorUpdate::Rotated => status("monitor-rotated"),
+        MonitorUpdate::Missing => status("monitor-file-missing"),
+    }
+}
+
+fn code_unit(encoding: TextEncoding) -> u64
+{
+    if matches!(encoding, TextEncoding::Utf16Le | TextEncoding::Utf16Be) { 2 } else { 1 }
+}
+
+fn monitors() -> &'static Mutex<HashMap<u64, Arc<AtomicBool>>>
+{
+    MONITORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Device and inode on Unix; elsewhere rotation is only seen as truncation.
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata) -> Option<(u64, u64)>
+{
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata) -> Option<(u64, u64)>
+{
+    None
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn append(path: &Path, bytes: &[u8])
+    {
+        fs::OpenOptions::new().append(true).open(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    fn text_of(updates: &[MonitorUpdate]) -> String
+    {
+        updates.iter()
+            .filter_map(|u| match u
+            {
+                MonitorUpdate::Data { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn starts_at_end_of_file_by_default()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "existing line\n").unwrap();
+
+        let mut state = TailState::open(&path, None).unwrap();
+        assert!(state.poll().unwrap().is_empty());
+
+        append(&path, b"new line\n");
+        let updates = state.poll().unwrap();
+        assert_eq!(updates, vec![MonitorUpdate::Data { text: "new line\n".into(), offset: 23 }]);
+    }
+
+    #[test]
+    fn from_offset_backfills_missing_bytes()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "abcdef").unwrap();
+
+        let mut state = TailState::open(&path, Some(4)).unwrap();
+        assert_eq!(text_of(&state.poll().unwrap()), "ef");
+    }
+
+    #[test]
+    fn detects_truncation()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "a long first line\n").unwrap();
+
+        let mut state = TailState::open(&path, None).unwrap();
+        fs::write(&path, "short\n").unwrap();
+
+        let updates = state.poll().unwrap();
+        assert_eq!(updates[0], MonitorUpdate::Truncated);
+        assert_eq!(text_of(&updates), "short\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detects_rotation_and_drains_old_file()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "start\n").unwrap();
+
+        let mut state = TailState::open(&path, None).unwrap();
+        append(&path, b"last words\n");
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        fs::write(&path, "fresh\n").unwrap();
+
+        let updates = state.poll().unwrap();
+        assert_eq!(text_of(&updates[..1]), "last words\n");
+        assert_eq!(updates[1], MonitorUpdate::Rotated);
+        assert_eq!(text_of(&updates[2..]), "fresh\n");
+    }
+
+    #[test]
+    fn reports_missing_file_once_and_recovers()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "x").unwrap();
+
+        let mut state = TailState::open(&path, None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(state.poll().unwrap(), vec![MonitorUpdate::Missing]);
+        assert!(state.poll().unwrap().is_empty());
+
+        fs::write(&path, "back\n").unwrap();
+        let updates = state.poll().unwrap();
+        assert_eq!(updates[0], MonitorUpdate::Rotated);
+        assert_eq!(text_of(&updates), "back\n");
+    }
+
+    #[test]
+    fn carries_split_utf8_sequences_across_polls()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "é\n").unwrap();
+
+        let mut state = TailState::open(&path, None).unwrap();
+        let rocket = "🚀".as_bytes();
+        append(&path, &rocket[..2]);
+        assert_eq!(text_of(&state.poll().unwrap()), "");
+        append(&path, &rocket[2..]);
+        assert_eq!(text_of(&state.poll().unwrap()), "🚀");
+    }
+
+    #[test]
+    fn holds_back_odd_utf16_bytes_until_the_code_unit_completes()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        let utf16 = |text: &str| -> Vec<u8> { text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect() };
+        let mut head = vec![0xFF, 0xFE];
+        head.extend(utf16("start\n"));
+        fs::write(&path, &head).unwrap();
+
+        let mut state = TailState::open(&path, Some(5)).unwrap();
+        assert_eq!(state.offset, 4);
+        state.poll().unwrap();
+
+        let line = utf16("ok\n");
+        append(&path, &line[..3]);
+        assert_eq!(state.poll().unwrap(), vec![MonitorUpdate::Data { text: "o".into(), offset: 16 }]);
+        append(&path, &line[3..]);
+        assert_eq!(state.poll().unwrap(), vec![MonitorUpdate::Data { text: "k\n".into(), offset: 20 }]);
+    }
+
+    #[test]
+    fn rejects_directories()
+    {
+        let dir = TempDir::new().unwrap();
+        assert!(TailState::open(dir.path(), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn stop_monitoring_rejects_unknown_id()
+    {
+        assert!(stop_monitoring(u64::MAX).await.is_err());
+    }
+}
@@ -0,0 +1,766 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::{check_access, check_entry_access};
+use super::file_operations::FileError;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// Progress of a recursive copy, move or delete.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationProgress
+{
+    /// "copy", "move", "duplicate" or "delete".
+    pub operation: String,
+    pub source: String,
+    #[serde(rename = "currentPath")]
+    pub current_path: String,
+    #[serde(rename = "itemsDone")]
+    pub items_done: u64,
+    #[serde(rename = "itemsTotal")]
+    pub items_total: u64,
+    #[serde(rename = "bytesDone")]
+    pub bytes_done: u64,
+    #[serde(rename = "bytesTotal")]
+    pub bytes_total: u64,
+}
+
+/// Tracks progress and throttles how often it is reported.
+struct ProgressTracker<'a>
+{
+    progress: OperationProgress,
+    last_report: Option<Instant>,
+    report: Option<&'a mut dyn FnMut(&OperationProgress)>,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Creates an empty file, and any missing parent folders. Never
+/// overwrites: an existing path comes back as `already-exists`.
+#[tauri::command]
+pub async fn create_file(path: String) -> Result<String, FileError>
+{
+    check_access(&path)?;
+    run_blocking(move || {
+        let target = PathBuf::from(&path);
+        ensure_absent(&target)?;
+        if let Some(parent) = target.parent()
+        {
+            fs::create_dir_all(parent).map_err(|e| FileError::from_io(&path, "create", &e))?;
+        }
+        fs::OpenOptions::new().write(true).create_new(true).open(&target)
+            .map_err(|e| FileError::from_io(&path, "create", &e))?;
+        Ok(path)
+    }).await
+}
+
+#[tauri::command]
+pub async fn create_dir(path: String) -> Result<String, FileError>
+{
+    check_access(&path)?;
+    run_blocking(move || {
+        ensure_absent(Path::new(&path))?;
+        fs::create_dir_all(&path).map_err(|e| FileError::from_io(&path, "create folder", &e))?;
+        Ok(path)
+    }).await
+}
+
+/// Copies a file or folder tree. With `overwrite`, an existing
+/// destination is replaced; otherwise it is reported as `already-exists`.
+#[tauri::command]
+pub async fn copy_path(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    overwrite: Option<bool>,
+) -> Result<String, FileError>
+{
+    check_entry_access(&source)?;
+    check_entry_access(&destination)?;
+    run_blocking(move || {
+        let mut report = progress_emitter(&app);
+        copy_to(Path::new(&source), Path::new(&destination), overwrite.unwrap_or(false), &mut report)?;
+        Ok(destination)
+    }).await
+}
+
+/// Moves a file or folder. Within one filesystem this is a rename;
+/// across filesystems the tree is copied and the source removed.
+#[tauri::command]
+pub async fn move_path(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    overwrite: Option<bool>,
+) -> Result<String, FileError>
+{
+    check_entry_access(&source)?;
+    check_entry_access(&destination)?;
+    run_blocking(move || {
+        let mut report = progress_emitter(&app);
+        move_to(Path::new(&source), Path::new(&destination), overwrite.unwrap_or(false), &mut report)?;
+        Ok(destination)
+    }).await
+}
+
+/// Copies `path` next to itself as "name copy.ext", "name copy 2.ext", …
+/// and returns the new path.
+#[tauri::command]
+pub async fn duplicate_path(app: AppHandle, path: String) -> Result<String, FileError>
+{
+    check_entry_access(&path)?;
+    run_blocking(move || {
+        let mut report = progress_emitter(&app);
+        duplicate(Path::new(&path), &mut report)
+            .map(|copy| copy.to_string_lossy().into_owned())
+    }).await
+}
+
+/// Permanently deletes a file or folder tree. Use `delete_to_trash` for
+/// deletes the user may want to undo.
+#[tauri::command]
+pub async fn delete_path(app: AppHandle, path: String) -> Result<(), FileError>
+{
+    check_entry_access(&path)?;
+    run_blocking(move || {
+        let mut report = progress_emitter(&app);
+        delete(Path::new(&path), &mut report)
+    }).await
+}
+
+// ─── Operations ─────────────────────────────────────────────────
+
+pub fn copy_to(
+    source: &Path,
+    destination: &Path,
+    overwrite: bool,
+    report: &mut dyn FnMut(&OperationProgress),
+) -> Result<(), FileError>
+{
+    let set_aside = prepare_destination(source, destination, overwrite)?;
+    let result = ProgressTracker::new("copy", source, report).and_then(|mut tracker| {
+        copy_tree(source, destination, &mut tracker)
+            .map_err(|e| FileError::from_io(&destination.to_string_lossy(), "copy", &e))?;
+        tracker.finish();
+        Ok(())
+    });
+    settle(destination, set_aside, result)
+}
+
+pub fn move_to(
+    source: &Path,
+    destination: &Path,
+    overwrite: bool,
+    report: &mut dyn FnMut(&OperationProgress),
+) -> Result<(), FileError>
+{
+    let set_aside = prepare_destination(source, destination, overwrite)?;
+    let mut copied = false;
+    let result = place_moved(source, destination, &mut copied, report);
+    settle(destination, set_aside, result)?;
+
+    // The source goes only once the copy is safely in place
+    if copied
+    {
+        remove_tree(source, &mut ProgressTracker::silent())
+            .map_err(|e| FileError::from_io(&source.to_string_lossy(), "move", &e))?;
+    }
+    Ok(())
+}
+
+pub fn duplicate(path: &Path, report: &mut dyn FnMut(&OperationProgress)) -> Result<PathBuf, FileError>
+{
+    let copy = unique_copy_path(path);
+    let result = ProgressTracker::new("duplicate", path, report).and_then(|mut tracker| {
+        copy_tree(path, &copy, &mut tracker)
+            .map_err(|e| FileError::from_io(&copy.to_string_lossy(), "duplicate", &e))?;
+        tracker.finish();
+        Ok(())
+    });
+    settle(&copy, None, result).map(|_| copy)
+}
+
+pub fn delete(path: &Path, report: &mut dyn FnMut(&OperationProgress)) -> Result<(), FileError>
+{
+    let mut tracker = ProgressTracker::new("delete", path, report)?;
+    remove_tree(path, &mut tracker)
+        .map_err(|e| FileError::from_io(&path.to_string_lossy(), "delete", &e))?;
+    tracker.finish();
+    Ok(())
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+async fn run_blocking<T, F>(job: F) -> Result<T, FileError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, FileError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(job)
+        .await
+        .map_err(|e| FileError::new("", "io", e.to_string()))?
+}
+
+fn progress_emitter(app: &AppHandle) -> impl FnMut(&OperationProgress) + '_
+{
+    move |progress| {
+        let _ = app.emit("file-operation-progress", progress.clone());
+    }
+}
+
+fn ensure_absent(path: &Path) -> Result<(), FileError>
+{
+    if fs::symlink_metadata(path).is_ok()
+    {
+        let display = path.to_string_lossy();
+        return Err(FileError::new(&display, "already-exists", format!("'{}' already exists", display)));
+    }
+    Ok(())
+}
+
+/// Checks a copy or move before anything is written: the source must
+/// exist, a folder cannot go inside itself, and an existing destination
+/// is either a conflict or, with `overwrite`, renamed aside until the
+/// new one is in place. Returns where it was put.
+fn prepare_destination(source: &Path, destination: &Path, overwrite: bool) -> Result<Option<PathBuf>, FileError>
+{
+    let source_display = source.to_string_lossy();
+    let metadata = fs::symlink_metadata(source)
+        .map_err(|e| FileError::from_io(&source_display, "read", &e))?;
+
+    if metadata.is_dir() && is_within(destination, source)
+    {
+        let display = destination.to_string_lossy();
+        return Err(FileError::new(&display, "invalid-destination", format!(
+            "Cannot copy or move '{}' into itself", source_display
+        )));
+    }
+
+    if fs::symlink_metadata(destination).is_err()
+    {
+        return Ok(None);
+    }
+    if !overwrite || same_file(source, destination)
+    {
+        return ensure_absent(destination).map(|_| None);
+    }
+
+    let aside = aside_path(destination);
+    fs::rename(destination, &aside)
+        .map_err(|e| FileError::from_io(&destination.to_string_lossy(), "replace", &e))?;
+    Ok(Some(aside))
+}
+
+/// Renames `source` into place, or copies it when it lives on another
+/// device. `copied` tells the caller the source still has to be removed.
+fn place_moved(
+    source: &Path,
+    destination: &Path,
+    copied: &mut bool,
+    report: &mut dyn FnMut(&OperationProgress),
+) -> Result<(), FileError>
+{
+    if let Some(parent) = destination.parent()
+    {
+        fs::create_dir_all(parent).map_err(|e| FileError::from_io(&parent.to_string_lossy(), "create folder", &e))?;
+    }
+
+    match fs::rename(source, destination)
+    {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            let mut tracker = ProgressTracker::new("move", source, report)?;
+            copy_tree(source, destination, &mut tracker)
+                .map_err(|e| FileError::from_io(&destination.to_string_lossy(), "move", &e))?;
+            tracker.finish();
+            *copied = true;
+            Ok(())
+        },
+        Err(e) => Err(FileError::from_io(&source.to_string_lossy(), "move", &e)),
+    }
+}
+
+/// Finishes a copy or move. On success a set-aside old destination is
+/// deleted. On failure any partial copy is removed and the old
+/// destination, if there was one, is put back.
+fn settle(destination: &Path, set_aside: Option<PathBuf>, result: Result<(), FileError>) -> Result<(), FileError>
+{
+    if result.is_ok()
+    {
+        if let Some(aside) = set_aside
+        {
+            if let Err(e) = remove_tree(&aside, &mut ProgressTracker::silent())
+            {
+                eprintln!("Failed to delete replaced '{}': {}", aside.display(), e);
+            }
+        }
+        return result;
+    }
+
+    // The destination was free or set aside beforehand, so whatever is
+    // there now is ours
+    if fs::symlink_metadata(destination).is_ok()
+    {
+        if let Err(e) = remove_tree(destination, &mut ProgressTracker::silent())
+        {
+            eprintln!("Failed to clean up partial '{}': {}", destination.display(), e);
+        }
+    }
+    if let Some(aside) = set_aside
+    {
+        if let Err(e) = fs::rename(&aside, destination)
+        {
+            eprintln!("Failed to restore '{}' from '{}': {}", destination.display(), aside.display(), e);
+        }
+    }
+    result
+}
+
+/// A free hidden sibling of `path`, so setting it aside is a rename
+/// within the same folder.
+fn aside_path(path: &Path) -> PathBuf
+{
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut counter = 0;
+    loop
+    {
+        let candidate = parent.join(format!(".{}.notemac-replaced-{}-{}", name, std::process::id(), counter));
+        if fs::symlink_metadata(&candidate).is_err()
+        {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Whether `path` is `ancestor` or lies beneath it, comparing canonical
+/// forms of the closest existing parents.
+fn is_within(path: &Path, ancestor: &Path) -> bool
+{
+    let Ok(ancestor) = ancestor.canonicalize() else { return false };
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+    while existing.canonicalize().is_err()
+    {
+        match (existing.file_name().map(|n| n.to_os_string()), existing.parent())
+        {
+            (Some(name), Some(parent)) => {
+                rest.push(name);
+                existing = parent.to_path_buf();
+            },
+            _ => return false,
+        }
+    }
+
+    let mut resolved = existing.canonicalize().unwrap_or(existing);
+    resolved.extend(rest.iter().rev());
+    resolved.starts_with(&ancestor)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool
+{
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Copies links as links and files with their permissions.
+fn copy_tree(source: &Path, destination: &Path, tracker: &mut ProgressTracker) -> io::Result<()>
+{
+    let metadata = fs::symlink_metadata(source)?;
+    tracker.start_item(source);
+
+    if metadata.file_type().is_symlink()
+    {
+        copy_symlink(source, destination)?;
+    }
+    else if metadata.is_dir()
+    {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)?
+        {
+            let entry = entry?;
+            copy_tree(&entry.path(), &destination.join(entry.file_name()), tracker)?;
+        }
+        fs::set_permissions(destination, metadata.permissions())?;
+    }
+    else
+    {
+        if let Some(parent) = destination.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source, destination)?;
+    }
+
+    tracker.finish_item(&metadata);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> io::Result<()>
+{
+    std::os::unix::fs::symlink(fs::read_link(source)?, destination)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, destination: &Path) -> io::Result<()>
+{
+    fs::copy(source, destination).map(|_| ())
+}
+
+/// Deletes children before their folder so progress advances per item.
+fn remove_tree(path: &Path, tracker: &mut ProgressTracker) -> io::Result<()>
+{
+    let metadata = fs::symlink_metadata(path)?;
+    tracker.start_item(path);
+
+    if metadata.is_dir()
+    {
+        for entry in fs::read_dir(path)?
+        {
+            remove_tree(&entry?.path(), tracker)?;
+        }
+        fs::remove_dir(path)?;
+    }
+    else
+    {
+        fs::remove_file(path)?;
+    }
+
+    tracker.finish_item(&metadata);
+    Ok(())
+}
+
+/// "notes.txt" → "notes copy.txt", then "notes copy 2.txt", and so on.
+fn unique_copy_path(path: &Path) -> PathBuf
+{
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let is_dir = path.is_dir();
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let (stem, extension) = match (is_dir, name.rfind('.'))
+    {
+        (false, Some(dot)) if dot > 0 => (name[..dot].to_string(), name[dot..].to_string()),
+        _ => (name.clone(), String::new()),
+    };
+
+    let mut counter = 1;
+    loop
+    {
+        let suffix = if counter == 1 { " copy".to_string() } else { format!(" copy {}", counter) };
+        let candidate = parent.join(format!("{}{}{}", stem, suffix, extension));
+        if fs::symlink_metadata(&candidate).is_err()
+        {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Counts items and bytes under `path` without following links.
+fn measure(path: &Path) -> io::Result<(u64, u64)>
+{
+    let metadata = fs::symlink_metadata(path)?;
+    let mut totals = (1, if metadata.is_file() { metadata.len() } else { 0 });
+    if metadata.is_dir()
+    {
+        for entry in fs::read_dir(path)?
+        {
+            let (items, bytes) = measure(&entry?.path())?;
+            totals.0 += items;
+            totals.1 += bytes;
+        }
+    }
+    Ok(totals)
+}
+
+impl<'a> ProgressTracker<'a>
+{
+    fn new(operation: &str, source: &Path, report: &'a mut dyn FnMut(&OperationProgress)) -> Result<Self, FileError>
+    {
+        let display = source.to_string_lossy();
+        let (items_total, bytes_total) = measure(source)
+            .map_err(|e| FileError::from_io(&display, "read", &e))?;
+
+        Ok(ProgressTracker {
+            progress: OperationProgress {
+                operation: operation.to_string(),
+                source: display.into_owned(),
+                items_total,
+                bytes_total,
+                ..Default::default()
+            },
+            last_report: None,
+            report: Some(report),
+        })
+    }
+
+    /// For steps whose progress is already covered by another tracker.
+    fn silent() -> ProgressTracker<'static>
+    {
+        ProgressTracker {
+            progress: OperationProgress::default(),
+            last_report: None,
+            report: None,
+        }
+    }
+
+    fn start_item(&mut self, path: &Path)
+    {
+        self.progress.current_path = path.to_string_lossy().into_owned();
+    }
+
+    fn finish_item(&mut self, metadata: &fs::Metadata)
+    {
+        self.progress.items_done += 1;
+        if metadata.is_file()
+        {
+            self.progress.bytes_done += metadata.len();
+        }
+
+        if self.last_report.is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL)
+        {
+            self.last_report = Some(Instant::now());
+            self.emit();
+        }
+    }
+
+    /// Always reports the final state.
+    fn finish(&mut self)
+    {
+        self.emit();
+    }
+
+    fn emit(&mut self)
+    {
+        if let Some(report) = self.report.as_mut()
+        {
+            report(&self.progress);
+        }
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn no_progress() -> impl FnMut(&OperationProgress)
+    {
+        |_| {}
+    }
+
+    fn sample_tree(root: &Path) -> PathBuf
+    {
+        let project = root.join("project");
+        fs::create_dir_all(project.join("src/nested")).unwrap();
+        fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(project.join("src/nested/lib.rs"), "pub fn x() {}").unwrap();
+        fs::write(project.join("README.md"), "# Project").unwrap();
+        project
+    }
+
+    // ── create ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn create_file_and_dir()
+    {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("new/inner/file.txt");
+        let folder = dir.path().join("folder");
+
+        create_file(file.to_string_lossy().into_owned()).await.unwrap();
+        create_dir(folder.to_string_lossy().into_owned()).await.unwrap();
+
+        assert_eq!(fs::read(&file).unwrap(), b"");
+        assert!(folder.is_dir());
+    }
+
+    #[tokio::test]
+    async fn create_reports_conflicts()
+    {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("taken.txt");
+        fs::write(&file, "keep").unwrap();
+
+        let err = create_file(file.to_string_lossy().into_owned()).await.unwrap_err();
+        assert_eq!(err.kind, "already-exists");
+        let err = create_dir(file.to_string_lossy().into_owned()).await.unwrap_err();
+        assert_eq!(err.kind, "already-exists");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "keep");
+    }
+
+    // ── copy / move ─────────────────────────────────────────────
+
+    #[test]
+    fn copy_is_recursive_and_reports_progress()
+    {
+        let dir = TempDir::new().unwrap();
+        let project = sample_tree(dir.path());
+        let target = dir.path().join("backup");
+
+        let mut reports = Vec::new();
+        copy_to(&project, &target, false, &mut |p: &OperationProgress| reports.push(p.clone())).unwrap();
+
+        assert_eq!(fs::read_to_string(target.join("src/nested/lib.rs")).unwrap(), "pub fn x() {}");
+        assert!(project.join("src/main.rs").exists());
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.operation, "copy");
+        assert_eq!((last.items_done, last.items_total), (6, 6));
+        assert_eq!(last.bytes_done, last.bytes_total);
+    }
+
+    #[test]
+    fn copy_conflict_unless_overwrite()
+    {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("a.txt");
+        let target = dir.path().join("b.txt");
+        fs::write(&source, "new").unwrap();
+        fs::write(&target, "old").unwrap();
+
+        let err = copy_to(&source, &target, false, &mut no_progress()).unwrap_err();
+        assert_eq!(err.kind, "already-exists");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+
+        copy_to(&source, &target, true, &mut no_progress()).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_overwrite_keeps_the_old_destination()
+    {
+        let dir = TempDir::new().unwrap();
+        let source = sample_tree(dir.path());
+        // A socket cannot be copied, so the copy fails partway through
+        let _socket = std::os::unix::net::UnixListener::bind(source.join("src/zz.sock")).unwrap();
+        let target = dir.path().join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), "old").unwrap();
+
+        assert!(copy_to(&source, &target, true, &mut no_progress()).is_err());
+        assert_eq!(fs::read_to_string(target.join("keep.txt")).unwrap(), "old");
+        assert!(!target.join("src").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_copy_leaves_no_partial_destination()
+    {
+        let dir = TempDir::new().unwrap();
+        let source = sample_tree(dir.path());
+        let _socket = std::os::unix::net::UnixListener::bind(source.join("src/zz.sock")).unwrap();
+        let target = dir.path().join("target");
+
+        assert!(copy_to(&source, &target, false, &mut no_progress()).is_err());
+        assert!(!target.exists());
+        assert!(duplicate(&source, &mut no_progress()).is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn copy_into_itself_is_rejected()
+    {
+        let dir = TempDir::new().unwrap();
+        let project = sample_tree(dir.path());
+
+        let err = copy_to(&project, &project.join("src/copy"), false, &mut no_progress()).unwrap_err();
+        assert_eq!(err.kind, "invalid-destination");
+        assert!(!project.join("src/copy").exists());
+    }
+
+    #[test]
+    fn move_renames_tree()
+    {
+        let dir = TempDir::new().unwrap();
+        let project = sample_tree(dir.path());
+        let target = dir.path().join("archive/project");
+
+        move_to(&project, &target, false, &mut no_progress()).unwrap();
+        assert!(!project.exists());
+        assert_eq!(fs::read_to_string(target.join("README.md")).unwrap(), "# Project");
+    }
+
+    #[test]
+    fn move_missing_source_is_not_found()
+    {
+        let dir = TempDir::new().unwrap();
+        let err = move_to(&dir.path().join("nope"), &dir.path().join("x"), false, &mut no_progress()).unwrap_err();
+        assert_eq!(err.kind, "not-found");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_keeps_symlinks_as_links()
+    {
+        let dir = TempDir::new().unwrap();
+        let project = sample_tree(dir.path());
+        std::os::unix::fs::symlink("README.md", project.join("link.md")).unwrap();
+
+        let target = dir.path().join("copy");
+        copy_to(&project, &target, false, &mut no_progress()).unwrap();
+        assert_eq!(fs::read_link(target.join("link.md")).unwrap(), Path::new("README.md"));
+    }
+
+    // ── duplicate / delete ──────────────────────────────────────
+
+    #[test]
+    fn duplicate_picks_unique_names()
+    {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "x").unwrap();
+
+        let first = duplicate(&file, &mut no_progress()).unwrap();
+        let second = duplicate(&file, &mut no_progress()).unwrap();
+        assert_eq!(first.file_name().unwrap(), "notes copy.txt");
+        assert_eq!(second.file_name().unwrap(), "notes copy 2.txt");
+
+        let project = sample_tree(dir.path());
+        let copy = duplicate(&project, &mut no_progress()).unwrap();
+        assert_eq!(copy.file_name().unwrap(), "project copy");
+        assert!(copy.join("src/main.rs").exists());
+    }
+
+    #[test]
+    fn delete_removes_tree_with_progress()
+    {
+        let dir = TempDir::new().unwrap();
+        let project = sample_tree(dir.path());
+
+        let mut last = OperationProgress::default();
+        delete(&project, &mut |p: &OperationProgress| last = p.clone()).unwrap();
+
+        assert!(!project.exists());
+        assert_eq!(last.operation, "delete");
+        assert_eq!(last.items_done, 6);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn delete_does_not_follow_symlinks()
+    {
+        let dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("precious.txt"), "x").unwrap();
+        let folder = dir.path().join("folder");
+        fs::create_dir(&folder).unwrap();
+        std::os::unix::fs::symlink(outside.path(), folder.join("link")).unwrap();
+
+        delete(&folder, &mut no_progress()).unwrap();
+        assert!(outside.path().join("precious.txt").exists());
+    }
+}
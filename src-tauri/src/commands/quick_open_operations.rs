@@ -0,0 +1,649 @@
+use ignore::WalkState;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use super::search_operations::{SearchQuery, walk_files};
+use super::tree_operations::{IgnoreStack, TreeFilter, TreeSettings, current_settings};
+use super::watcher_operations::workspace_root;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 1000;
+
+// Fuzzy scoring weights
+const SCORE_MATCH: i64 = 16;
+const BONUS_SEPARATOR: i64 = 10;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 5;
+/// Awarded when a term matches entirely within the file name.
+const BONUS_FILE_NAME: i64 = 24;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// The workspace's file paths, relative to its root.
+static INDEX: OnceLock<RwLock<PathIndex>> = OnceLock::new();
+
+/// Cancel flag of the build in progress, if any.
+static CURRENT_BUILD: OnceLock<Mutex<Option<Arc<AtomicBool>>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct QuickOpenMatch
+{
+    pub path: String,
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+    pub score: i64,
+    /// Character indices into `relativePath` that matched the query.
+    pub highlights: Vec<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct QuickOpenResult
+{
+    pub matches: Vec<QuickOpenMatch>,
+    /// True while the index is still being built; results may be partial.
+    pub indexing: bool,
+    #[serde(rename = "indexedFiles")]
+    pub indexed_files: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct IndexReadyPayload
+{
+    root: String,
+    #[serde(rename = "fileCount")]
+    file_count: usize,
+    #[serde(rename = "elapsedMs")]
+    elapsed_ms: u64,
+}
+
+#[derive(Default)]
+struct PathIndex
+{
+    root: Option<PathBuf>,
+    /// Relative path ('/'-separated) → its lowercase form.
+    paths: BTreeMap<String, String>,
+    ready: bool,
+    /// Changes seen while a build runs, replayed once it finishes.
+    pending: Vec<(PathBuf, bool)>,
+}
+
+/// One watcher change, worked out before the index is locked.
+enum IndexUpdate
+{
+    /// A file or folder (and everything below it) is gone.
+    Remove(String),
+    /// Relative paths and their lowercase forms.
+    Add(Vec<(String, String)>),
+    Nothing,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Fuzzy-ranks the workspace's files against `query`. Whitespace splits
+/// the query into terms that must all match.
+#[tauri::command]
+pub async fn quick_open(query: String, limit: Option<usize>) -> Result<QuickOpenResult, String>
+{
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let index = index_lock().read().map_err(|_| "Quick open index lock poisoned".to_string())?;
+
+    let root = match &index.root
+    {
+        Some(root) => root,
+        None => return Ok(QuickOpenResult { matches: Vec::new(), indexing: false, indexed_files: 0 }),
+    };
+
+    Ok(QuickOpenResult {
+        matches: rank(&index.paths, root, &query, limit),
+        indexing: !index.ready,
+        indexed_files: index.paths.len(),
+    })
+}
+
+/// Rebuilds the index for the current workspace, e.g. after the tree's
+/// ignore settings changed.
+#[tauri::command]
+pub async fn refresh_quick_open_index(app: AppHandle) -> Result<(), String>
+{
+    let root = workspace_root().ok_or_else(|| "No workspace folder is open".to_string())?;
+    index_workspace(&app, &root);
+    Ok(())
+}
+
+// ─── Indexing ───────────────────────────────────────────────────
+
+/// Starts indexing `root` in the background, replacing any previous
+/// workspace. Emits `quick-open-index-ready` when done.
+pub fn index_workspace(app: &AppHandle, root: &Path)
+{
+    let cancel = start_build(root);
+    let app = app.clone();
+    let root = root.to_path_buf();
+
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        let Some(file_count) = build_index(&root, &current_settings(), &cancel)
+        else
+        {
+            return;
+        };
+
+        let _ = app.emit("quick-open-index-ready", IndexReadyPayload {
+            root: root.to_string_lossy().into_owned(),
+            file_count,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    });
+}
+
+/// Resets the index to an empty, not-yet-ready state for `root` and
+/// cancels any build still running for a previous workspace.
+fn start_build(root: &Path) -> Arc<AtomicBool>
+{
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Ok(mut current) = build_lock().lock()
+    {
+        if let Some(previous) = current.replace(cancel.clone())
+        {
+            previous.store(true, Ordering::SeqCst);
+        }
+    }
+
+    if let Ok(mut index) = index_lock().write()
+    {
+        *index = PathIndex { root: Some(root.to_path_buf()), ..PathIndex::default() };
+    }
+    cancel
+}
+
+/// Walks `root` and installs the result, unless cancelled or superseded
+/// by another workspace. Returns the number of files indexed.
+fn build_index(root: &Path, settings: &TreeSettings, cancel: &AtomicBool) -> Option<usize>
+{
+    let found = Mutex::new(BTreeMap::new());
+    let walked = walk_files(root, &SearchQuery::default(), settings, cancel, |path| {
+        if let (Some(relative), Ok(mut found)) = (relative_path(root, path), found.lock())
+        {
+            let lower = relative.to_lowercase();
+            found.insert(relative, lower);
+        }
+        WalkState::Continue
+    });
+
+    if walked.is_err() || cancel.load(Ordering::SeqCst)
+    {
+        return None;
+    }
+
+    // Changes seen during the walk are replayed onto the result before it
+    // is installed, so the lock is only held to swap it in
+    let mut paths = found.into_inner().ok()?;
+    loop
+    {
+        let pending = {
+            let mut index = index_lock().write().ok()?;
+            if index.root.as_deref() != Some(root)
+            {
+                return None;
+            }
+            if index.pending.is_empty()
+            {
+                index.paths = paths;
+                index.ready = true;
+                return Some(index.paths.len());
+            }
+            std::mem::take(&mut index.pending)
+        };
+
+        for (path, exists) in pending
+        {
+            scan_change(root, &path, exists).apply(&mut paths);
+        }
+    }
+}
+
+/// Keeps the index in step with the file watcher. `exists` is false for
+/// removals; a created folder is walked so files moved in with it appear.
+pub fn apply_workspace_change(path: &Path, exists: bool)
+{
+    let Some(root) = index_lock().read().ok().and_then(|index| index.root.clone())
+    else
+    {
+        return;
+    };
+    // Walking a created folder can take a while; searches keep reading
+    // the index meanwhile
+    let update = scan_change(&root, path, exists);
+
+    let Ok(mut index) = index_lock().write()
+    else
+    {
+        return;
+    };
+    if index.root.as_deref() != Some(root.as_path())
+    {
+        return;
+    }
+    if !index.ready
+    {
+        index.pending.push((path.to_path_buf(), exists));
+        return;
+    }
+    update.apply(&mut index.paths);
+}
+
+/// Whether the workspace index lists the file at `path`.
+pub fn is_indexed(path: &Path) -> bool
+{
+    let Ok(index) = index_lock().read()
+    else
+    {
+        return false;
+    };
+    index.root.as_deref()
+        .and_then(|root| relative_path(root, path))
+        .is_some_and(|relative| index.paths.contains_key(&relative))
+}
+
+/// Works out how one watcher change alters the index, without touching it.
+fn scan_change(root: &Path, path: &Path, exists: bool) -> IndexUpdate
+{
+    let Some(relative) = relative_path(root, path)
+    else
+    {
+        return IndexUpdate::Nothing;
+    };
+
+    if !exists
+    {
+        return IndexUpdate::Remove(relative);
+    }
+
+    let Ok(metadata) = fs::metadata(path)
+    else
+    {
+        return IndexUpdate::Nothing;
+    };
+    let settings = TreeSettings { include: Vec::new(), ..current_settings() };
+    if !is_visible(root, path, metadata.is_dir(), &settings)
+    {
+        return IndexUpdate::Nothing;
+    }
+
+    if metadata.is_file()
+    {
+        let lower = relative.to_lowercase();
+        return IndexUpdate::Add(vec![(relative, lower)]);
+    }
+
+    let added = Mutex::new(Vec::new());
+    let _ = walk_files(path, &SearchQuery::default(), &settings, &AtomicBool::new(false), |file| {
+        if let (Some(relative), Ok(mut added)) = (relative_path(root, file), added.lock())
+        {
+            let lower = relative.to_lowercase();
+            added.push((relative, lower));
+        }
+        WalkState::Continue
+    });
+    IndexUpdate::Add(added.into_inner().unwrap_or_default())
+}
+
+impl IndexUpdate
+{
+    fn apply(self, paths: &mut BTreeMap<String, String>)
+    {
+        match self
+        {
+            IndexUpdate::Remove(relative) => {
+                let prefix = format!("{}/", relative);
+                paths.retain(|key, _| *key != relative && !key.starts_with(&prefix));
+            },
+            IndexUpdate::Add(added) => paths.extend(added),
+            IndexUpdate::Nothing => {},
+        }
+    }
+}
+
+/// Whether the tree would show `path`: it and every folder above it up
+/// to `root` must pass the filters and ignore files.
+fn is_visible(root: &Path, path: &Path, is_dir: bool, settings: &TreeSettings) -> bool
+{
+    let Ok(filter) = TreeFilter::new(settings).map(|filter| filter.within(root))
+    else
+    {
+        return true;
+    };
+    let Ok(relative) = path.strip_prefix(root)
+    else
+    {
+        return false;
+    };
+
+    let mut ignores = IgnoreStack::for_dir(root);
+    let mut current = root.to_path_buf();
+    let parts: Vec<_> = relative.components().collect();
+    for (i, part) in parts.iter().enumerate()
+    {
+        current.push(part);
+        let last = i + 1 == parts.len();
+        let name = part.as_os_str().to_string_lossy();
+        if !filter.allows(&current, &name, !last || is_dir, &ignores)
+        {
+            return false;
+        }
+        if !last
+        {
+            ignores = ignores.child(&current);
+        }
+    }
+    true
+}
+
+// ─── Ranking ────────────────────────────────────────────────────
+
+/// The best `limit` matches, by score, then shorter path, then name.
+fn rank(paths: &BTreeMap<String, String>, root: &Path, query: &str, limit: usize) -> Vec<QuickOpenMatch>
+{
+    let terms: Vec<Vec<char>> = query.split_whitespace()
+        .map(|term| term.to_lowercase().chars().collect())
+        .collect();
+
+    let to_match = |relative: &String, score: i64, highlights: Vec<usize>| QuickOpenMatch {
+        path: root.join(relative).to_string_lossy().into_owned(),
+        relative_path: relative.clone(),
+        score,
+        highlights,
+    };
+
+    if terms.is_empty()
+    {
+        return paths.keys().take(limit).map(|relative| to_match(relative, 0, Vec::new())).collect();
+    }
+
+    let mut scored: Vec<(i64, &String, Vec<usize>)> = paths.iter()
+        .filter_map(|(relative, lower)| {
+            score_terms(&terms, relative, lower).map(|(score, highlights)| (score, relative, highlights))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.len().cmp(&b.1.len()))
+            .then_with(|| a.1.cmp(b.1))
+    });
+    scored.truncate(limit);
+
+    scored.into_iter().map(|(score, relative, highlights)| to_match(relative, score, highlights)).collect()
+}
+
+/// Every term must match; scores add up and highlights are merged.
+fn score_terms(terms: &[Vec<char>], candidate: &str, lower: &str) -> Option<(i64, Vec<usize>)>
+{
+    let original: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = lower.chars().collect();
+    // Lowercasing can change the length (e.g. 'İ'); fall back to char-wise folding
+    let folded = if folded.len() == original.len()
+    {
+        folded
+    }
+    else
+    {
+        original.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect()
+    };
+
+    let mut total = 0;
+    let mut highlights = Vec::new();
+    for term in terms
+    {
+        let (score, positions) = fuzzy_match(term, &original, &folded)?;
+        total += score;
+        highlights.extend(positions);
+    }
+
+    highlights.sort_unstable();
+    highlights.dedup();
+    Some((total, highlights))
+}
+
+/// Matches `term` (lowercase) as a subsequence of the candidate. A match
+/// inside the file name is preferred over one spread across folders.
+pub fn fuzzy_match(term: &[char], original: &[char], folded: &[char]) -> Option<(i64, Vec<usize>)>
+{
+    let name_start = folded.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+
+    if let Some(positions) = tightest_window(term, folded, name_start)
+    {
+        return Some((score_positions(&positions, original) + BONUS_FILE_NAME, positions));
+    }
+    tightest_window(term, folded, 0).map(|positions| (score_positions(&positions, original), positions))
+}
+
+/// Finds the first complete match at or after `from`, then walks back
+/// from its end to the latest possible start, which keeps the matched
+/// characters as close together as a single pass allows.
+fn tightest_window(term: &[char], folded: &[char], from: usize) -> Option<Vec<usize>>
+{
+    if term.is_empty()
+    {
+        return Some(Vec::new());
+    }
+
+    let mut next = 0;
+    let mut end = None;
+    for (i, &c) in folded.iter().enumerate().skip(from)
+    {
+        if c == term[next]
+        {
+            next += 1;
+            if next == term.len()
+            {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    let mut positions = Vec::with_capacity(term.len());
+    let mut remaining = term.len();
+    for i in (from..=end).rev()
+    {
+        if folded[i] == term[remaining - 1]
+        {
+            positions.push(i);
+            remaining -= 1;
+            if remaining == 0
+            {
+                break;
+            }
+        }
+    }
+
+    positions.reverse();
+    Some(positions)
+}
+
+fn score_positions(positions: &[usize], original: &[char]) -> i64
+{
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &i in positions
+    {
+        score += SCORE_MATCH;
+
+        let before = if i == 0 { None } else { Some(original[i - 1]) };
+        score += match before
+        {
+            None | Some('/') | Some('\\') => BONUS_SEPARATOR,
+            Some('_') | Some('-') | Some('.') | Some(' ') => BONUS_BOUNDARY,
+            Some(b) if b.is_lowercase() && original[i].is_uppercase() => BONUS_CAMEL_CASE,
+            _ => 0,
+        };
+
+        if let Some(previous) = previous
+        {
+            let gap = (i - previous - 1) as i64;
+            if gap == 0
+            {
+                score += BONUS_CONSECUTIVE;
+            }
+            else
+            {
+                score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (gap - 1);
+            }
+        }
+        previous = Some(i);
+    }
+
+    score
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn index_lock() -> &'static RwLock<PathIndex>
+{
+    INDEX.get_or_init(|| RwLock::new(PathIndex::default()))
+}
+
+fn build_lock() -> &'static Mutex<Option<Arc<AtomicBool>>>
+{
+    CURRENT_BUILD.get_or_init(|| Mutex::new(None))
+}
+
+/// `path` relative to `root` with '/' separators, on every platform.
+fn relative_path(root: &Path, path: &Path) -> Option<String>
+{
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty()
+    {
+        return None;
+    }
+
+    let parts: Vec<String> = relative.components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("/"))
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn index_of(paths: &[&str]) -> BTreeMap<String, String>
+    {
+        paths.iter().map(|p| (p.to_string(), p.to_lowercase())).collect()
+    }
+
+    fn ranked(paths: &[&str], query: &str) -> Vec<String>
+    {
+        rank(&index_of(paths), Path::new("/ws"), query, 10)
+            .into_iter()
+            .map(|m| m.relative_path)
+            .collect()
+    }
+
+    // ── Matching ────────────────────────────────────────────────
+
+    #[test]
+    fn matches_subsequences_case_insensitively()
+    {
+        let candidate: Vec<char> = "src/FileTree.tsx".chars().collect();
+        let folded: Vec<char> = "src/filetree.tsx".chars().collect();
+        let term: Vec<char> = "ftree".chars().collect();
+
+        let (_, positions) = fuzzy_match(&term, &candidate, &folded).unwrap();
+        assert_eq!(positions, vec![4, 8, 9, 10, 11]);
+        assert!(fuzzy_match(&"xyz".chars().collect::<Vec<_>>(), &candidate, &folded).is_none());
+    }
+
+    #[test]
+    fn prefers_file_name_and_word_boundary_matches()
+    {
+        let paths = ["docs/mainframe/notes.md", "src/main.rs", "src/domain/admin.rs"];
+        assert_eq!(ranked(&paths, "main")[0], "src/main.rs");
+
+        let paths = ["src/components/FileTree.tsx", "src/filters/tree_utils.ts"];
+        assert_eq!(ranked(&paths, "ft")[0], "src/components/FileTree.tsx");
+    }
+
+    #[test]
+    fn all_terms_must_match_and_highlights_merge()
+    {
+        let matches = rank(&index_of(&["src/app/main.rs", "src/lib.rs"]), Path::new("/ws"), "app main", 10);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, Path::new("/ws").join("src/app/main.rs").to_string_lossy());
+        assert_eq!(matches[0].highlights, vec![4, 5, 6, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn shorter_paths_break_ties()
+    {
+        assert_eq!(ranked(&["b/deep/util.rs", "a/util.rs"], "util.rs"), vec!["a/util.rs", "b/deep/util.rs"]);
+    }
+
+    #[test]
+    fn empty_query_lists_paths_up_to_limit()
+    {
+        let matches = rank(&index_of(&["a", "b", "c"]), Path::new("/ws"), "  ", 2);
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[test]
+    fn ranks_fifty_thousand_paths_quickly()
+    {
+        let paths: BTreeMap<String, String> = (0..50_000)
+            .map(|i| {
+                let path = format!("packages/pkg{}/src/module{}/file_{}.ts", i % 97, i % 13, i);
+                let lower = path.clone();
+                (path, lower)
+            })
+            .collect();
+
+        // About 30 ms optimized; unoptimized test builds run ten times slower
+        let budget = if cfg!(debug_assertions) { 1000 } else { 100 };
+        let started = Instant::now();
+        let matches = rank(&paths, Path::new("/ws"), "pkg12 file_4999", 20);
+        assert!(!matches.is_empty());
+        assert!(started.elapsed().as_millis() < budget);
+    }
+
+    // ── Index maintenance ───────────────────────────────────────
+
+    #[test]
+    fn visibility_checks_every_ancestor()
+    {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("src/gen")).unwrap();
+        fs::write(root.join(".gitignore"), "gen/\n").unwrap();
+        let settings = TreeSettings::default();
+
+        assert!(is_visible(root, &root.join("src/main.rs"), false, &settings));
+        assert!(!is_visible(root, &root.join("node_modules/pkg/index.js"), false, &settings));
+        assert!(!is_visible(root, &root.join("src/gen/out.rs"), false, &settings));
+    }
+
+    #[test]
+    fn relative_paths_use_forward_slashes()
+    {
+        let root = Path::new("/ws");
+        assert_eq!(relative_path(root, &root.join("src").join("main.rs")).as_deref(), Some("src/main.rs"));
+        assert_eq!(relative_path(root, root), None);
+        assert_eq!(relative_path(root, Path::new("/elsewhere/file")), None);
+    }
+}
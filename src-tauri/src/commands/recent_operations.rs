@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::reserve;
+use super::save_operations::atomic_write;
+
+const RECENT_FILE_NAME: &str = "recent.json";
+/// Notepad++ defaults to 10 entries.
+const DEFAULT_MAX_ENTRIES: usize = 10;
+const MAX_ENTRIES_LIMIT: usize = 50;
+
+static RECENT: OnceLock<Mutex<RecentState>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind
+{
+    File,
+    Folder,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentEntry
+{
+    pub path: String,
+    /// Milliseconds since the Unix epoch.
+    #[serde(rename = "openedAt")]
+    pub opened_at: u64,
+}
+
+/// Most recently used files and folders, newest first. This is also the
+/// on-disk format of `recent.json` in the config dir.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RecentList
+{
+    #[serde(rename = "maxFiles")]
+    pub max_files: usize,
+    #[serde(rename = "maxFolders")]
+    pub max_folders: usize,
+    pub files: Vec<RecentEntry>,
+    pub folders: Vec<RecentEntry>,
+}
+
+#[derive(Default)]
+struct RecentState
+{
+    list: RecentList,
+    /// `None` until loaded (and in tests): changes stay in memory.
+    store: Option<PathBuf>,
+}
+
+impl Default for RecentList
+{
+    fn default() -> Self
+    {
+        RecentList {
+            max_files: DEFAULT_MAX_ENTRIES,
+            max_folders: DEFAULT_MAX_ENTRIES,
+            files: Vec::new(),
+            folders: Vec::new(),
+        }
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// The recent lists, without entries that no longer exist.
+#[tauri::command]
+pub async fn list_recent(app: AppHandle) -> Result<RecentList, String>
+{
+    Ok(recent_entries(&app))
+}
+
+/// Forgets recent files, recent folders, or both when `kind` is omitted.
+#[tauri::command]
+pub async fn clear_recent(app: AppHandle, kind: Option<RecentKind>) -> Result<(), String>
+{
+    update(&app, |list| list.clear(kind));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_recent(app: AppHandle, path: String) -> Result<(), String>
+{
+    update(&app, |list| list.remove(&path));
+    Ok(())
+}
+
+/// Sets how many files and folders are remembered (0 to 50); longer
+/// lists are cut down right away.
+#[tauri::command]
+pub async fn set_recent_limits(app: AppHandle, max_files: Option<usize>, max_folders: Option<usize>) -> Result<(), String>
+{
+    update(&app, |list| list.set_limits(max_files, max_folders));
+    Ok(())
+}
+
+// ─── Recording ──────────────────────────────────────────────────
+
+/// Loads the saved lists at startup, before the menu is built, and drops
+/// entries that have disappeared since the last run. The file is
+/// reserved: Open Recent grants whatever it lists, so the webview must not
+/// be able to plant entries through the config dir grant.
+pub fn load(config_dir: Option<PathBuf>)
+{
+    let Some(store) = config_dir.map(|dir| dir.join(RECENT_FILE_NAME))
+    else
+    {
+        return;
+    };
+    reserve(&store);
+
+    let mut list = match fs::read(&store)
+    {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("Ignoring unreadable recent list '{}': {}", store.display(), e);
+            RecentList::default()
+        }),
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound
+            {
+                eprintln!("Failed to read recent list '{}': {}", store.display(), e);
+            }
+            RecentList::default()
+        },
+    };
+
+    let pruned = list.prune_missing();
+    if let Ok(mut state) = state_lock().lock()
+    {
+        *state = RecentState { list, store: Some(store) };
+        if pruned
+        {
+            save(&state);
+        }
+    }
+}
+
+/// The lists as they are, without touching the disk. Used to build the menu.
+pub fn current() -> RecentList
+{
+    state_lock().lock().map(|state| state.list.clone()).unwrap_or_default()
+}
+
+/// Moves `path` to the top of its list. Called whenever a file or folder
+/// is opened.
+pub fn record_recent(app: &AppHandle, path: &str, kind: RecentKind)
+{
+    update(app, |list| list.record(path, kind, now_millis()));
+}
+
+/// The current lists, pruned of paths that have disappeared.
+pub fn recent_entries(app: &AppHandle) -> RecentList
+{
+    let mut pruned = false;
+    let list = match state_lock().lock()
+    {
+        Ok(mut state) => {
+            pruned = state.list.prune_missing();
+            if pruned
+            {
+                save(&state);
+            }
+            state.list.clone()
+        },
+        Err(_) => RecentList::default(),
+    };
+
+    if pruned
+    {
+        announce(app, &list);
+    }
+    list
+}
+
+/// Applies a change, then saves and announces the lists if it did anything.
+fn update<F>(app: &AppHandle, change: F)
+where
+    F: FnOnce(&mut RecentList),
+{
+    let list = {
+        let Ok(mut state) = state_lock().lock()
+        else
+        {
+            return;
+        };
+        let before = state.list.clone();
+        change(&mut state.list);
+        if state.list == before
+        {
+            return;
+        }
+        save(&state);
+        state.list.clone()
+    };
+    announce(app, &list);
+}
+
+/// Rebuilds the Open Recent menu and tells the frontend.
+fn announce(app: &AppHandle, list: &RecentList)
+{
+    crate::menu::refresh_open_recent(app, list);
+    let _ = app.emit("recent-changed", list.clone());
+}
+
+// ─── List ───────────────────────────────────────────────────────
+
+impl RecentList
+{
+    pub fn record(&mut self, path: &str, kind: RecentKind, opened_at: u64)
+    {
+        let max = self.max(kind);
+        let entries = self.entries_mut(kind);
+        entries.retain(|entry| entry.path != path);
+        entries.insert(0, RecentEntry { path: path.to_string(), opened_at });
+        entries.truncate(max);
+    }
+
+    pub fn remove(&mut self, path: &str)
+    {
+        self.files.retain(|entry| entry.path != path);
+        self.folders.retain(|entry| entry.path != path);
+    }
+
+    pub fn clear(&mut self, kind: Option<RecentKind>)
+    {
+        if kind != Some(RecentKind::Folder)
+        {
+            self.files.clear();
+        }
+        if kind != Some(RecentKind::File)
+        {
+            self.folders.clear();
+        }
+    }
+
+    pub fn set_limits(&mut self, max_files: Option<usize>, max_folders: Option<usize>)
+    {
+        if let Some(max) = max_files
+        {
+            self.max_files = max.min(MAX_ENTRIES_LIMIT);
+            self.files.truncate(self.max_files);
+        }
+        if let Some(max) = max_folders
+        {
+            self.max_folders = max.min(MAX_ENTRIES_LIMIT);
+            self.folders.truncate(self.max_folders);
+        }
+    }
+
+    /// Drops files and folders that no longer exist (or changed kind).
+    /// Returns whether anything was removed.
+    pub fn prune_missing(&mut self) -> bool
+    {
+        let before = self.files.len() + self.folders.len();
+        self.files.retain(|entry| Path::new(&entry.path).is_file());
+        self.folders.retain(|entry| Path::new(&entry.path).is_dir());
+        self.files.len() + self.folders.len() != before
+    }
+
+    fn max(&self, kind: RecentKind) -> usize
+    {
+        match kind
+        {
+            RecentKind::File => self.max_files,
+            RecentKind::Folder => self.max_folders,
+        }
+    }
+
+    fn entries_mut(&mut self, kind: RecentKind) -> &mut Vec<RecentEntry>
+    {
+        match kind
+        {
+            RecentKind::File => &mut self.files,
+            RecentKind::Folder => &mut self.folders,
+        }
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn state_lock() -> &'static Mutex<RecentState>
+{
+    RECENT.get_or_init(|| Mutex::new(RecentState::default()))
+}
+
+fn save(state: &RecentState)
+{
+    let Some(store) = &state.store
+    else
+    {
+        return;
+    };
+
+    let result = serde_json::to_vec_pretty(&state.list)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|json| {
+            if let Some(dir) = store.parent()
+            {
+                fs::create_dir_all(dir)?;
+            }
+            atomic_write(store, &json)
+        });
+    if let Err(e) = result
+    {
+        eprintln!("Failed to save recent list '{}': {}", store.display(), e);
+    }
+}
+
+fn now_millis() -> u64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn paths(entries: &[RecentEntry]) -> Vec<&str>
+    {
+        entries.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn recording_moves_entries_to_the_top_and_caps_the_list()
+    {
+        let mut list = RecentList { max_files: 3, ..RecentList::default() };
+        for (time, path) in ["a", "b", "c", "a", "d"].into_iter().enumerate()
+        {
+            list.record(path, RecentKind::File, time as u64);
+        }
+
+        assert_eq!(paths(&list.files), vec!["d", "a", "c"]);
+        assert_eq!(list.files[1].opened_at, 3);
+        assert!(list.folders.is_empty());
+    }
+
+    #[test]
+    fn limits_truncate_and_are_bounded()
+    {
+        let mut list = RecentList::default();
+        list.record("x", RecentKind::Folder, 0);
+        list.record("y", RecentKind::Folder, 1);
+
+        list.set_limits(Some(500), Some(1));
+        assert_eq!(list.max_files, MAX_ENTRIES_LIMIT);
+        assert_eq!(paths(&list.folders), vec!["y"]);
+
+        list.set_limits(None, Some(0));
+        assert!(list.folders.is_empty());
+    }
+
+    #[test]
+    fn clear_and_remove_affect_the_right_lists()
+    {
+        let mut list = RecentList::default();
+        list.record("f", RecentKind::File, 0);
+        list.record("d", RecentKind::Folder, 0);
+
+        list.clear(Some(RecentKind::Folder));
+        assert_eq!((list.files.len(), list.folders.len()), (1, 0));
+        list.remove("f");
+        assert!(list.files.is_empty());
+    }
+
+    #[test]
+    fn missing_paths_are_pruned()
+    {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("kept.txt");
+        fs::write(&file, "").unwrap();
+
+        let mut list = RecentList::default();
+        list.record(&dir.path().join("gone.txt").to_string_lossy(), RecentKind::File, 0);
+        list.record(&file.to_string_lossy(), RecentKind::File, 1);
+        // A file recorded as a folder does not count as that folder
+        list.record(&file.to_string_lossy(), RecentKind::Folder, 2);
+        list.record(&dir.path().to_string_lossy(), RecentKind::Folder, 3);
+
+        assert!(list.prune_missing());
+        assert_eq!(paths(&list.files), vec![file.to_string_lossy()]);
+        assert_eq!(paths(&list.folders), vec![dir.path().to_string_lossy()]);
+        assert!(!list.prune_missing());
+    }
+
+    #[test]
+    fn saved_list_round_trips()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut list = RecentList { max_folders: 4, ..RecentList::default() };
+        list.record("/some/file", RecentKind::File, 7);
+
+        let state = RecentState { list: list.clone(), store: Some(dir.path().join("config").join(RECENT_FILE_NAME)) };
+        save(&state);
+
+        let bytes = fs::read(dir.path().join("config").join(RECENT_FILE_NAME)).unwrap();
+        assert_eq!(serde_json::from_slice::<RecentList>(&bytes).unwrap(), list);
+    }
+}
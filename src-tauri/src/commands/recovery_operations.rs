@@ -0,0 +1,713 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use super::save_operations::{atomic_write, content_hash};
+
+const RECOVERY_DIR_NAME: &str = "recovery";
+/// One directory per running instance, named after its process ID. One
+/// whose owner no longer holds its lock belongs to a session that crashed.
+const SESSIONS_DIR_NAME: &str = "sessions";
+/// Prefixes of session directories still being set up or being adopted,
+/// followed by the ID of the process doing it.
+const CREATING_PREFIX: &str = ".new-";
+const ADOPTING_PREFIX: &str = ".adopt-";
+/// Kept locked by the owning process on Unix; elsewhere rewritten every
+/// interval as a heartbeat.
+const LOCK_NAME: &str = "session.lock";
+/// Prefix for the ID a restored buffer is kept under in this session until
+/// the frontend snapshots the reopened tab itself.
+const RESTORED_PREFIX: &str = "restored-";
+/// Snapshots left behind by crashed sessions, waiting to be restored.
+const RECOVERED_DIR_NAME: &str = "recovered";
+const MANIFEST_NAME: &str = "manifest.json";
+/// How often the frontend is asked for its dirty buffers, as in Notepad++.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(7);
+/// A session whose lock is older than this is presumed dead (non-Unix only).
+#[cfg(not(unix))]
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+
+static STORE: OnceLock<Mutex<Option<RecoveryStore>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// A dirty or untitled buffer as sent by the frontend.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct BufferSnapshot
+{
+    #[serde(rename = "bufferId")]
+    pub buffer_id: String,
+    /// `None` for untitled buffers.
+    pub path: Option<String>,
+    pub title: String,
+    /// `None` when unchanged since the previous snapshot, so large buffers
+    /// are not sent over IPC every interval.
+    pub content: Option<String>,
+    pub encoding: Option<String>,
+    #[serde(rename = "lineEnding")]
+    pub line_ending: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecoverableBuffer
+{
+    #[serde(rename = "bufferId")]
+    pub buffer_id: String,
+    pub path: Option<String>,
+    pub title: String,
+    /// Name of the snapshot file; buffers with equal text share one.
+    pub snapshot: String,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    #[serde(rename = "savedAt")]
+    pub saved_at: u64,
+    pub encoding: Option<String>,
+    #[serde(rename = "lineEnding")]
+    pub line_ending: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RestoredBuffer
+{
+    #[serde(flatten)]
+    pub buffer: RecoverableBuffer,
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RecoveryManifest
+{
+    buffers: Vec<RecoverableBuffer>,
+}
+
+/// The recovery directory: this session's snapshots plus whatever
+/// earlier crashed sessions left to restore.
+pub struct RecoveryStore
+{
+    root: PathBuf,
+    /// `sessions/<pid>` of this process.
+    dir: PathBuf,
+    session: Vec<RecoverableBuffer>,
+    /// Held for as long as the session runs; the OS releases it if the
+    /// process dies, however it dies.
+    #[cfg(unix)]
+    _lock: File,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Replaces this session's snapshots with `buffers`, the current dirty and
+/// untitled buffers. Unchanged text is not rewritten and buffers missing
+/// from the list (saved or closed since) are dropped.
+#[tauri::command]
+pub async fn snapshot_buffers(buffers: Vec<BufferSnapshot>) -> Result<(), String>
+{
+    tauri::async_runtime::spawn_blocking(move || {
+        with_store(|store| store.snapshot(buffers).map_err(|e| format!("Failed to snapshot buffers: {}", e)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Buffers a crashed session left unsaved, newest first.
+#[tauri::command]
+pub async fn list_recoverable_buffers() -> Result<Vec<RecoverableBuffer>, String>
+{
+    with_store(|store| Ok(store.recoverable()))
+}
+
+/// Returns a recoverable buffer's text and removes it from the list; the
+/// restored tab is snapshotted again by this session while it stays dirty.
+#[tauri::command]
+pub async fn restore_buffer(buffer_id: String) -> Result<RestoredBuffer, String>
+{
+    with_store(|store| store.restore(&buffer_id).map_err(|e| format!("Failed to restore buffer: {}", e)))
+}
+
+/// Drops the given recoverable buffers, or all of them.
+#[tauri::command]
+pub async fn discard_recoverable_buffers(buffer_ids: Option<Vec<String>>) -> Result<(), String>
+{
+    with_store(|store| store.discard(buffer_ids.as_deref()).map_err(|e| format!("Failed to discard buffers: {}", e)))
+}
+
+// ─── Lifecycle ──────────────────────────────────────────────────
+
+/// Opens the recovery directory at startup and starts asking the frontend
+/// for snapshots. Called from the app setup.
+pub fn start(app: &AppHandle, data_dir: Option<PathBuf>)
+{
+    let Some(data_dir) = data_dir
+    else
+    {
+        eprintln!("Crash recovery disabled: no app data directory");
+        return;
+    };
+
+    match RecoveryStore::open(&data_dir.join(RECOVERY_DIR_NAME))
+    {
+        Ok(store) => {
+            if let Ok(mut slot) = store_lock().lock()
+            {
+                *slot = Some(store);
+            }
+        },
+        Err(e) => {
+            eprintln!("Crash recovery disabled: {}", e);
+            return;
+        },
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || loop
+    {
+        std::thread::sleep(SNAPSHOT_INTERVAL);
+        #[cfg(not(unix))]
+        let _ = with_store(|store| store.heartbeat().map_err(|e| e.to_string()));
+        let _ = app.emit("recovery-snapshot-due", ());
+    });
+}
+
+/// Marks the session as cleanly ended. Called when the app exits normally.
+pub fn finish()
+{
+    let store = store_lock().lock().ok().and_then(|mut slot| slot.take());
+    if let Some(store) = store
+    {
+        if let Err(e) = store.close()
+        {
+            eprintln!("Failed to clear the recovery session: {}", e);
+        }
+    }
+}
+
+// ─── Store ──────────────────────────────────────────────────────
+
+impl RecoveryStore
+{
+    /// Starts this process's session under `root`. Snapshots of sessions
+    /// whose process is gone are moved to the recovered set; sessions of
+    /// other running instances are left alone.
+    pub fn open(root: &Path) -> io::Result<Self>
+    {
+        let sessions = root.join(SESSIONS_DIR_NAME);
+        fs::create_dir_all(&sessions)?;
+        let own_pid = std::process::id();
+
+        for entry in fs::read_dir(&sessions)?
+        {
+            let path = entry?.path();
+            let Some(owner) = path.file_name().and_then(|name| session_owner(&name.to_string_lossy()))
+            else
+            {
+                continue;
+            };
+            // A directory with our own PID is left from an earlier process
+            if owner == own_pid || !session_is_live(&path, owner)
+            {
+                recover_session(&path, &root.join(RECOVERED_DIR_NAME), own_pid)?;
+            }
+        }
+
+        // Set up under a temporary name so no other instance sees a
+        // session directory without its lock
+        let dir = sessions.join(own_pid.to_string());
+        let creating = sessions.join(format!("{}{}", CREATING_PREFIX, own_pid));
+        remove_dir_if_present(&creating)?;
+        fs::create_dir_all(&creating)?;
+        #[cfg(unix)]
+        let lock = lock_session(&creating.join(LOCK_NAME))?;
+        #[cfg(not(unix))]
+        atomic_write(&creating.join(LOCK_NAME), format!("{}\n", own_pid).as_bytes())?;
+        fs::rename(&creating, &dir)?;
+        Ok(RecoveryStore {
+            root: root.to_path_buf(),
+            dir,
+            session: Vec::new(),
+            #[cfg(unix)]
+            _lock: lock,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn heartbeat(&self) -> io::Result<()>
+    {
+        atomic_write(&self.dir.join(LOCK_NAME), format!("{}\n", std::process::id()).as_bytes()).map(|_| ())
+    }
+
+    pub fn snapshot(&mut self, buffers: Vec<BufferSnapshot>) -> io::Result<()>
+    {
+        let dir = self.dir.clone();
+        let saved_at = now_millis();
+        let mut next = Vec::with_capacity(buffers.len());
+
+        for buffer in buffers
+        {
+            let previous = self.session.iter().find(|b| b.buffer_id == buffer.buffer_id);
+            let (snapshot, size, saved_at) = match (&buffer.content, previous)
+            {
+                (Some(content), _) => {
+                    let name = snapshot_name(content);
+                    let file = dir.join(&name);
+                    if !file.exists()
+                    {
+                        atomic_write(&file, content.as_bytes())?;
+                    }
+                    // Keep the time of the last real change, not of every tick
+                    let unchanged = previous.filter(|p| p.snapshot == name);
+                    (name, content.len() as u64, unchanged.map_or(saved_at, |p| p.saved_at))
+                },
+                (None, Some(previous)) => (previous.snapshot.clone(), previous.size, previous.saved_at),
+                (None, None) => continue,
+            };
+
+            next.push(RecoverableBuffer {
+                buffer_id: buffer.buffer_id,
+                path: buffer.path,
+                title: buffer.title,
+                snapshot,
+                size,
+                saved_at,
+                encoding: buffer.encoding,
+                line_ending: buffer.line_ending,
+            });
+        }
+
+        if next == self.session
+        {
+            return Ok(());
+        }
+        let stale = unreferenced(&self.session, &next);
+        write_manifest(&dir, &RecoveryManifest { buffers: next.clone() })?;
+        self.session = next;
+        remove_snapshots(&dir, stale);
+        Ok(())
+    }
+
+    pub fn recoverable(&self) -> Vec<RecoverableBuffer>
+    {
+        let mut buffers = read_manifest(&self.root.join(RECOVERED_DIR_NAME))
+            .map(|m| m.buffers)
+            .unwrap_or_default();
+        buffers.sort_by_key(|b| std::cmp::Reverse(b.saved_at));
+        buffers
+    }
+
+    pub fn restore(&mut self, buffer_id: &str) -> io::Result<RestoredBuffer>
+    {
+        let dir = self.root.join(RECOVERED_DIR_NAME);
+        let manifest = read_manifest(&dir)?;
+        let buffer = manifest.buffers.iter()
+            .find(|b| b.buffer_id == buffer_id)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No recoverable buffer '{}'", buffer_id)))?;
+
+        let bytes = fs::read(dir.join(&buffer.snapshot))?;
+        let content = String::from_utf8_lossy(&bytes).into_owned();
+
+        // Carry it into this session first, so a crash before the reopened
+        // tab is snapshotted cannot lose it
+        let own_snapshot = self.dir.join(&buffer.snapshot);
+        if !own_snapshot.exists()
+        {
+            atomic_write(&own_snapshot, &bytes)?;
+        }
+        let kept_id = format!("{}{}", RESTORED_PREFIX, buffer.buffer_id);
+        let mut next: Vec<RecoverableBuffer> = self.session.iter().filter(|b| b.buffer_id != kept_id).cloned().collect();
+        next.push(RecoverableBuffer { buffer_id: kept_id, ..buffer.clone() });
+        write_manifest(&self.dir, &RecoveryManifest { buffers: next.clone() })?;
+        self.session = next;
+
+        self.discard(Some(&[buffer_id.to_string()]))?;
+        Ok(RestoredBuffer { buffer, content })
+    }
+
+    pub fn discard(&mut self, buffer_ids: Option<&[String]>) -> io::Result<()>
+    {
+        let dir = self.root.join(RECOVERED_DIR_NAME);
+        let manifest = read_manifest(&dir)?;
+        let kept: Vec<RecoverableBuffer> = match buffer_ids
+        {
+            Some(ids) => manifest.buffers.iter().filter(|b| !ids.contains(&b.buffer_id)).cloned().collect(),
+            None => Vec::new(),
+        };
+
+        if kept.is_empty()
+        {
+            return remove_dir_if_present(&dir);
+        }
+        write_manifest(&dir, &RecoveryManifest { buffers: kept.clone() })?;
+        remove_snapshots(&dir, unreferenced(&manifest.buffers, &kept));
+        Ok(())
+    }
+
+    /// Ends the session cleanly: its snapshots are no longer needed.
+    pub fn close(self) -> io::Result<()>
+    {
+        remove_dir_if_present(&self.dir)
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn store_lock() -> &'static Mutex<Option<RecoveryStore>>
+{
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+fn with_store<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce(&mut RecoveryStore) -> Result<T, String>,
+{
+    let mut slot = store_lock().lock().map_err(|_| "Recovery store lock poisoned".to_string())?;
+    match slot.as_mut()
+    {
+        Some(store) => f(store),
+        None => Err("Crash recovery is not available".to_string()),
+    }
+}
+
+/// The process that owns a directory in `sessions`: `<pid>`,
+/// `.new-<pid>` or `.adopt-<pid>-<name>`.
+fn session_owner(name: &str) -> Option<u32>
+{
+    let pid = match name.strip_prefix(CREATING_PREFIX).or_else(|| name.strip_prefix(ADOPTING_PREFIX))
+    {
+        Some(rest) => rest.split('-').next()?,
+        None => name,
+    };
+    pid.parse().ok()
+}
+
+/// Creates the lock file and takes an exclusive lock on it.
+#[cfg(unix)]
+fn lock_session(path: &Path) -> io::Result<File>
+{
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+
+    let mut file = fs::OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    writeln!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// A session is live while its lock is held. Unlike a PID, the lock
+/// cannot be mistaken for an unrelated process that reused the number.
+#[cfg(unix)]
+fn session_is_live(dir: &Path, pid: u32) -> bool
+{
+    use std::os::unix::io::AsRawFd;
+
+    match File::open(dir.join(LOCK_NAME))
+    {
+        // A failed probe counts as held; closing the file drops the
+        // probe's own lock
+        Ok(file) => {
+            let probe = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) };
+            probe != 0
+        },
+        // Only a directory still being set up has no lock file yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => process_exists(pid),
+        Err(_) => true,
+    }
+}
+
+#[cfg(unix)]
+fn process_exists(pid: u32) -> bool
+{
+    let Ok(pid) = libc::pid_t::try_from(pid)
+    else
+    {
+        return false;
+    };
+    // Signal 0 only checks; EPERM means it exists under another user
+    let exists = unsafe { libc::kill(pid, 0) } == 0;
+    exists || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn session_is_live(dir: &Path, _pid: u32) -> bool
+{
+    fs::metadata(dir.join(LOCK_NAME))
+        .or_else(|_| fs::metadata(dir))
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < HEARTBEAT_TIMEOUT))
+}
+
+/// Claims a dead session's directory, so two instances starting at once
+/// cannot both adopt it, then moves its snapshots to `recovered_dir`.
+fn recover_session(dir: &Path, recovered_dir: &Path, own_pid: u32) -> io::Result<()>
+{
+    let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let claimed = dir.with_file_name(format!("{}{}-{}", ADOPTING_PREFIX, own_pid, name.trim_start_matches('.')));
+    match fs::rename(dir, &claimed)
+    {
+        Ok(()) => {},
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+
+    let crashed = read_manifest(&claimed)?;
+    if !crashed.buffers.is_empty()
+    {
+        adopt(&claimed, recovered_dir, crashed)?;
+    }
+    remove_dir_if_present(&claimed)
+}
+
+/// Moves a crashed session's snapshots into `recovered`, merging with any
+/// not yet restored from an earlier crash.
+fn adopt(session_dir: &Path, recovered_dir: &Path, crashed: RecoveryManifest) -> io::Result<()>
+{
+    fs::create_dir_all(recovered_dir)?;
+    let mut buffers = read_manifest(recovered_dir)?.buffers;
+
+    for mut buffer in crashed.buffers
+    {
+        if buffers.iter().any(|b| b.snapshot == buffer.snapshot && b.path == buffer.path)
+        {
+            continue;
+        }
+        let target = recovered_dir.join(&buffer.snapshot);
+        if !target.exists()
+        {
+            fs::rename(session_dir.join(&buffer.snapshot), &target)?;
+        }
+        // Buffer IDs are only unique within a session; keep both versions
+        if buffers.iter().any(|b| b.buffer_id == buffer.buffer_id)
+        {
+            buffer.buffer_id = format!("{}-{}", buffer.buffer_id, buffer.saved_at);
+        }
+        buffers.push(buffer);
+    }
+
+    write_manifest(recovered_dir, &RecoveryManifest { buffers })
+}
+
+fn read_manifest(dir: &Path) -> io::Result<RecoveryManifest>
+{
+    match fs::read(dir.join(MANIFEST_NAME))
+    {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(RecoveryManifest::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_manifest(dir: &Path, manifest: &RecoveryManifest) -> io::Result<()>
+{
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_vec_pretty(manifest).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    atomic_write(&dir.join(MANIFEST_NAME), &json).map(|_| ())
+}
+
+/// Snapshot files `before` used that `after` no longer does.
+fn unreferenced(before: &[RecoverableBuffer], after: &[RecoverableBuffer]) -> Vec<String>
+{
+    let kept: HashSet<&str> = after.iter().map(|b| b.snapshot.as_str()).collect();
+    let mut stale: Vec<String> = before.iter()
+        .map(|b| b.snapshot.clone())
+        .filter(|name| !kept.contains(name.as_str()))
+        .collect();
+    stale.sort();
+    stale.dedup();
+    stale
+}
+
+fn remove_snapshots(dir: &Path, names: Vec<String>)
+{
+    for name in names
+    {
+        if let Err(e) = fs::remove_file(dir.join(&name))
+        {
+            if e.kind() != io::ErrorKind::NotFound
+            {
+                eprintln!("Failed to remove recovery snapshot '{}': {}", name, e);
+            }
+        }
+    }
+}
+
+fn remove_dir_if_present(dir: &Path) -> io::Result<()>
+{
+    match fs::remove_dir_all(dir)
+    {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn snapshot_name(content: &str) -> String
+{
+    format!("{:016x}.txt", content_hash(content.as_bytes()))
+}
+
+fn now_millis() -> u64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn buffer(id: &str, content: Option<&str>) -> BufferSnapshot
+    {
+        BufferSnapshot {
+            buffer_id: id.to_string(),
+            title: format!("{}.txt", id),
+            content: content.map(str::to_string),
+            ..BufferSnapshot::default()
+        }
+    }
+
+    fn snapshot_files(dir: &Path) -> usize
+    {
+        fs::read_dir(dir).unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension().is_some_and(|ext| ext == "txt"))
+            .count()
+    }
+
+    #[test]
+    fn equal_buffers_share_one_snapshot()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+
+        store.snapshot(vec![buffer("a", Some("same")), buffer("b", Some("same"))]).unwrap();
+        assert_eq!(snapshot_files(&store.dir), 1);
+
+        // Unsent content keeps the previous snapshot; dropped buffers lose theirs
+        store.snapshot(vec![buffer("a", None), buffer("c", Some("other"))]).unwrap();
+        assert_eq!(store.session.len(), 2);
+        assert_eq!(snapshot_files(&store.dir), 2);
+
+        store.snapshot(vec![buffer("c", Some("other"))]).unwrap();
+        assert_eq!(snapshot_files(&store.dir), 1);
+    }
+
+    #[test]
+    fn clean_shutdown_leaves_nothing_to_recover()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+        store.snapshot(vec![buffer("a", Some("draft"))]).unwrap();
+        store.close().unwrap();
+
+        let store = RecoveryStore::open(dir.path()).unwrap();
+        assert!(store.recoverable().is_empty());
+        assert_eq!(fs::read_dir(dir.path().join(SESSIONS_DIR_NAME)).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn other_running_instances_keep_their_sessions()
+    {
+        let dir = TempDir::new().unwrap();
+        let sessions = dir.path().join(SESSIONS_DIR_NAME);
+
+        // Only the lock counts: the dead session is named after a running
+        // process, as if its PID had been reused
+        let dead = sessions.join(std::os::unix::process::parent_id().to_string());
+        let live = sessions.join((std::process::id() + 1).to_string());
+
+        let mut others = Vec::new();
+        for (session, text) in [(&live, "live draft"), (&dead, "dead draft")]
+        {
+            fs::create_dir_all(session).unwrap();
+            let mut other = RecoveryStore {
+                root: dir.path().to_path_buf(),
+                dir: session.clone(),
+                session: Vec::new(),
+                _lock: lock_session(&session.join(LOCK_NAME)).unwrap(),
+            };
+            other.snapshot(vec![buffer("x", Some(text))]).unwrap();
+            others.push(other);
+        }
+        // The dead session's process is gone, so its lock is released
+        others.pop();
+
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+        assert!(live.exists());
+        assert!(!dead.exists());
+        let listed = store.recoverable();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(store.restore(&listed[0].buffer_id).unwrap().content, "dead draft");
+    }
+
+    #[test]
+    fn crashed_session_buffers_can_be_restored()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut crashed = RecoveryStore::open(dir.path()).unwrap();
+        crashed.snapshot(vec![buffer("a", Some("draft a")), buffer("b", Some("draft b"))]).unwrap();
+        drop(crashed);
+
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+        let listed = store.recoverable();
+        assert_eq!(listed.len(), 2);
+
+        let restored = store.restore("a").unwrap();
+        assert_eq!(restored.content, "draft a");
+        assert_eq!(restored.buffer.title, "a.txt");
+        assert_eq!(store.recoverable().len(), 1);
+        assert!(store.restore("a").is_err());
+
+        store.discard(None).unwrap();
+        assert!(store.recoverable().is_empty());
+        assert!(!dir.path().join(RECOVERED_DIR_NAME).exists());
+    }
+
+    #[test]
+    fn restored_buffers_survive_a_crash_before_the_next_snapshot()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut crashed = RecoveryStore::open(dir.path()).unwrap();
+        crashed.snapshot(vec![buffer("a", Some("draft a"))]).unwrap();
+        drop(crashed);
+
+        let mut restoring = RecoveryStore::open(dir.path()).unwrap();
+        restoring.restore("a").unwrap();
+        assert!(restoring.recoverable().is_empty());
+        drop(restoring);
+
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+        let listed = store.recoverable();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(store.restore(&listed[0].buffer_id).unwrap().content, "draft a");
+    }
+
+    #[test]
+    fn repeated_crashes_accumulate_recoverable_buffers()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut first = RecoveryStore::open(dir.path()).unwrap();
+        first.snapshot(vec![buffer("1", Some("first run"))]).unwrap();
+        drop(first);
+
+        let mut second = RecoveryStore::open(dir.path()).unwrap();
+        second.snapshot(vec![buffer("1", Some("second run"))]).unwrap();
+        drop(second);
+
+        let mut store = RecoveryStore::open(dir.path()).unwrap();
+        let ids: Vec<String> = store.recoverable().into_iter().map(|b| b.buffer_id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        assert_eq!(store.restore(&ids[1]).unwrap().content, "first run");
+        assert_eq!(store.recoverable().len(), 1);
+    }
+}
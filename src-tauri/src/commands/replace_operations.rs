@@ -0,0 +1,868 @@
+use ignore::WalkState;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use super::access_operations::check_access;
+use super::binary_operations::classify_bytes;
+use super::encoding_operations::{TextEncoding, decode_with, detect_encoding, encode_text};
+use super::save_operations::{atomic_write, content_hash};
+use super::search_operations::{MAX_SEARCH_FILE_SIZE, SNIFF_LENGTH, SearchQuery, build_regex, split_lines, walk_files};
+use super::tree_operations::{TreeSettings, current_settings};
+use super::watcher_operations::record_own_write;
+
+/// Directory under the app data dir holding one folder per applied replace.
+const JOURNAL_DIR_NAME: &str = "replace-journal";
+const MANIFEST_NAME: &str = "manifest.json";
+/// Journals older than this are deleted the next time a replace is applied.
+const JOURNAL_MAX_AGE: Duration = Duration::from_secs(7 * 86_400);
+/// Previews nobody applied or discarded; the oldest go first.
+const MAX_PREVIEWS: usize = 8;
+
+static NEXT_PREVIEW_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_JOURNAL_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// Previews waiting to be applied, keyed by preview ID.
+static PREVIEWS: OnceLock<Mutex<HashMap<u64, Vec<PendingFile>>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// One replacement as shown in the preview: the line before and after.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReplaceEdit
+{
+    /// Index of the edit within its file; used to select edits to apply.
+    pub id: usize,
+    /// 1-based line number.
+    pub line: u64,
+    /// 1-based column of the match start, in characters.
+    pub column: u64,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FilePreview
+{
+    pub path: String,
+    pub edits: Vec<ReplaceEdit>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SkippedFile
+{
+    pub path: String,
+    /// "changed-since-preview", "changed-since-replace", "decode-errors",
+    /// "unencodable", "missing", "access-denied" or "io".
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplacePreview
+{
+    #[serde(rename = "previewId")]
+    pub preview_id: u64,
+    pub files: Vec<FilePreview>,
+    /// Files with matches that cannot be rewritten safely.
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Edits to apply in one file; all of them when `edits` is omitted.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ReplaceSelection
+{
+    pub path: String,
+    pub edits: Option<Vec<usize>>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ApplyResult
+{
+    /// Pass to `undo_replace_in_files`; `None` when nothing was written.
+    #[serde(rename = "journalId")]
+    pub journal_id: Option<String>,
+    pub applied: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct UndoResult
+{
+    pub restored: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Size and content hash; cheap to keep for thousands of files.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+struct Fingerprint
+{
+    size: u64,
+    hash: u64,
+}
+
+#[derive(Debug)]
+struct PendingEdit
+{
+    /// Byte range in the decoded text.
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+/// A previewed file: how it looked and what to change in it.
+#[derive(Debug)]
+pub struct PendingFile
+{
+    path: PathBuf,
+    fingerprint: Fingerprint,
+    encoding: TextEncoding,
+    has_bom: bool,
+    edits: Vec<PendingEdit>,
+    preview: FilePreview,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct JournalManifest
+{
+    id: String,
+    #[serde(rename = "createdAt")]
+    created_at: u64,
+    files: Vec<JournalFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct JournalFile
+{
+    path: String,
+    /// Name of the copy of the original bytes inside the journal folder.
+    original: String,
+    /// What the file looked like right after the replace.
+    replaced: Fingerprint,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Finds every match of `query` under `root` and returns the edits that
+/// replacing them with `replacement` would make, without writing
+/// anything. In regex mode `$1` and `${name}` expand to capture groups.
+#[tauri::command]
+pub async fn replace_in_files(root: String, query: SearchQuery, replacement: String) -> Result<ReplacePreview, String>
+{
+    check_access(&root).map_err(|e| e.message)?;
+
+    let (pending, skipped) = tauri::async_runtime::spawn_blocking(move || {
+        prepare_replace(Path::new(&root), &query, &replacement, &current_settings())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let files = pending.iter().map(|file| file.preview.clone()).collect();
+    let preview_id = NEXT_PREVIEW_ID.fetch_add(1, Ordering::SeqCst);
+    let mut stored = previews().lock()
+        .map_err(|_| "Replace preview lock poisoned".to_string())?;
+    remember_preview(&mut stored, preview_id, pending);
+
+    Ok(ReplacePreview { preview_id, files, skipped })
+}
+
+/// Applies a preview. Files changed since the preview are skipped; the
+/// rest are written together, with their original bytes journaled first.
+/// If any write fails, the files already written are put back.
+#[tauri::command]
+pub async fn apply_replace_in_files(
+    app: AppHandle,
+    preview_id: u64,
+    selection: Option<Vec<ReplaceSelection>>,
+) -> Result<ApplyResult, String>
+{
+    let pending = previews().lock()
+        .map_err(|_| "Replace preview lock poisoned".to_string())?
+        .remove(&preview_id)
+        .ok_or_else(|| format!("No replace preview with ID {}", preview_id))?;
+    let journal_root = journal_root(&app)?;
+
+    tauri::async_runtime::spawn_blocking(move || apply_pending(pending, selection.as_deref(), &journal_root))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to replace in files: {}", e))
+}
+
+/// Restores every file touched by one applied replace. Files edited since
+/// are left alone and reported; the journal keeps them for another try.
+#[tauri::command]
+pub async fn undo_replace_in_files(app: AppHandle, journal_id: String) -> Result<UndoResult, String>
+{
+    let journal_root = journal_root(&app)?;
+
+    tauri::async_runtime::spawn_blocking(move || undo_journal(&journal_root, &journal_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to undo replace: {}", e))
+}
+
+/// Drops a preview that will not be applied.
+#[tauri::command]
+pub async fn discard_replace_preview(preview_id: u64) -> Result<(), String>
+{
+    previews().lock()
+        .map_err(|_| "Replace preview lock poisoned".to_string())?
+        .remove(&preview_id);
+    Ok(())
+}
+
+// ─── Preview ────────────────────────────────────────────────────
+
+/// Walks `root` like Find in Files and plans the replacement in each file
+/// with matches. Returns the planned files sorted by path, plus files that
+/// matched but could not be decoded or re-encoded without loss.
+pub fn prepare_replace(
+    root: &Path,
+    query: &SearchQuery,
+    replacement: &str,
+    settings: &TreeSettings,
+) -> Result<(Vec<PendingFile>, Vec<SkippedFile>), String>
+{
+    let regex = build_regex(query)?;
+    let planned = Mutex::new((Vec::new(), Vec::new()));
+
+    walk_files(root, query, settings, &AtomicBool::new(false), |path| {
+        let outcome = plan_file(path, &regex, replacement, query.is_regex);
+        if let Ok(mut planned) = planned.lock()
+        {
+            match outcome
+            {
+                Ok(Some(file)) => planned.0.push(file),
+                Ok(None) => {},
+                Err(reason) => planned.1.push(skipped(path, reason)),
+            }
+        }
+        WalkState::Continue
+    })?;
+
+    let (mut files, mut skipped) = planned.into_inner().map_err(|_| "Replace planning lock poisoned".to_string())?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    skipped.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((files, skipped))
+}
+
+/// Plans the edits for one file; `Ok(None)` when it has no matches or is
+/// not a text file, `Err(reason)` when it matches but is unsafe to rewrite.
+fn plan_file(path: &Path, regex: &Regex, replacement: &str, expand: bool) -> Result<Option<PendingFile>, &'static str>
+{
+    let too_large = fs::metadata(path).map(|m| m.len() > MAX_SEARCH_FILE_SIZE).map_err(|_| "io")?;
+    if too_large
+    {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).map_err(|_| "io")?;
+    if classify_bytes(&bytes[..bytes.len().min(SNIFF_LENGTH)]).is_binary
+    {
+        return Ok(None);
+    }
+
+    let detected = detect_encoding(&bytes);
+    let (text, had_errors) = decode_with(&bytes, detected.encoding);
+
+    let mut edits = Vec::new();
+    for line in split_lines(&text)
+    {
+        let line_start = line.as_ptr() as usize - text.as_ptr() as usize;
+        for captures in regex.captures_iter(line)
+        {
+            let found = captures.get(0).expect("group 0 always matches");
+            if found.as_str().is_empty()
+            {
+                continue;
+            }
+
+            let mut expanded = String::new();
+            if expand
+            {
+                captures.expand(replacement, &mut expanded);
+            }
+            else
+            {
+                expanded.push_str(replacement);
+            }
+            edits.push(PendingEdit {
+                start: line_start + found.start(),
+                end: line_start + found.end(),
+                replacement: expanded,
+            });
+        }
+    }
+
+    if edits.is_empty()
+    {
+        return Ok(None);
+    }
+    // Rewriting text that did not decode cleanly would corrupt the file
+    if had_errors
+    {
+        return Err("decode-errors");
+    }
+
+    let file = PendingFile {
+        path: path.to_path_buf(),
+        fingerprint: fingerprint(&bytes),
+        encoding: detected.encoding,
+        has_bom: detected.has_bom,
+        preview: preview_edits(path, &text, &edits),
+        edits,
+    };
+    file.rewrite(&text, None).map_err(|_| "unencodable")?;
+    Ok(Some(file))
+}
+
+/// Each edit as its line before and after the replacement.
+fn preview_edits(path: &Path, text: &str, edits: &[PendingEdit]) -> FilePreview
+{
+    let lines = split_lines(text);
+    let line_starts: Vec<usize> = lines.iter().map(|l| l.as_ptr() as usize - text.as_ptr() as usize).collect();
+
+    let edits = edits.iter().enumerate().filter_map(|(id, edit)| {
+        let index = line_starts.partition_point(|&start| start <= edit.start).checked_sub(1)?;
+        let line = lines[index];
+        let (start, end) = (edit.start - line_starts[index], edit.end - line_starts[index]);
+
+        Some(ReplaceEdit {
+            id,
+            line: index as u64 + 1,
+            column: line[..start].chars().count() as u64 + 1,
+            before: line.to_string(),
+            after: format!("{}{}{}", &line[..start], edit.replacement, &line[end..]),
+        })
+    }).collect();
+
+    FilePreview { path: path.to_string_lossy().into_owned(), edits }
+}
+
+impl PendingFile
+{
+    /// Applies the selected edits (all when `only` is `None`) to `text`
+    /// and encodes the result like the original file.
+    fn rewrite(&self, text: &str, only: Option<&[usize]>) -> Result<Vec<u8>, String>
+    {
+        let mut output = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (id, edit) in self.edits.iter().enumerate()
+        {
+            if only.is_some_and(|ids| !ids.contains(&id))
+            {
+                continue;
+            }
+            output.push_str(&text[copied..edit.start]);
+            output.push_str(&edit.replacement);
+            copied = edit.end;
+        }
+        output.push_str(&text[copied..]);
+
+        encode_text(&output, self.encoding, self.has_bom)
+            .map_err(|_| "Replacement text cannot be encoded".to_string())
+    }
+}
+
+// ─── Apply and Undo ─────────────────────────────────────────────
+
+/// Writes the selected edits and journals the originals under
+/// `journal_root`. Selection entries for files not in the preview are
+/// ignored; files missing from a selection are left untouched.
+pub fn apply_pending(
+    files: Vec<PendingFile>,
+    selection: Option<&[ReplaceSelection]>,
+    journal_root: &Path,
+) -> io::Result<ApplyResult>
+{
+    let mut result = ApplyResult::default();
+    let mut writes: Vec<(PathBuf, Vec<u8>, Vec<u8>)> = Vec::new();
+
+    for file in &files
+    {
+        let path = file.path.to_string_lossy().into_owned();
+        // The walk follows links, and atomic_write writes through them
+        if check_access(&path).is_err()
+        {
+            result.skipped.push(skipped(&file.path, "access-denied"));
+            continue;
+        }
+        let only = match selection
+        {
+            None => None,
+            Some(selection) => match selection.iter().find(|s| s.path == path)
+            {
+                Some(chosen) => chosen.edits.as_deref(),
+                None => continue,
+            },
+        };
+
+        let original = match fs::read(&file.path)
+        {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                result.skipped.push(skipped(&file.path, "missing"));
+                continue;
+            },
+        };
+        if fingerprint(&original) != file.fingerprint
+        {
+            result.skipped.push(skipped(&file.path, "changed-since-preview"));
+            continue;
+        }
+
+        let text = decode_with(&original, file.encoding).0;
+        match file.rewrite(&text, only)
+        {
+            Ok(replaced) if replaced != original => writes.push((file.path.clone(), original, replaced)),
+            Ok(_) => {},
+            Err(_) => result.skipped.push(skipped(&file.path, "unencodable")),
+        }
+    }
+
+    if writes.is_empty()
+    {
+        return Ok(result);
+    }
+
+    if let Err(e) = prune_journals(journal_root, SystemTime::now())
+    {
+        eprintln!("Failed to clean up replace journals in '{}': {}", journal_root.display(), e);
+    }
+
+    let id = new_journal_id();
+    let journal_dir = journal_root.join(&id);
+    write_journal(&journal_dir, &id, &writes)?;
+
+    for (index, (path, _, replaced)) in writes.iter().enumerate()
+    {
+        if let Err(e) = atomic_write(path, replaced)
+        {
+            // Put back what was already written. The journal goes only if
+            // that worked; otherwise it holds the sole copy of an original.
+            let not_restored: Vec<String> = writes[..index].iter()
+                .filter(|(written, original, _)| atomic_write(written, original).is_err())
+                .map(|(written, _, _)| written.display().to_string())
+                .collect();
+            let message = if not_restored.is_empty()
+            {
+                let _ = fs::remove_dir_all(&journal_dir);
+                format!("'{}': {}", path.display(), e)
+            }
+            else
+            {
+                format!(
+                    "'{}': {}; could not put back {}, undo replace {} to restore them",
+                    path.display(), e, not_restored.join(", "), id
+                )
+            };
+            return Err(io::Error::new(e.kind(), message));
+        }
+        record_own_write(path);
+    }
+
+    result.applied = writes.iter().map(|(path, _, _)| path.to_string_lossy().into_owned()).collect();
+    result.journal_id = Some(id);
+    Ok(result)
+}
+
+/// Deletes journals created more than `JOURNAL_MAX_AGE` before `now`,
+/// going by the creation time at the start of their ID.
+pub fn prune_journals(journal_root: &Path, now: SystemTime) -> io::Result<()>
+{
+    let cutoff = now.checked_sub(JOURNAL_MAX_AGE)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |age| age.as_millis() as u64);
+
+    let entries = match fs::read_dir(journal_root)
+    {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let created = name.split('-').next().and_then(|millis| millis.parse::<u64>().ok());
+        if created.is_some_and(|created| created < cutoff) && entry.file_type()?.is_dir()
+        {
+            fs::remove_dir_all(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Restores the originals recorded in journal `id`. Restored entries are
+/// removed from the journal, and the journal itself once it is empty.
+pub fn undo_journal(journal_root: &Path, id: &str) -> io::Result<UndoResult>
+{
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit() || c == '-')
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a replace journal ID", id)));
+    }
+
+    let journal_dir = journal_root.join(id);
+    let mut manifest: JournalManifest = serde_json::from_slice(&fs::read(journal_dir.join(MANIFEST_NAME))?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut result = UndoResult::default();
+    let mut remaining = Vec::new();
+    for entry in manifest.files.drain(..)
+    {
+        let reason = restore_entry(&journal_dir, &entry);
+        match reason
+        {
+            None => result.restored.push(entry.path),
+            Some(reason) => {
+                result.skipped.push(SkippedFile { path: entry.path.clone(), reason: reason.to_string() });
+                remaining.push(entry);
+            },
+        }
+    }
+
+    if remaining.is_empty()
+    {
+        fs::remove_dir_all(&journal_dir)?;
+    }
+    else
+    {
+        manifest.files = remaining;
+        write_manifest(&journal_dir, &manifest)?;
+    }
+    Ok(result)
+}
+
+/// Restores one file; returns why it was skipped, if it was.
+fn restore_entry(journal_dir: &Path, entry: &JournalFile) -> Option<&'static str>
+{
+    if check_access(&entry.path).is_err()
+    {
+        return Some("access-denied");
+    }
+
+    let path = Path::new(&entry.path);
+    let current = match fs::read(path)
+    {
+        Ok(bytes) => bytes,
+        Err(_) => return Some("missing"),
+    };
+    if fingerprint(&current) != entry.replaced
+    {
+        return Some("changed-since-replace");
+    }
+
+    let Ok(original) = fs::read(journal_dir.join(&entry.original))
+    else
+    {
+        return Some("missing-original");
+    };
+    if atomic_write(path, &original).is_err()
+    {
+        return Some("io");
+    }
+    record_own_write(path);
+    None
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn previews() -> &'static Mutex<HashMap<u64, Vec<PendingFile>>>
+{
+    PREVIEWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Stores a preview, dropping the oldest beyond `MAX_PREVIEWS`.
+fn remember_preview(previews: &mut HashMap<u64, Vec<PendingFile>>, id: u64, pending: Vec<PendingFile>)
+{
+    previews.insert(id, pending);
+    while previews.len() > MAX_PREVIEWS
+    {
+        let Some(&oldest) = previews.keys().min()
+        else
+        {
+            break;
+        };
+        previews.remove(&oldest);
+    }
+}
+
+fn journal_root(app: &AppHandle) -> Result<PathBuf, String>
+{
+    app.path().app_data_dir()
+        .map(|dir| dir.join(JOURNAL_DIR_NAME))
+        .map_err(|e| format!("Cannot locate the app data directory: {}", e))
+}
+
+fn skipped(path: &Path, reason: &str) -> SkippedFile
+{
+    SkippedFile { path: path.to_string_lossy().into_owned(), reason: reason.to_string() }
+}
+
+fn now_millis() -> u64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Unique across restarts: creation time plus a per-process sequence.
+fn new_journal_id() -> String
+{
+    format!("{}-{}-{}", now_millis(), std::process::id(), NEXT_JOURNAL_SEQ.fetch_add(1, Ordering::SeqCst))
+}
+
+/// Copies every original into the journal, then writes the manifest last,
+/// so a journal without a manifest is never mistaken for a complete one.
+fn write_journal(journal_dir: &Path, id: &str, writes: &[(PathBuf, Vec<u8>, Vec<u8>)]) -> io::Result<()>
+{
+    fs::create_dir_all(journal_dir)?;
+
+    let mut files = Vec::with_capacity(writes.len());
+    for (index, (path, original, replaced)) in writes.iter().enumerate()
+    {
+        let name = format!("{}.orig", index);
+        fs::write(journal_dir.join(&name), original)?;
+        files.push(JournalFile {
+            path: path.to_string_lossy().into_owned(),
+            original: name,
+            replaced: fingerprint(replaced),
+        });
+    }
+
+    write_manifest(journal_dir, &JournalManifest { id: id.to_string(), created_at: now_millis(), files })
+}
+
+fn write_manifest(journal_dir: &Path, manifest: &JournalManifest) -> io::Result<()>
+{
+    let json = serde_json::to_vec_pretty(manifest).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    atomic_write(&journal_dir.join(MANIFEST_NAME), &json).map(|_| ())
+}
+
+fn fingerprint(bytes: &[u8]) -> Fingerprint
+{
+    Fingerprint { size: bytes.len() as u64, hash: content_hash(bytes) }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(pattern: &str) -> SearchQuery
+    {
+        SearchQuery { pattern: pattern.to_string(), case_sensitive: true, ..SearchQuery::default() }
+    }
+
+    fn plan(root: &Path, query: &SearchQuery, replacement: &str) -> Vec<PendingFile>
+    {
+        prepare_replace(root, query, replacement, &TreeSettings::default()).unwrap().0
+    }
+
+    fn workspace() -> TempDir
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "old value\nkeep\nold again\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "nothing old here? old").unwrap();
+        fs::write(dir.path().join("c.txt"), "no match").unwrap();
+        dir
+    }
+
+    // ── Preview ─────────────────────────────────────────────────
+
+    #[test]
+    fn preview_lists_edits_per_file_without_writing()
+    {
+        let dir = workspace();
+        let files = plan(dir.path(), &query("old"), "new");
+
+        assert_eq!(files.len(), 2);
+        let preview = &files[0].preview;
+        assert!(preview.path.ends_with("a.txt"));
+        assert_eq!(preview.edits[1], ReplaceEdit {
+            id: 1,
+            line: 3,
+            column: 1,
+            before: "old again".into(),
+            after: "new again".into(),
+        });
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "old value\nkeep\nold again\n");
+    }
+
+    #[test]
+    fn regex_replacement_expands_capture_groups()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("f.rs"), "let foo_bar = 1;").unwrap();
+        let files = plan(dir.path(), &SearchQuery { is_regex: true, ..query(r"(\w+)_(\w+)") }, "${2}_$1");
+
+        assert_eq!(files[0].preview.edits[0].after, "let bar_foo = 1;");
+    }
+
+    #[test]
+    fn literal_replacement_keeps_dollar_signs()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("f.txt"), "price").unwrap();
+        let files = plan(dir.path(), &query("price"), "$1 cost");
+
+        assert_eq!(files[0].preview.edits[0].after, "$1 cost");
+    }
+
+    // ── Apply ───────────────────────────────────────────────────
+
+    #[test]
+    fn apply_writes_selected_edits_and_journals_originals()
+    {
+        let dir = workspace();
+        let journal = TempDir::new().unwrap();
+        let files = plan(dir.path(), &query("old"), "new");
+        let a = dir.path().join("a.txt").to_string_lossy().into_owned();
+
+        let selection = [ReplaceSelection { path: a, edits: Some(vec![0]) }];
+        let result = apply_pending(files, Some(&selection), journal.path()).unwrap();
+
+        assert_eq!(result.applied.len(), 1);
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "new value\nkeep\nold again\n");
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "nothing old here? old");
+        assert!(journal.path().join(result.journal_id.unwrap()).join(MANIFEST_NAME).exists());
+    }
+
+    #[test]
+    fn apply_skips_files_changed_since_preview()
+    {
+        let dir = workspace();
+        let journal = TempDir::new().unwrap();
+        let files = plan(dir.path(), &query("old"), "new");
+        fs::write(dir.path().join("b.txt"), "edited meanwhile: old").unwrap();
+
+        let result = apply_pending(files, None, journal.path()).unwrap();
+
+        assert_eq!(result.applied.len(), 1);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].reason, "changed-since-preview");
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "edited meanwhile: old");
+    }
+
+    #[test]
+    fn apply_preserves_encoding_and_bom()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("utf16.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("old text".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        fs::write(&path, bytes).unwrap();
+        let journal = TempDir::new().unwrap();
+
+        apply_pending(plan(dir.path(), &query("old"), "new"), None, journal.path()).unwrap();
+
+        let mut expected = vec![0xFF, 0xFE];
+        expected.extend("new text".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        assert_eq!(fs::read(&path).unwrap(), expected);
+    }
+
+    // ── Undo ────────────────────────────────────────────────────
+
+    #[test]
+    fn undo_restores_every_touched_file_and_removes_the_journal()
+    {
+        let dir = workspace();
+        let journal = TempDir::new().unwrap();
+        let result = apply_pending(plan(dir.path(), &query("old"), "new"), None, journal.path()).unwrap();
+        let id = result.journal_id.unwrap();
+
+        let undo = undo_journal(journal.path(), &id).unwrap();
+
+        assert_eq!(undo.restored.len(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "old value\nkeep\nold again\n");
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "nothing old here? old");
+        assert!(!journal.path().join(&id).exists());
+    }
+
+    #[test]
+    fn undo_leaves_files_edited_after_the_replace()
+    {
+        let dir = workspace();
+        let journal = TempDir::new().unwrap();
+        let id = apply_pending(plan(dir.path(), &query("old"), "new"), None, journal.path())
+            .unwrap()
+            .journal_id
+            .unwrap();
+        fs::write(dir.path().join("a.txt"), "hand edited").unwrap();
+
+        let undo = undo_journal(journal.path(), &id).unwrap();
+
+        assert_eq!(undo.restored.len(), 1);
+        assert_eq!(undo.skipped[0].reason, "changed-since-replace");
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "hand edited");
+        assert!(journal.path().join(&id).exists());
+    }
+
+    #[test]
+    fn old_journals_are_pruned_by_the_time_in_their_id()
+    {
+        let journal = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let millis = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let old = format!("{}-1-1", millis(now - JOURNAL_MAX_AGE - Duration::from_secs(60)));
+        let recent = format!("{}-1-2", millis(now - Duration::from_secs(60)));
+        for id in [&old, &recent, &"notes".to_string()]
+        {
+            fs::create_dir(journal.path().join(id)).unwrap();
+        }
+
+        prune_journals(journal.path(), now).unwrap();
+
+        assert!(!journal.path().join(&old).exists());
+        assert!(journal.path().join(&recent).exists());
+        assert!(journal.path().join("notes").exists());
+    }
+
+    #[test]
+    fn only_the_latest_previews_are_kept()
+    {
+        let mut stored = HashMap::new();
+        for id in 1..=(MAX_PREVIEWS as u64 + 2)
+        {
+            remember_preview(&mut stored, id, Vec::new());
+        }
+
+        assert_eq!(stored.len(), MAX_PREVIEWS);
+        assert!(!stored.contains_key(&1) && !stored.contains_key(&2));
+        assert!(stored.contains_key(&(MAX_PREVIEWS as u64 + 2)));
+    }
+
+    #[test]
+    fn undo_keeps_the_journal_when_an_original_is_missing()
+    {
+        let dir = workspace();
+        let journal = TempDir::new().unwrap();
+        let id = apply_pending(plan(dir.path(), &query("old"), "new"), None, journal.path())
+            .unwrap()
+            .journal_id
+            .unwrap();
+        let journal_dir = journal.path().join(&id);
+        let manifest: JournalManifest = serde_json::from_slice(&fs::read(journal_dir.join(MANIFEST_NAME)).unwrap()).unwrap();
+        fs::remove_file(journal_dir.join(&manifest.files[0].original)).unwrap();
+
+        let undo = undo_journal(journal.path(), &id).unwrap();
+
+        assert_eq!(undo.restored.len(), 1);
+        assert_eq!(undo.skipped[0].reason, "missing-original");
+        assert!(journal_dir.exists());
+    }
+
+    #[test]
+    fn undo_rejects_ids_that_are_not_journal_names()
+    {
+        let journal = TempDir::new().unwrap();
+        let err = undo_journal(journal.path(), "../../etc").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
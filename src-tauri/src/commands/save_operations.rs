@@ -0,0 +1,416 @@
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counter that keeps temp file names unique within this process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// How a save reached the disk.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaveStrategy
+{
+    /// Sibling temp file, fsync, rename over the target.
+    Atomic,
+    /// Truncate and rewrite the target itself. Used when the directory is
+    /// not writable, or when a rename would lose ownership or hard links.
+    InPlace,
+}
+
+// ─── Atomic Write ───────────────────────────────────────────────
+
+/// Durably replaces the contents of `path` with `bytes`.
+///
+/// Symlinks are resolved so the link itself survives and its target is
+/// updated. The original's permissions, owner and extended attributes are
+/// copied onto the temp file before it is renamed into place, and both the
+/// file and its directory are fsynced. If the atomic path is impossible,
+/// the file is rewritten in place, restoring the old bytes on failure.
+/// A file without write permission is refused rather than replaced.
+pub fn atomic_write(path: &Path, bytes: &[u8]) -> io::Result<SaveStrategy>
+{
+    atomic_write_with(path, &mut |out| out.write_all(bytes))
+}
+
+/// `atomic_write` for contents produced by `fill`, which streams them into
+/// the temp file. When the file has to be rewritten in place, `fill` runs
+/// again to collect the contents in memory first.
+pub fn atomic_write_with(path: &Path, fill: &mut dyn FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<SaveStrategy>
+{
+    let target = resolve_symlink_target(path)?;
+    let original = match fs::metadata(&target)
+    {
+        Ok(metadata) => Some(metadata),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    if let Some(metadata) = &original
+    {
+        if !metadata.is_file()
+        {
+            return Err(io::Error::other(format!("'{}' is not a regular file", target.display())));
+        }
+        // A rename would replace the file whatever its permissions say
+        if metadata.permissions().readonly()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("'{}' is read-only", target.display()),
+            ));
+        }
+        if must_write_in_place(metadata)
+        {
+            write_in_place(&target, &collect(fill)?)?;
+            return Ok(SaveStrategy::InPlace);
+        }
+    }
+
+    let parent = match target.parent()
+    {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (temp_path, mut temp_file) = match create_temp_file(&parent, &target)
+    {
+        Ok(created) => created,
+        // Read-only directory with a writable file: fall back to in-place
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied && original.is_some() => {
+            eprintln!(
+                "Directory '{}' is not writable, saving '{}' in place",
+                parent.display(),
+                target.display()
+            );
+            write_in_place(&target, &collect(fill)?)?;
+            return Ok(SaveStrategy::InPlace);
+        },
+        Err(e) => return Err(e),
+    };
+
+    let result = (|| -> io::Result<SaveStrategy> {
+        fill(&mut temp_file)?;
+
+        if let Some(metadata) = &original
+        {
+            if !copy_metadata(metadata, &target, &temp_file)?
+            {
+                // Ownership cannot be preserved by a rename; keep the inode instead
+                drop(temp_file);
+                let _ = fs::remove_file(&temp_path);
+                write_in_place(&target, &collect(fill)?)?;
+                return Ok(SaveStrategy::InPlace);
+            }
+        }
+
+        temp_file.sync_all()?;
+        drop(temp_file);
+
+        fs::rename(&temp_path, &target)?;
+        sync_directory(&parent);
+        Ok(SaveStrategy::Atomic)
+    })();
+
+    if result.is_err()
+    {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Follows a chain of symlinks to the file that should actually be written.
+/// Dangling links resolve to their (not yet existing) target.
+pub fn resolve_symlink_target(path: &Path) -> io::Result<PathBuf>
+{
+    let mut current = path.to_path_buf();
+
+    // Same hop limit as Linux's MAXSYMLINKS
+    for _ in 0..40
+    {
+        match fs::symlink_metadata(&current)
+        {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)?;
+                current = match current.parent()
+                {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            },
+            Ok(_) => return Ok(current),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(current),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::Error::other(format!("Too many levels of symbolic links: '{}'", path.display())))
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn collect(fill: &mut dyn FnMut(&mut dyn Write) -> io::Result<()>) -> io::Result<Vec<u8>>
+{
+    let mut bytes = Vec::new();
+    fill(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn create_temp_file(dir: &Path, target: &Path) -> io::Result<(PathBuf, File)>
+{
+    let name = target.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "untitled".to_string());
+
+    loop
+    {
+        let counter = TEMP_COUNTER.fetch_add(1, Ordering::SeqCst);
+        let temp_path = dir.join(format!(".{}.notemac-{}-{}.tmp", name, std::process::id(), counter));
+
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// A rename would silently split hard links, so those are rewritten in place.
+#[cfg(unix)]
+fn must_write_in_place(metadata: &fs::Metadata) -> bool
+{
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn must_write_in_place(_metadata: &fs::Metadata) -> bool
+{
+    false
+}
+
+/// Copies mode, owner and xattrs from the original onto the temp file.
+/// Returns false when the owner could not be preserved.
+#[cfg(unix)]
+fn copy_metadata(metadata: &fs::Metadata, original: &Path, temp: &File) -> io::Result<bool>
+{
+    use std::os::unix::fs::{MetadataExt, fchown};
+    use xattr::FileExt;
+
+    temp.set_permissions(metadata.permissions())?;
+
+    let temp_metadata = temp.metadata()?;
+    if (temp_metadata.uid(), temp_metadata.gid()) != (metadata.uid(), metadata.gid())
+        && fchown(temp, Some(metadata.uid()), Some(metadata.gid())).is_err()
+    {
+        return Ok(false);
+    }
+
+    // Best effort: some namespaces (e.g. security.*) may be read-only for us
+    if let Ok(names) = xattr::list(original)
+    {
+        for name in names
+        {
+            if let Ok(Some(value)) = xattr::get(original, &name)
+            {
+                let _ = temp.set_xattr(&name, &value);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+fn copy_metadata(metadata: &fs::Metadata, _original: &Path, temp: &File) -> io::Result<bool>
+{
+    temp.set_permissions(metadata.permissions())?;
+    Ok(true)
+}
+
+/// Rewrites the file through its existing inode. The previous contents are
+/// kept in memory and written back if the new contents cannot be.
+fn write_in_place(path: &Path, bytes: &[u8]) -> io::Result<()>
+{
+    let previous = fs::read(path)?;
+    let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
+
+    let result = file.write_all(bytes).and_then(|_| file.sync_all());
+    if let Err(e) = result
+    {
+        let _ = file.set_len(0);
+        let _ = fs::write(path, &previous);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Persists the rename itself. Directories cannot be opened for sync on
+/// every platform, so failures here are ignored.
+fn sync_directory(dir: &Path)
+{
+    #[cfg(unix)]
+    {
+        if let Ok(handle) = File::open(dir)
+        {
+            let _ = handle.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+    }
+}
+
+/// FNV-1a hash of `bytes`; stable across runs and Rust versions, unlike
+/// `DefaultHasher`, so it can be persisted.
+pub fn content_hash(bytes: &[u8]) -> u64
+{
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn temp_files_in(dir: &Path) -> Vec<String>
+    {
+        fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|n| n.ends_with(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn atomic_write_creates_new_file()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("new.txt");
+
+        assert_eq!(atomic_write(&path, b"fresh").unwrap(), SaveStrategy::Atomic);
+        assert_eq!(fs::read(&path).unwrap(), b"fresh");
+        assert!(temp_files_in(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn atomic_write_replaces_existing_file()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("existing.txt");
+        fs::write(&path, "old contents that are longer").unwrap();
+
+        atomic_write(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(temp_files_in(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn atomic_write_rejects_directories()
+    {
+        let dir = TempDir::new().unwrap();
+        assert!(atomic_write(dir.path(), b"x").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_preserves_mode()
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("script.sh");
+        fs::write(&path, "#!/bin/sh").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o751)).unwrap();
+
+        atomic_write(&path, b"#!/bin/sh\necho hi").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o751);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_updates_symlink_target()
+    {
+        let dir = TempDir::new().unwrap();
+        let real = dir.path().join("real.conf");
+        let link = dir.path().join("link.conf");
+        fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink("real.conf", &link).unwrap();
+
+        atomic_write(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&real).unwrap(), b"new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_keeps_hard_links_intact()
+    {
+        let dir = TempDir::new().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "old").unwrap();
+        fs::hard_link(&first, &second).unwrap();
+
+        assert_eq!(atomic_write(&first, b"new").unwrap(), SaveStrategy::InPlace);
+        assert_eq!(fs::read(&second).unwrap(), b"new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_falls_back_when_directory_is_read_only()
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        let path = locked.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root ignores directory permissions, in which case the atomic path still works
+        let strategy = atomic_write(&path, b"new").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(temp_files_in(&locked).is_empty());
+        if unsafe { libc::geteuid() } != 0
+        {
+            assert_eq!(strategy, SaveStrategy::InPlace);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_refuses_read_only_files()
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("locked.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+
+        let err = atomic_write(&path, b"new").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+        assert!(temp_files_in(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn resolve_symlink_target_passes_through_regular_paths()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("missing.txt");
+        assert_eq!(resolve_symlink_target(&path).unwrap(), path);
+    }
+}
@@ -0,0 +1,709 @@
+use globset::GlobSet;
+use ignore::{WalkBuilder, WalkState};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::check_access;
+use super::binary_operations::classify_bytes;
+use super::encoding_operations::{decode_with, detect_encoding};
+use super::tree_operations::{TreeSettings, build_glob_set, current_settings};
+use super::trigram_operations::{narrow_search, refresh_files};
+
+/// Lines of context before and after a match when the query gives none.
+const DEFAULT_CONTEXT_LINES: usize = 2;
+/// The search stops, and reports itself truncated, after this many matches.
+const DEFAULT_MAX_RESULTS: u64 = 20_000;
+/// Larger files are skipped; they belong in the large-file viewer.
+pub const MAX_SEARCH_FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// Bytes inspected to tell binary files from text.
+pub const SNIFF_LENGTH: usize = 8 * 1024;
+/// Longer lines are sent as a window around the match.
+const MAX_PREVIEW_CHARS: usize = 500;
+/// Characters kept before the match when a preview is windowed.
+const PREVIEW_LEAD_CHARS: usize = 100;
+/// Ignore files honored in addition to .gitignore and .ignore.
+const CUSTOM_IGNORE_FILE: &str = ".notemacignore";
+
+static NEXT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Cancel flags of running searches, keyed by search ID.
+static SEARCHES: OnceLock<Mutex<HashMap<u64, Arc<AtomicBool>>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchQuery
+{
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression rather than literal text.
+    #[serde(rename = "isRegex")]
+    pub is_regex: bool,
+    #[serde(rename = "caseSensitive")]
+    pub case_sensitive: bool,
+    #[serde(rename = "wholeWord")]
+    pub whole_word: bool,
+    /// When non-empty, only files matching one of these are searched.
+    pub include: Vec<String>,
+    /// Added to the project tree's exclude globs.
+    pub exclude: Vec<String>,
+    #[serde(rename = "contextLines")]
+    pub context_lines: Option<usize>,
+    #[serde(rename = "maxResults")]
+    pub max_results: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SearchMatch
+{
+    /// 1-based line number.
+    pub line: u64,
+    /// 1-based column of the match start, in characters.
+    pub column: u64,
+    /// Match length in characters.
+    pub length: u64,
+    /// The matched line, or a window of it when the line is very long.
+    #[serde(rename = "lineText")]
+    pub line_text: String,
+    /// Character offset of `line_text` within the full line.
+    #[serde(rename = "previewStart")]
+    pub preview_start: u64,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// All matches of one file; sent as one `search-result` event.
+#[derive(Serialize, Clone, Debug)]
+pub struct FileMatches
+{
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct SearchResultPayload
+{
+    #[serde(rename = "searchId")]
+    search_id: u64,
+    #[serde(flatten)]
+    file: FileMatches,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct SearchSummary
+{
+    #[serde(rename = "filesSearched")]
+    pub files_searched: u64,
+    #[serde(rename = "filesMatched")]
+    pub files_matched: u64,
+    #[serde(rename = "matchCount")]
+    pub match_count: u64,
+    pub cancelled: bool,
+    /// The match limit was reached before the whole tree was searched.
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct SearchCompletePayload
+{
+    #[serde(rename = "searchId")]
+    search_id: u64,
+    root: String,
+    #[serde(flatten)]
+    summary: SearchSummary,
+    #[serde(rename = "elapsedMs")]
+    elapsed_ms: u64,
+    /// Set when the search could not run at all.
+    error: Option<String>,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Searches every text file under `root` on background threads. Returns a
+/// search ID right away; each file with matches arrives as a
+/// `search-result` event and the run ends with `search-complete`.
+#[tauri::command]
+pub async fn find_in_files(app: AppHandle, root: String, query: SearchQuery) -> Result<u64, String>
+{
+    check_access(&root).map_err(|e| e.message)?;
+    // Validate up front so a bad pattern fails the call, not the event
+    build_regex(&query)?;
+    SearchFilter::new(&query, &current_settings())?;
+
+    let search_id = NEXT_SEARCH_ID.fetch_add(1, Ordering::SeqCst);
+    let cancel = Arc::new(AtomicBool::new(false));
+    searches().lock()
+        .map_err(|_| "Search registry lock poisoned".to_string())?
+        .insert(search_id, cancel.clone());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let narrowing = narrow_search(Path::new(&root), &query);
+        let should_search = |path: &Path| narrowing.as_ref().is_none_or(|n| n.should_search(path));
+        let result = search_files(Path::new(&root), &query, &current_settings(), &cancel, should_search, |file| {
+            let _ = app.emit("search-result", SearchResultPayload { search_id, file });
+        });
+        if let Some(narrowing) = narrowing
+        {
+            refresh_files(&narrowing.into_stale());
+        }
+
+        if let Ok(mut registry) = searches().lock()
+        {
+            registry.remove(&search_id);
+        }
+
+        let (summary, error) = match result
+        {
+            Ok(summary) => (summary, None),
+            Err(e) => (SearchSummary::default(), Some(e)),
+        };
+        let _ = app.emit("search-complete", SearchCompletePayload {
+            search_id,
+            root,
+            summary,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            error,
+        });
+    });
+
+    Ok(search_id)
+}
+
+/// Cancels one search, or every running search when no ID is given.
+#[tauri::command]
+pub async fn cancel_search(search_id: Option<u64>) -> Result<(), String>
+{
+    let registry = searches().lock()
+        .map_err(|_| "Search registry lock poisoned".to_string())?;
+
+    match search_id
+    {
+        Some(id) => {
+            if let Some(cancel) = registry.get(&id)
+            {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        },
+        None => {
+            for cancel in registry.values()
+            {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        },
+    }
+    Ok(())
+}
+
+// ─── Searching ──────────────────────────────────────────────────
+
+/// Compiles the query into one regex. Literal patterns are escaped and
+/// whole-word searches are wrapped in word boundaries.
+pub fn build_regex(query: &SearchQuery) -> Result<Regex, String>
+{
+    if query.pattern.is_empty()
+    {
+        return Err("Search pattern is empty".to_string());
+    }
+
+    let pattern = if query.is_regex { query.pattern.clone() } else { regex::escape(&query.pattern) };
+    let pattern = if query.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Which files a search visits, on top of the ignore files.
+struct SearchFilter
+{
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl SearchFilter
+{
+    fn new(query: &SearchQuery, settings: &TreeSettings) -> Result<Self, String>
+    {
+        let include = if query.include.is_empty() { None } else { Some(build_glob_set(&query.include)?) };
+        let exclude: Vec<String> = settings.exclude.iter().chain(&query.exclude).cloned().collect();
+
+        Ok(SearchFilter { include, exclude: build_glob_set(&exclude)? })
+    }
+
+    /// Globs match the entry's name, its path below the root, or its full path.
+    fn matches(set: &GlobSet, root: &Path, path: &Path) -> bool
+    {
+        let name = path.file_name().map(Path::new).unwrap_or(path);
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        set.is_match(name) || set.is_match(relative) || set.is_match(path)
+    }
+}
+
+/// Walks `root` in parallel and hands every file with matches to
+/// `on_file`. Binary and very large files are skipped.
+pub fn search_tree<F>(
+    root: &Path,
+    query: &SearchQuery,
+    settings: &TreeSettings,
+    cancel: &AtomicBool,
+    on_file: F,
+) -> Result<SearchSummary, String>
+where
+    F: Fn(FileMatches) + Sync,
+{
+    search_files(root, query, settings, cancel, |_| true, on_file)
+}
+
+/// Like `search_tree`, but files for which `should_search` returns false
+/// are passed over without being read, e.g. when an index rules them out.
+pub fn search_files<S, F>(
+    root: &Path,
+    query: &SearchQuery,
+    settings: &TreeSettings,
+    cancel: &AtomicBool,
+    should_search: S,
+    on_file: F,
+) -> Result<SearchSummary, String>
+where
+    S: Fn(&Path) -> bool + Sync,
+    F: Fn(FileMatches) + Sync,
+{
+    let regex = build_regex(query)?;
+    let context = query.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES);
+    let max_results = query.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let files_searched = AtomicU64::new(0);
+    let files_matched = AtomicU64::new(0);
+    let match_count = AtomicU64::new(0);
+    let truncated = AtomicBool::new(false);
+
+    walk_files(root, query, settings, cancel, |path| {
+        if truncated.load(Ordering::SeqCst)
+        {
+            return WalkState::Quit;
+        }
+        if !should_search(path)
+        {
+            return WalkState::Continue;
+        }
+
+        let text = match read_searchable(path)
+        {
+            Ok(Some(text)) => text,
+            Ok(None) => return WalkState::Continue,
+            Err(e) => {
+                eprintln!("Skipping unreadable file '{}': {}", path.display(), e);
+                return WalkState::Continue;
+            },
+        };
+        files_searched.fetch_add(1, Ordering::SeqCst);
+
+        let mut matches = search_text(&text, &regex, context);
+        if matches.is_empty()
+        {
+            return WalkState::Continue;
+        }
+
+        let found = matches.len() as u64;
+        let previous = match_count.fetch_add(found, Ordering::SeqCst);
+        if previous >= max_results
+        {
+            truncated.store(true, Ordering::SeqCst);
+            return WalkState::Quit;
+        }
+        if previous + found > max_results
+        {
+            matches.truncate((max_results - previous) as usize);
+            truncated.store(true, Ordering::SeqCst);
+        }
+
+        files_matched.fetch_add(1, Ordering::SeqCst);
+        on_file(FileMatches { path: path.to_string_lossy().into_owned(), matches });
+        WalkState::Continue
+    })?;
+
+    Ok(SearchSummary {
+        files_searched: files_searched.into_inner(),
+        files_matched: files_matched.into_inner(),
+        match_count: match_count.into_inner().min(max_results),
+        cancelled: cancel.load(Ordering::SeqCst),
+        truncated: truncated.into_inner(),
+    })
+}
+
+/// Calls `visit` in parallel for every file under `root` that the query's
+/// globs allow. Honors .gitignore, .ignore and .notemacignore unless the
+/// project tree is set to show ignored files. Links that lead outside the
+/// allowed roots are passed over, so nothing beyond them is ever read.
+pub fn walk_files<F>(
+    root: &Path,
+    query: &SearchQuery,
+    settings: &TreeSettings,
+    cancel: &AtomicBool,
+    visit: F,
+) -> Result<(), String>
+where
+    F: Fn(&Path) -> WalkState + Sync,
+{
+    let filter = Arc::new(SearchFilter::new(query, settings)?);
+
+    let respect = settings.respect_ignore_files;
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!settings.show_hidden)
+        .follow_links(settings.follow_links)
+        .git_ignore(respect)
+        .git_exclude(respect)
+        .git_global(false)
+        .ignore(respect)
+        .parents(respect)
+        .require_git(false);
+    if respect
+    {
+        builder.add_custom_ignore_filename(CUSTOM_IGNORE_FILE);
+    }
+
+    let walk_root = root.to_path_buf();
+    let walk_filter = filter.clone();
+    builder.filter_entry(move |entry| {
+        if entry.depth() == 0
+        {
+            return true;
+        }
+        if entry.path_is_symlink() && check_access(&entry.path().to_string_lossy()).is_err()
+        {
+            return false;
+        }
+        !SearchFilter::matches(&walk_filter.exclude, &walk_root, entry.path())
+    });
+
+    builder.build_parallel().run(|| {
+        let (filter, visit) = (&filter, &visit);
+
+        Box::new(move |entry| {
+            if cancel.load(Ordering::SeqCst)
+            {
+                return WalkState::Quit;
+            }
+
+            let Ok(entry) = entry
+            else
+            {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file())
+            {
+                return WalkState::Continue;
+            }
+            let path = entry.path();
+            if filter.include.as_ref().is_some_and(|include| !SearchFilter::matches(include, root, path))
+            {
+                return WalkState::Continue;
+            }
+
+            visit(path)
+        })
+    });
+
+    Ok(())
+}
+
+/// Every match of `regex` in `text`, line by line. LF, CRLF and lone CR
+/// all end a line; matches never span lines.
+pub fn search_text(text: &str, regex: &Regex, context: usize) -> Vec<SearchMatch>
+{
+    let lines = split_lines(text);
+    let mut matches = Vec::new();
+
+    for (index, line) in lines.iter().enumerate()
+    {
+        for found in regex.find_iter(line)
+        {
+            if found.as_str().is_empty()
+            {
+                continue;
+            }
+
+            let column = line[..found.start()].chars().count();
+            let length = found.as_str().chars().count();
+            let (line_text, preview_start) = preview(line, column);
+
+            matches.push(SearchMatch {
+                line: index as u64 + 1,
+                column: column as u64 + 1,
+                length: length as u64,
+                line_text,
+                preview_start: preview_start as u64,
+                before: lines[index.saturating_sub(context)..index].iter().map(|l| clip(l)).collect(),
+                after: lines[(index + 1).min(lines.len())..(index + 1 + context).min(lines.len())]
+                    .iter()
+                    .map(|l| clip(l))
+                    .collect(),
+            });
+        }
+    }
+
+    matches
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn searches() -> &'static Mutex<HashMap<u64, Arc<AtomicBool>>>
+{
+    SEARCHES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The decoded text of `path`, or `None` for binary and oversized files.
+pub fn read_searchable(path: &Path) -> io::Result<Option<String>>
+{
+    if fs::metadata(path)?.len() > MAX_SEARCH_FILE_SIZE
+    {
+        return Ok(None);
+    }
+
+    let bytes = fs::read(path)?;
+    if classify_bytes(&bytes[..bytes.len().min(SNIFF_LENGTH)]).is_binary
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(decode_with(&bytes, detect_encoding(&bytes).encoding).0))
+}
+
+/// Splits on LF, CRLF and lone CR, keeping a final empty line.
+pub fn split_lines(text: &str) -> Vec<&str>
+{
+    let mut lines = Vec::new();
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len()
+    {
+        match bytes[i]
+        {
+            b'\n' => {
+                lines.push(&text[start..i]);
+                start = i + 1;
+            },
+            b'\r' => {
+                lines.push(&text[start..i]);
+                if bytes.get(i + 1) == Some(&b'\n')
+                {
+                    i += 1;
+                }
+                start = i + 1;
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    lines.push(&text[start..]);
+    lines
+}
+
+/// The line itself when short, else a window that starts a little before
+/// the match column. Returns the text and its character offset.
+fn preview(line: &str, column: usize) -> (String, usize)
+{
+    if line.chars().count() <= MAX_PREVIEW_CHARS
+    {
+        return (line.to_string(), 0);
+    }
+
+    let start = column.saturating_sub(PREVIEW_LEAD_CHARS);
+    (line.chars().skip(start).take(MAX_PREVIEW_CHARS).collect(), start)
+}
+
+fn clip(line: &str) -> String
+{
+    line.chars().take(MAX_PREVIEW_CHARS).collect()
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(pattern: &str) -> SearchQuery
+    {
+        SearchQuery { pattern: pattern.to_string(), ..SearchQuery::default() }
+    }
+
+    fn search(root: &Path, query: &SearchQuery) -> (Vec<FileMatches>, SearchSummary)
+    {
+        let found = Mutex::new(Vec::new());
+        let summary = search_tree(root, query, &TreeSettings::default(), &AtomicBool::new(false), |file| {
+            found.lock().unwrap().push(file);
+        }).unwrap();
+
+        let mut found = found.into_inner().unwrap();
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        (found, summary)
+    }
+
+    fn names(found: &[FileMatches]) -> Vec<String>
+    {
+        found.iter()
+            .map(|f| Path::new(&f.path).file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    // ── Patterns ────────────────────────────────────────────────
+
+    #[test]
+    fn literal_patterns_are_escaped_and_case_insensitive_by_default()
+    {
+        let regex = build_regex(&query("a.b(")).unwrap();
+        assert!(regex.is_match("x A.B( y"));
+        assert!(!regex.is_match("axb("));
+    }
+
+    #[test]
+    fn case_and_whole_word_flags()
+    {
+        let regex = build_regex(&SearchQuery { case_sensitive: true, whole_word: true, ..query("Foo") }).unwrap();
+        assert!(regex.is_match("call Foo()"));
+        assert!(!regex.is_match("call foo()"));
+        assert!(!regex.is_match("FooBar"));
+    }
+
+    #[test]
+    fn invalid_regex_and_empty_pattern_are_rejected()
+    {
+        assert!(build_regex(&SearchQuery { is_regex: true, ..query("(unclosed") }).is_err());
+        assert!(build_regex(&query("")).is_err());
+    }
+
+    // ── Matching ────────────────────────────────────────────────
+
+    #[test]
+    fn search_text_reports_line_column_and_context()
+    {
+        let regex = build_regex(&query("needle")).unwrap();
+        let matches = search_text("one\r\ntwo\rthé needle\nfour\nfive", &regex, 1);
+
+        assert_eq!(matches, vec![SearchMatch {
+            line: 3,
+            column: 5,
+            length: 6,
+            line_text: "thé needle".into(),
+            preview_start: 0,
+            before: vec!["two".into()],
+            after: vec!["four".into()],
+        }]);
+    }
+
+    #[test]
+    fn search_text_windows_very_long_lines()
+    {
+        let line = format!("{}needle", "x".repeat(1000));
+        let regex = build_regex(&query("needle")).unwrap();
+        let matches = search_text(&line, &regex, 0);
+
+        assert_eq!(matches[0].column, 1001);
+        assert_eq!(matches[0].preview_start, 900);
+        assert!(matches[0].line_text.contains("needle"));
+    }
+
+    // ── Walking ─────────────────────────────────────────────────
+
+    #[test]
+    fn search_tree_honors_ignore_files_and_globs()
+    {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn needle() {}").unwrap();
+        fs::write(root.join("src/notes.txt"), "needle").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "needle").unwrap();
+        fs::write(root.join("build/out.rs"), "needle").unwrap();
+
+        let (found, summary) = search(root, &query("needle"));
+        assert_eq!(names(&found), vec!["main.rs", "notes.txt"]);
+        assert_eq!(summary.match_count, 2);
+
+        let (found, _) = search(root, &SearchQuery { include: vec!["*.rs".into()], ..query("needle") });
+        assert_eq!(names(&found), vec!["main.rs"]);
+
+        let (found, _) = search(root, &SearchQuery { exclude: vec!["src/*.rs".into()], ..query("needle") });
+        assert_eq!(names(&found), vec!["notes.txt"]);
+    }
+
+    #[test]
+    fn search_tree_skips_binary_files()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("data.bin"), b"needle\0\0\0").unwrap();
+        fs::write(dir.path().join("text.txt"), "needle").unwrap();
+
+        let (found, summary) = search(dir.path(), &query("needle"));
+        assert_eq!(names(&found), vec!["text.txt"]);
+        assert_eq!(summary.files_searched, 1);
+    }
+
+    #[test]
+    fn search_tree_stops_at_max_results()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("many.txt"), "hit\n".repeat(50)).unwrap();
+
+        let (found, summary) = search(dir.path(), &SearchQuery { max_results: Some(10), ..query("hit") });
+        assert_eq!(found[0].matches.len(), 10);
+        assert!(summary.truncated);
+        assert_eq!(summary.match_count, 10);
+    }
+
+    #[test]
+    fn search_tree_stops_when_cancelled()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "needle").unwrap();
+
+        let summary = search_tree(dir.path(), &query("needle"), &TreeSettings::default(), &AtomicBool::new(true), |_| {
+            panic!("no results expected after cancellation");
+        }).unwrap();
+        assert!(summary.cancelled);
+    }
+
+    // ── Serialization ───────────────────────────────────────────
+
+    #[test]
+    fn search_query_deserializes_camel_case_with_defaults()
+    {
+        let query: SearchQuery = serde_json::from_str(r#"{"pattern":"x","wholeWord":true,"contextLines":0}"#).unwrap();
+        assert!(query.whole_word);
+        assert!(!query.is_regex);
+        assert_eq!(query.context_lines, Some(0));
+    }
+
+    #[test]
+    fn result_payload_flattens_file_matches()
+    {
+        let payload = SearchResultPayload {
+            search_id: 7,
+            file: FileMatches { path: "/tmp/a.txt".into(), matches: vec![] },
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["searchId"], 7);
+        assert_eq!(json["path"], "/tmp/a.txt");
+        assert!(json["matches"].as_array().unwrap().is_empty());
+    }
+}
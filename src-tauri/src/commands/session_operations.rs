@@ -0,0 +1,758 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use xmltree::{Element, EmitterConfig, XMLNode};
+
+use super::access_operations::check_access;
+use super::encoding_operations::TextEncoding;
+use super::file_operations::{FileError, read_text_file};
+use super::save_operations::atomic_write;
+
+const SESSION_VERSION: u32 = 1;
+
+/// Frontend language IDs and the names Notepad++ writes in `lang`.
+const NOTEPADPP_LANGUAGES: &[(&str, &str)] = &[
+    ("plaintext", "Normal Text"),
+    ("bat", "Batch"),
+    ("c", "C"),
+    ("cpp", "C++"),
+    ("csharp", "C#"),
+    ("css", "CSS"),
+    ("go", "Go"),
+    ("html", "HTML"),
+    ("ini", "ini"),
+    ("java", "Java"),
+    ("javascript", "JavaScript"),
+    ("json", "JSON"),
+    ("lua", "Lua"),
+    ("markdown", "Markdown"),
+    ("perl", "Perl"),
+    ("php", "PHP"),
+    ("powershell", "PowerShell"),
+    ("python", "Python"),
+    ("r", "R"),
+    ("ruby", "Ruby"),
+    ("rust", "Rust"),
+    ("shell", "Shell"),
+    ("sql", "SQL"),
+    ("swift", "Swift"),
+    ("typescript", "TypeScript"),
+    ("xml", "XML"),
+    ("yaml", "YAML"),
+];
+
+/// Windows code pages Notepad++ stores in `encoding`, and our labels.
+const CODE_PAGES: &[(i32, &str)] = &[
+    (874, "windows-874"),
+    (932, "shift_jis"),
+    (936, "gbk"),
+    (949, "euc-kr"),
+    (950, "big5"),
+    (1250, "windows-1250"),
+    (1251, "windows-1251"),
+    (1252, "windows-1252"),
+    (1253, "windows-1253"),
+    (1254, "windows-1254"),
+    (1255, "windows-1255"),
+    (1256, "windows-1256"),
+    (1257, "windows-1257"),
+    (1258, "windows-1258"),
+    (20866, "koi8-r"),
+    (21866, "koi8-u"),
+    (28591, "iso-8859-1"),
+    (28592, "iso-8859-2"),
+    (28593, "iso-8859-3"),
+    (28594, "iso-8859-4"),
+    (28595, "iso-8859-5"),
+    (28596, "iso-8859-6"),
+    (28597, "iso-8859-7"),
+    (28598, "iso-8859-8"),
+    (28599, "iso-8859-9"),
+    (28605, "iso-8859-15"),
+    (51932, "euc-jp"),
+];
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// Everything needed to reopen a set of tabs: one view, or two when the
+/// editor is split, each with its own tabs and active tab.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Session
+{
+    pub version: u32,
+    pub views: Vec<SessionView>,
+    /// Index into `views` of the view with focus.
+    #[serde(rename = "activeView")]
+    pub active_view: usize,
+    /// `None` when the editor is not split.
+    pub split: Option<SplitLayout>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SessionView
+{
+    pub tabs: Vec<SessionTab>,
+    #[serde(rename = "activeIndex")]
+    pub active_index: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SplitLayout
+{
+    pub orientation: SplitOrientation,
+    /// Share of the space taken by the first view, between 0 and 1.
+    pub ratio: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitOrientation
+{
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+/// One tab. Lines and columns are 1-based; columns count characters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SessionTab
+{
+    pub path: String,
+    /// Frontend language ID, e.g. "typescript".
+    pub language: Option<String>,
+    /// Encoding label; `None` means detect on open.
+    pub encoding: Option<String>,
+    pub pinned: bool,
+    #[serde(rename = "readOnly")]
+    pub read_only: bool,
+    #[serde(rename = "cursorLine")]
+    pub cursor_line: usize,
+    #[serde(rename = "cursorColumn")]
+    pub cursor_column: usize,
+    /// Other end of the selection, when there is one.
+    #[serde(rename = "anchorLine")]
+    pub anchor_line: Option<usize>,
+    #[serde(rename = "anchorColumn")]
+    pub anchor_column: Option<usize>,
+    /// Scroll offset in pixels, as saved by this app.
+    #[serde(rename = "scrollTop")]
+    pub scroll_top: Option<f64>,
+    /// Top visible line, as saved by Notepad++.
+    #[serde(rename = "firstVisibleLine")]
+    pub first_visible_line: Option<usize>,
+    pub bookmarks: Vec<usize>,
+    /// Notepad++ caret byte offset that is not turned into a line and
+    /// column yet, because the file could not be read when the session was
+    /// loaded. See `resolve_session_tab`.
+    #[serde(rename = "caretOffset")]
+    pub caret_offset: Option<usize>,
+    #[serde(rename = "anchorOffset")]
+    pub anchor_offset: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionFormat
+{
+    Native,
+    /// Notepad++ `session.xml`.
+    Notepadpp,
+}
+
+impl Default for Session
+{
+    fn default() -> Self
+    {
+        Session { version: SESSION_VERSION, views: Vec::new(), active_view: 0, split: None }
+    }
+}
+
+impl Default for SplitLayout
+{
+    fn default() -> Self
+    {
+        SplitLayout { orientation: SplitOrientation::Vertical, ratio: 0.5 }
+    }
+}
+
+impl Default for SessionTab
+{
+    fn default() -> Self
+    {
+        SessionTab {
+            path: String::new(),
+            language: None,
+            encoding: None,
+            pinned: false,
+            read_only: false,
+            cursor_line: 1,
+            cursor_column: 1,
+            anchor_line: None,
+            anchor_column: None,
+            scroll_top: None,
+            first_visible_line: None,
+            bookmarks: Vec::new(),
+            caret_offset: None,
+            anchor_offset: None,
+        }
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Reads a session file, in this app's format or as a Notepad++
+/// `session.xml`. Listing a file in a session grants nothing: tabs outside
+/// the allowed roots are reopened through `request_path_access` or a dialog.
+#[tauri::command]
+pub async fn load_session(path: String) -> Result<Session, FileError>
+{
+    check_access(&path)?;
+    let error_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || read_session(&path))
+        .await
+        .map_err(|e| FileError::new(&error_path, "io", e.to_string()))?
+}
+
+/// Turns a Notepad++ tab's pending `caretOffset`/`anchorOffset` into lines
+/// and columns, once its file has been opened and may be read.
+#[tauri::command]
+pub async fn resolve_session_tab(tab: SessionTab) -> Result<SessionTab, FileError>
+{
+    check_access(&tab.path)?;
+    let error_path = tab.path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut tab = tab;
+        resolve_offsets(&mut tab);
+        tab
+    })
+    .await
+    .map_err(|e| FileError::new(&error_path, "io", e.to_string()))
+}
+
+/// Writes a session file. Without a `format`, a `.xml` path is written as
+/// a Notepad++ session and anything else in this app's format.
+#[tauri::command]
+pub async fn save_session(path: String, session: Session, format: Option<SessionFormat>) -> Result<(), FileError>
+{
+    check_access(&path)?;
+    let format = format.unwrap_or_else(|| default_format(Path::new(&path)));
+
+    let error_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_session(&path, &session, format))
+        .await
+        .map_err(|e| FileError::new(&error_path, "io", e.to_string()))?
+}
+
+// ─── Reading & Writing ──────────────────────────────────────────
+
+pub fn read_session(path: &str) -> Result<Session, FileError>
+{
+    let bytes = fs::read(path).map_err(|e| FileError::from_io(path, "read", &e))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let body = text.trim_start_matches('\u{feff}').trim_start();
+
+    if body.starts_with('<')
+    {
+        from_notepadpp_xml(body).map_err(|message| {
+            FileError::new(path, "invalid-session", format!("'{}' is not a valid Notepad++ session: {}", path, message))
+        })
+    }
+    else
+    {
+        serde_json::from_str(body).map_err(|e| {
+            FileError::new(path, "invalid-session", format!("'{}' is not a valid session file: {}", path, e))
+        })
+    }
+}
+
+pub fn write_session(path: &str, session: &Session, format: SessionFormat) -> Result<(), FileError>
+{
+    let bytes = match format
+    {
+        SessionFormat::Native => serde_json::to_vec_pretty(session)
+            .map_err(|e| FileError::new(path, "io", format!("Failed to serialize session: {}", e)))?,
+        SessionFormat::Notepadpp => to_notepadpp_xml(session)
+            .map_err(|e| FileError::new(path, "io", format!("Failed to serialize session: {}", e)))?,
+    };
+
+    if let Some(parent) = Path::new(path).parent()
+    {
+        fs::create_dir_all(parent).map_err(|e| FileError::from_io(path, "create directory for", &e))?;
+    }
+    atomic_write(Path::new(path), &bytes).map_err(|e| FileError::from_io(path, "write", &e))?;
+    Ok(())
+}
+
+fn default_format(path: &Path) -> SessionFormat
+{
+    match path.extension().and_then(|ext| ext.to_str())
+    {
+        Some(ext) if ext.eq_ignore_ascii_case("xml") => SessionFormat::Notepadpp,
+        _ => SessionFormat::Native,
+    }
+}
+
+// ─── Notepad++ ──────────────────────────────────────────────────
+
+/// Parses `<NotepadPlus><Session activeView><mainView|subView activeIndex>
+/// <File .../>`. Notepad++ stores carets as byte offsets, which are turned
+/// into lines and columns for files that may be read now; the others keep
+/// them in `caretOffset`/`anchorOffset`.
+pub fn from_notepadpp_xml(xml: &str) -> Result<Session, String>
+{
+    let root = Element::parse(xml.as_bytes()).map_err(|e| e.to_string())?;
+    let session_element = root.get_child("Session").ok_or("missing <Session> element")?;
+
+    let mut session = Session {
+        active_view: int_attribute(session_element, "activeView").unwrap_or(0).max(0) as usize,
+        ..Session::default()
+    };
+
+    for name in ["mainView", "subView"]
+    {
+        let Some(view_element) = session_element.get_child(name)
+        else
+        {
+            continue;
+        };
+        let tabs: Vec<SessionTab> = child_elements(view_element, "File").filter_map(tab_from_notepadpp).collect();
+        if name == "subView" && tabs.is_empty()
+        {
+            continue;
+        }
+        let active_index = int_attribute(view_element, "activeIndex").unwrap_or(0).max(0) as usize;
+        session.views.push(SessionView { active_index: active_index.min(tabs.len().saturating_sub(1)), tabs });
+    }
+
+    if session.views.len() > 1
+    {
+        session.split = Some(SplitLayout::default());
+    }
+    session.active_view = session.active_view.min(session.views.len().saturating_sub(1));
+    Ok(session)
+}
+
+fn tab_from_notepadpp(file: &Element) -> Option<SessionTab>
+{
+    let path = file.attributes.get("filename").filter(|name| !name.is_empty())?.clone();
+    let caret = int_attribute(file, "endPos").unwrap_or(0).max(0) as usize;
+    let anchor = int_attribute(file, "startPos").unwrap_or(0).max(0) as usize;
+
+    let mut tab = SessionTab {
+        language: file.attributes.get("lang").and_then(|lang| language_from_notepadpp(lang)),
+        encoding: int_attribute(file, "encoding").and_then(code_page_label).map(str::to_string),
+        pinned: file.attributes.get("tabPinned").is_some_and(|v| v == "yes"),
+        read_only: file.attributes.get("userReadOnly").is_some_and(|v| v == "yes"),
+        first_visible_line: int_attribute(file, "firstVisibleLine").map(|line| line.max(0) as usize + 1),
+        bookmarks: child_elements(file, "Mark")
+            .filter_map(|mark| int_attribute(mark, "line"))
+            .map(|line| line.max(0) as usize + 1)
+            .collect(),
+        caret_offset: Some(caret),
+        anchor_offset: (anchor != caret).then_some(anchor),
+        path,
+        ..SessionTab::default()
+    };
+    resolve_offsets(&mut tab);
+    Some(tab)
+}
+
+/// Replaces pending Notepad++ offsets with lines and columns when the
+/// tab's file may be read; otherwise leaves them for `resolve_session_tab`.
+pub fn resolve_offsets(tab: &mut SessionTab)
+{
+    let Some(caret) = tab.caret_offset
+    else
+    {
+        return;
+    };
+    let Some((text, counted_in)) = caret_text(tab)
+    else
+    {
+        return;
+    };
+
+    (tab.cursor_line, tab.cursor_column) = position_at(&text, caret, counted_in);
+    let anchor = tab.anchor_offset.map(|anchor| position_at(&text, anchor, counted_in));
+    tab.anchor_line = anchor.map(|(line, _)| line);
+    tab.anchor_column = anchor.map(|(_, column)| column);
+    tab.caret_offset = None;
+    tab.anchor_offset = None;
+}
+
+pub fn to_notepadpp_xml(session: &Session) -> Result<Vec<u8>, xmltree::Error>
+{
+    let mut session_element = Element::new("Session");
+    session_element.attributes.insert("activeView".into(), session.active_view.min(1).to_string());
+
+    for (index, name) in ["mainView", "subView"].into_iter().enumerate()
+    {
+        let mut view_element = Element::new(name);
+        let view = session.views.get(index);
+        view_element.attributes.insert("activeIndex".into(), view.map_or(0, |v| v.active_index).to_string());
+        for tab in view.map_or(&[][..], |v| &v.tabs)
+        {
+            view_element.children.push(XMLNode::Element(tab_to_notepadpp(tab)));
+        }
+        session_element.children.push(XMLNode::Element(view_element));
+    }
+
+    let mut root = Element::new("NotepadPlus");
+    root.children.push(XMLNode::Element(session_element));
+
+    let mut out = Vec::new();
+    root.write_with_config(&mut out, EmitterConfig::new().perform_indent(true).indent_string("    "))?;
+    out.push(b'\n');
+    Ok(out)
+}
+
+fn tab_to_notepadpp(tab: &SessionTab) -> Element
+{
+    let (caret, anchor) = match tab.caret_offset
+    {
+        // Never resolved: write back what was read
+        Some(caret) => (caret, tab.anchor_offset.unwrap_or(caret)),
+        None => {
+            let text = caret_text(tab);
+            let offset = |line, column| text.as_ref().map_or(0, |(text, counted_in)| offset_at(text, line, column, *counted_in));
+            let caret = offset(tab.cursor_line, tab.cursor_column);
+            let anchor = match (tab.anchor_line, tab.anchor_column)
+            {
+                (Some(line), Some(column)) => offset(line, column),
+                _ => caret,
+            };
+            (caret, anchor)
+        },
+    };
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+
+    let attributes = [
+        ("firstVisibleLine", tab.first_visible_line.map_or(0, |line| line.saturating_sub(1)).to_string()),
+        ("xOffset", "0".to_string()),
+        ("scrollWidth", "1".to_string()),
+        ("startPos", anchor.to_string()),
+        ("endPos", caret.to_string()),
+        ("selMode", "0".to_string()),
+        ("offset", "0".to_string()),
+        ("wrapCount", "1".to_string()),
+        ("lang", tab.language.as_deref().map_or("Normal Text", language_to_notepadpp).to_string()),
+        ("encoding", tab.encoding.as_deref().map_or(-1, label_code_page).to_string()),
+        ("userReadOnly", yes_no(tab.read_only).to_string()),
+        ("filename", tab.path.clone()),
+        ("backupFilePath", String::new()),
+        ("originalFileLastModifTimestamp", "0".to_string()),
+        ("originalFileLastModifTimestampHigh", "0".to_string()),
+        ("tabColourId", "-1".to_string()),
+        ("RTL", "no".to_string()),
+        ("tabPinned", yes_no(tab.pinned).to_string()),
+    ];
+
+    let mut file = Element::new("File");
+    for (name, value) in attributes
+    {
+        file.attributes.insert(name.to_string(), value);
+    }
+    for line in &tab.bookmarks
+    {
+        let mut mark = Element::new("Mark");
+        mark.attributes.insert("line".into(), line.saturating_sub(1).to_string());
+        file.children.push(XMLNode::Element(mark));
+    }
+    file
+}
+
+fn language_from_notepadpp(name: &str) -> Option<String>
+{
+    NOTEPADPP_LANGUAGES.iter()
+        .find(|(_, npp)| npp.eq_ignore_ascii_case(name))
+        .map(|(id, _)| id.to_string())
+}
+
+fn language_to_notepadpp(id: &str) -> &str
+{
+    NOTEPADPP_LANGUAGES.iter()
+        .find(|(ours, _)| *ours == id)
+        .map_or("Normal Text", |(_, npp)| npp)
+}
+
+/// Label for a Notepad++ code page; -1 (and Unicode files) mean "detect".
+fn code_page_label(code_page: i64) -> Option<&'static str>
+{
+    CODE_PAGES.iter().find(|(page, _)| *page as i64 == code_page).map(|(_, label)| *label)
+}
+
+fn label_code_page(label: &str) -> i32
+{
+    let label = label.trim().to_lowercase();
+    CODE_PAGES.iter().find(|(_, ours)| *ours == label).map_or(-1, |(page, _)| *page)
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn child_elements<'a>(element: &'a Element, name: &'a str) -> impl Iterator<Item = &'a Element>
+{
+    element.children.iter()
+        .filter_map(XMLNode::as_element)
+        .filter(move |child| child.name == name)
+}
+
+fn int_attribute(element: &Element, name: &str) -> Option<i64>
+{
+    element.attributes.get(name)?.trim().parse().ok()
+}
+
+/// Text of a tab's file, when it exists and may be read, plus the encoding
+/// Notepad++ counts its byte offsets in. Notepad++ converts Unicode files
+/// and files opened with an explicit code page to UTF-8 in the editor, but
+/// keeps detected ANSI files as their raw bytes.
+fn caret_text(tab: &SessionTab) -> Option<(String, TextEncoding)>
+{
+    check_access(&tab.path).ok()?;
+    let decoded = read_text_file(&tab.path, tab.encoding.as_deref())
+        .or_else(|_| read_text_file(&tab.path, None))
+        .ok()?;
+
+    let code_page_chosen = tab.encoding.as_deref().is_some_and(|label| label_code_page(label) != -1);
+    let counted_in = match TextEncoding::from_label(&decoded.encoding)
+    {
+        Some(ansi @ (TextEncoding::Latin1 | TextEncoding::Legacy(_))) if !code_page_chosen => ansi,
+        _ => TextEncoding::Utf8,
+    };
+    Some((decoded.content, counted_in))
+}
+
+/// Bytes `ch` takes up in `encoding`.
+fn encoded_width(ch: char, encoding: TextEncoding) -> usize
+{
+    match encoding
+    {
+        TextEncoding::Latin1 => 1,
+        TextEncoding::Legacy(legacy) => {
+            let mut buffer = [0u8; 4];
+            legacy.encode(ch.encode_utf8(&mut buffer)).0.len()
+        },
+        _ => ch.len_utf8(),
+    }
+}
+
+/// 1-based line and character column of a byte offset counted in
+/// `encoding`.
+pub fn position_at(text: &str, offset: usize, encoding: TextEncoding) -> (usize, usize)
+{
+    let mut bytes = 0;
+    let mut end = text.len();
+    for (index, ch) in text.char_indices()
+    {
+        bytes += encoded_width(ch, encoding);
+        if bytes > offset
+        {
+            end = index;
+            break;
+        }
+    }
+    let before = &text[..end];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Byte offset, counted in `encoding`, of a 1-based line and character
+/// column, clamped to the end of the line (excluding its terminator).
+pub fn offset_at(text: &str, line: usize, column: usize, encoding: TextEncoding) -> usize
+{
+    let mut line_start = 0;
+    for _ in 1..line.max(1)
+    {
+        match text[line_start..].find('\n')
+        {
+            Some(i) => line_start += i + 1,
+            None => {
+                line_start = text.len();
+                break;
+            },
+        }
+    }
+    let line_text = &text[line_start..];
+    let line_end = line_text.find('\n').map_or(line_text.len(), |i| i);
+    let line_text = line_text[..line_end].trim_end_matches('\r');
+
+    let in_line = line_text.char_indices()
+        .nth(column.max(1) - 1)
+        .map_or(line_text.len(), |(i, _)| i);
+    text[..line_start + in_line].chars().map(|ch| encoded_width(ch, encoding)).sum()
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn two_view_session(dir: &Path) -> Session
+    {
+        let first = dir.join("main.rs");
+        let second = dir.join("notes.txt");
+        fs::write(&first, "fn main()\n{\n    println!(\"é\");\n}\n").unwrap();
+        fs::write(&second, "todo\n").unwrap();
+
+        Session {
+            views: vec![
+                SessionView {
+                    tabs: vec![SessionTab {
+                        path: first.to_string_lossy().into_owned(),
+                        language: Some("rust".into()),
+                        pinned: true,
+                        cursor_line: 3,
+                        cursor_column: 17,
+                        first_visible_line: Some(2),
+                        bookmarks: vec![1, 3],
+                        ..SessionTab::default()
+                    }],
+                    active_index: 0,
+                },
+                SessionView {
+                    tabs: vec![SessionTab {
+                        path: second.to_string_lossy().into_owned(),
+                        language: Some("plaintext".into()),
+                        encoding: Some("windows-1252".into()),
+                        read_only: true,
+                        first_visible_line: Some(1),
+                        ..SessionTab::default()
+                    }],
+                    active_index: 0,
+                },
+            ],
+            active_view: 1,
+            split: Some(SplitLayout::default()),
+            ..Session::default()
+        }
+    }
+
+    #[test]
+    fn native_sessions_round_trip()
+    {
+        let dir = TempDir::new().unwrap();
+        let session = Session { split: Some(SplitLayout { orientation: SplitOrientation::Horizontal, ratio: 0.3 }), ..two_view_session(dir.path()) };
+        let path = dir.path().join("work.session").to_string_lossy().into_owned();
+
+        write_session(&path, &session, default_format(Path::new(&path))).unwrap();
+        assert_eq!(read_session(&path).unwrap(), session);
+    }
+
+    #[test]
+    fn notepadpp_sessions_round_trip()
+    {
+        let dir = TempDir::new().unwrap();
+        let session = two_view_session(dir.path());
+        let path = dir.path().join("session.xml").to_string_lossy().into_owned();
+
+        write_session(&path, &session, default_format(Path::new(&path))).unwrap();
+        let xml = fs::read_to_string(&path).unwrap();
+        assert!(xml.contains("lang=\"Rust\""));
+        assert!(xml.contains("encoding=\"1252\""));
+        // Line 3, column 17 sits after the two-byte 'é'
+        assert!(xml.contains("endPos=\"29\""));
+
+        assert_eq!(read_session(&path).unwrap(), session);
+    }
+
+    #[test]
+    fn notepadpp_session_import_reads_real_files()
+    {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<NotepadPlus>
+    <Session activeView="0">
+        <mainView activeIndex="1">
+            <File firstVisibleLine="0" startPos="0" endPos="0" lang="C++" encoding="-1" filename="C:\src\a.cpp" tabPinned="no" />
+            <File firstVisibleLine="10" startPos="4" endPos="9" lang="Batch" encoding="932" userReadOnly="yes" filename="C:\src\b.bat">
+                <Mark line="4" />
+            </File>
+        </mainView>
+        <subView activeIndex="0" />
+    </Session>
+</NotepadPlus>
+"#;
+        let session = from_notepadpp_xml(xml).unwrap();
+
+        assert_eq!(session.views.len(), 1);
+        assert!(session.split.is_none());
+        let view = &session.views[0];
+        assert_eq!(view.active_index, 1);
+        assert_eq!(view.tabs[0].language.as_deref(), Some("cpp"));
+        let tab = &view.tabs[1];
+        assert_eq!(tab.path, r"C:\src\b.bat");
+        assert_eq!(tab.encoding.as_deref(), Some("shift_jis"));
+        assert!(tab.read_only);
+        assert_eq!(tab.first_visible_line, Some(11));
+        assert_eq!(tab.bookmarks, vec![5]);
+        // The file is not on this machine, so the offsets wait to be resolved
+        assert_eq!((tab.cursor_line, tab.cursor_column), (1, 1));
+        assert_eq!((tab.caret_offset, tab.anchor_offset), (Some(9), Some(4)));
+    }
+
+    #[test]
+    fn unresolved_offsets_are_written_back_unchanged()
+    {
+        let tab = SessionTab {
+            path: "/nowhere/a.txt".into(),
+            caret_offset: Some(12),
+            anchor_offset: Some(3),
+            ..SessionTab::default()
+        };
+        let file = tab_to_notepadpp(&tab);
+        assert_eq!(file.attributes.get("endPos").map(String::as_str), Some("12"));
+        assert_eq!(file.attributes.get("startPos").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn ansi_files_count_offsets_in_their_own_bytes()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ansi.txt");
+        // "façade\nx" in Windows-1252: 'ç' is one byte
+        fs::write(&path, b"fa\xe7ade d\xe9j\xe0 vu\nx").unwrap();
+
+        let mut tab = SessionTab {
+            path: path.to_string_lossy().into_owned(),
+            caret_offset: Some(15),
+            ..SessionTab::default()
+        };
+        resolve_offsets(&mut tab);
+        assert_eq!((tab.cursor_line, tab.cursor_column), (2, 1));
+        assert_eq!(tab.caret_offset, None);
+
+        // With a code page chosen, Notepad++ counts UTF-8 bytes instead
+        tab.encoding = Some("windows-1252".into());
+        assert_eq!(tab_to_notepadpp(&tab).attributes.get("endPos").map(String::as_str), Some("18"));
+    }
+
+    #[test]
+    fn invalid_session_files_are_reported()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.xml");
+        fs::write(&path, "<NotepadPlus><Other/></NotepadPlus>").unwrap();
+
+        let error = read_session(&path.to_string_lossy()).unwrap_err();
+        assert_eq!(error.kind, "invalid-session");
+    }
+
+    #[test]
+    fn positions_and_offsets_convert_both_ways()
+    {
+        let text = "ab\r\nçd\nlast";
+        let utf8 = TextEncoding::Utf8;
+        assert_eq!(position_at(text, 0, utf8), (1, 1));
+        assert_eq!(position_at(text, 7, utf8), (2, 3));
+        assert_eq!(position_at(text, 5, utf8), (2, 1));
+        assert_eq!(offset_at(text, 2, 3, utf8), 7);
+        assert_eq!(offset_at(text, 1, 99, utf8), 2);
+        assert_eq!(offset_at(text, 9, 1, utf8), text.len());
+        assert_eq!(offset_at(text, 2, 3, TextEncoding::Latin1), 6);
+    }
+}
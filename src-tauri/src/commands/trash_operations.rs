@@ -0,0 +1,607 @@
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::access_operations::check_entry_access;
+use super::file_operations::FileError;
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TrashedItem
+{
+    /// Handle for `restore_from_trash`. On Linux this is the item's path
+    /// inside the trash; elsewhere it is the original path.
+    pub id: String,
+    #[serde(rename = "originalPath")]
+    pub original_path: String,
+    /// Milliseconds since the Unix epoch.
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: u64,
+    /// Whether `restore_from_trash` can put this item back. False on
+    /// macOS, where the UI should hide its restore action.
+    pub restorable: bool,
+}
+
+/// macOS has no public API for putting trashed items back.
+const CAN_RESTORE: bool = cfg!(not(target_os = "macos"));
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Moves a file or folder to the trash instead of deleting it.
+#[tauri::command]
+pub async fn delete_to_trash(path: String) -> Result<TrashedItem, FileError>
+{
+    check_entry_access(&path)?;
+    trash_path(Path::new(&path))
+        .map_err(|e| FileError::from_io(&path, "move to trash", &e))
+}
+
+/// Puts a trashed item back where it came from and returns that path.
+/// Only items in the user's own trash directories can be restored, and
+/// only to places the access policy allows. Fails with `already-exists`
+/// rather than overwriting anything.
+///
+/// Not available on macOS: there it always fails with `unsupported` and
+/// items come back with `restorable: false`, so callers should offer the
+/// Finder's Put Back instead.
+#[tauri::command]
+pub async fn restore_from_trash(id: String) -> Result<String, FileError>
+{
+    restore_path(&id).map(|restored| restored.to_string_lossy().into_owned())
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+/// Makes `path` absolute without following a final symlink, so the
+/// link itself is what gets trashed.
+fn absolute_path(path: &Path) -> io::Result<PathBuf>
+{
+    let name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Cannot move this path to the trash"))?;
+    let parent = match path.parent()
+    {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok(parent.canonicalize()?.join(name))
+}
+
+fn now_millis() -> u64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// ─── Freedesktop Trash (Linux) ──────────────────────────────────
+
+#[cfg(target_os = "linux")]
+pub fn trash_path(path: &Path) -> io::Result<TrashedItem>
+{
+    freedesktop::trash_with(path, &freedesktop::home_trash_dir()?)
+}
+
+#[cfg(target_os = "linux")]
+pub fn restore_path(id: &str) -> Result<PathBuf, FileError>
+{
+    let io_error = |e: io::Error| FileError::from_io(id, "restore", &e);
+    let home_trash = freedesktop::home_trash_dir().map_err(io_error)?;
+    let item = freedesktop::locate(Path::new(id), &home_trash).map_err(io_error)?;
+    check_entry_access(&item.original.to_string_lossy())?;
+    freedesktop::restore(&item).map_err(io_error)
+}
+
+/// Implements the freedesktop.org Trash specification: items go to
+/// `$XDG_DATA_HOME/Trash` when they live on the same filesystem, and to
+/// `$topdir/.Trash/$uid` or `$topdir/.Trash-$uid` on other mounts, so a
+/// delete is always a rename and never a copy.
+#[cfg(target_os = "linux")]
+mod freedesktop
+{
+    use super::{CAN_RESTORE, TrashedItem, absolute_path, now_millis};
+    use std::ffi::OsString;
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    /// A trash directory and, for per-mount trashes, the mount it serves.
+    /// Paths in `.trashinfo` files are relative to `topdir` when it is set.
+    struct TrashDir
+    {
+        root: PathBuf,
+        topdir: Option<PathBuf>,
+    }
+
+    /// An item in one of the user's trash directories and where it came from.
+    #[derive(Debug)]
+    pub struct TrashEntry
+    {
+        pub trashed: PathBuf,
+        pub original: PathBuf,
+        info: PathBuf,
+    }
+
+    pub fn home_trash_dir() -> io::Result<PathBuf>
+    {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cannot locate the home trash: HOME is not set"))?;
+        Ok(data_home.join("Trash"))
+    }
+
+    pub fn trash_with(path: &Path, home_trash: &Path) -> io::Result<TrashedItem>
+    {
+        let original = absolute_path(path)?;
+        let metadata = fs::symlink_metadata(&original)?;
+        if original.starts_with(home_trash)
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Item is already in the trash"));
+        }
+
+        let trash = select_trash_dir(&original, metadata.dev(), home_trash)?;
+        let files_dir = trash.root.join("files");
+        let info_dir = trash.root.join("info");
+
+        let recorded_path = match &trash.topdir
+        {
+            Some(topdir) => original.strip_prefix(topdir).unwrap_or(&original).to_path_buf(),
+            None => original.clone(),
+        };
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&recorded_path),
+            local_timestamp()
+        );
+
+        // Claiming the .trashinfo name first reserves the slot in files/
+        let name = original.file_name().unwrap_or_default();
+        let mut counter = 1;
+        let (trashed_path, info_path) = loop
+        {
+            let mut candidate = name.to_os_string();
+            if counter > 1
+            {
+                candidate.push(format!(".{}", counter));
+            }
+            counter += 1;
+
+            let mut info_name = candidate.clone();
+            info_name.push(".trashinfo");
+            let info_path = info_dir.join(&info_name);
+            let trashed_path = files_dir.join(&candidate);
+
+            match OpenOptions::new().write(true).create_new(true).open(&info_path)
+            {
+                Ok(mut file) => {
+                    if fs::symlink_metadata(&trashed_path).is_ok()
+                    {
+                        // Orphan left in files/ by another tool; keep looking
+                        drop(file);
+                        let _ = fs::remove_file(&info_path);
+                        continue;
+                    }
+                    if let Err(e) = file.write_all(info.as_bytes()).and_then(|_| file.sync_all())
+                    {
+                        let _ = fs::remove_file(&info_path);
+                        return Err(e);
+                    }
+                    break (trashed_path, info_path);
+                },
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        };
+
+        if let Err(e) = fs::rename(&original, &trashed_path)
+        {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+
+        Ok(TrashedItem {
+            id: trashed_path.to_string_lossy().into_owned(),
+            original_path: original.to_string_lossy().into_owned(),
+            deleted_at: now_millis(),
+            restorable: CAN_RESTORE,
+        })
+    }
+
+    /// Finds a trashed item and reads where it came from. The item must sit
+    /// in `home_trash` or in this user's trash on a mount, so a look-alike
+    /// `files/` and `info/` pair elsewhere cannot name arbitrary paths.
+    pub fn locate(trashed_path: &Path, home_trash: &Path) -> io::Result<TrashEntry>
+    {
+        let not_in_trash = || io::Error::new(io::ErrorKind::NotFound, "Item is not in a trash directory");
+
+        let name = trashed_path.file_name().ok_or_else(not_in_trash)?;
+        let files_dir = trashed_path.parent().filter(|p| p.file_name() == Some("files".as_ref())).ok_or_else(not_in_trash)?;
+        let root = files_dir.parent().ok_or_else(not_in_trash)?.canonicalize().map_err(|_| not_in_trash())?;
+        let is_home_trash = home_trash.canonicalize().is_ok_and(|home| home == root);
+        if !is_home_trash && !is_mount_trash(&root)
+        {
+            return Err(not_in_trash());
+        }
+
+        let mut info_name = name.to_os_string();
+        info_name.push(".trashinfo");
+        let info_path = root.join("info").join(info_name);
+        let info = fs::read_to_string(&info_path).map_err(|_| not_in_trash())?;
+
+        let recorded = info.lines()
+            .find_map(|line| line.strip_prefix("Path="))
+            .map(decode_path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Trash info has no Path entry"))?;
+        let original = if recorded.is_absolute()
+        {
+            recorded
+        }
+        else
+        {
+            topdir_of(&root).ok_or_else(not_in_trash)?.join(recorded)
+        };
+
+        Ok(TrashEntry { trashed: root.join("files").join(name), original, info: info_path })
+    }
+
+    pub fn restore(item: &TrashEntry) -> io::Result<PathBuf>
+    {
+        let original = &item.original;
+        if fs::symlink_metadata(original).is_ok()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", original.display()),
+            ));
+        }
+        if let Some(parent) = original.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::rename(&item.trashed, original)?;
+        let _ = fs::remove_file(&item.info);
+        Ok(original.clone())
+    }
+
+    /// Whether `root` is this user's `$topdir/.Trash-$uid` or
+    /// `$topdir/.Trash/$uid`, owned by them, on a real mount point.
+    fn is_mount_trash(root: &Path) -> bool
+    {
+        let uid = unsafe { libc::getuid() };
+        let owned = fs::symlink_metadata(root).is_ok_and(|m| m.is_dir() && m.uid() == uid);
+        let Some(topdir) = topdir_of(root)
+        else
+        {
+            return false;
+        };
+        let Ok(device) = fs::metadata(&topdir).map(|m| m.dev())
+        else
+        {
+            return false;
+        };
+        let is_mount_point = match topdir.parent()
+        {
+            Some(parent) => fs::metadata(parent).is_ok_and(|m| m.dev() != device),
+            None => true,
+        };
+        owned && is_mount_point
+    }
+
+    fn select_trash_dir(original: &Path, device: u64, home_trash: &Path) -> io::Result<TrashDir>
+    {
+        let home_ready = ensure_trash_dirs(home_trash, false).is_ok();
+        if home_ready && fs::metadata(home_trash)?.dev() == device
+        {
+            return Ok(TrashDir { root: home_trash.to_path_buf(), topdir: None });
+        }
+
+        let topdir = mount_point(original, device)?;
+        let uid = unsafe { libc::getuid() };
+
+        // An admin-provided $topdir/.Trash must be a real, sticky directory
+        let shared = topdir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared)
+        {
+            if metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0
+            {
+                let root = shared.join(uid.to_string());
+                if ensure_trash_dirs(&root, true).is_ok()
+                {
+                    return Ok(TrashDir { root, topdir: Some(topdir) });
+                }
+            }
+        }
+
+        let root = topdir.join(format!(".Trash-{}", uid));
+        ensure_trash_dirs(&root, true)?;
+        Ok(TrashDir { root, topdir: Some(topdir) })
+    }
+
+    fn ensure_trash_dirs(root: &Path, private: bool) -> io::Result<()>
+    {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        if private
+        {
+            builder.mode(0o700);
+        }
+        builder.create(root.join("files"))?;
+        builder.create(root.join("info"))
+    }
+
+    /// Walks up from `path` to the last ancestor on the same device.
+    fn mount_point(path: &Path, device: u64) -> io::Result<PathBuf>
+    {
+        let mut current = path.parent().unwrap_or(path).to_path_buf();
+        while let Some(parent) = current.parent()
+        {
+            if fs::metadata(parent)?.dev() != device
+            {
+                break;
+            }
+            current = parent.to_path_buf();
+        }
+        Ok(current)
+    }
+
+    /// The mount a per-mount trash of this user belongs to:
+    /// `$topdir/.Trash-$uid` or `$topdir/.Trash/$uid`.
+    fn topdir_of(root: &Path) -> Option<PathBuf>
+    {
+        let uid = unsafe { libc::getuid() }.to_string();
+        let parent = root.parent()?;
+        let name = root.file_name()?;
+        if name.to_str() == Some(&format!(".Trash-{}", uid))
+        {
+            return Some(parent.to_path_buf());
+        }
+        if name.to_str() == Some(&uid) && parent.file_name()? == ".Trash"
+        {
+            return parent.parent().map(Path::to_path_buf);
+        }
+        None
+    }
+
+    /// URL-escapes every byte except unreserved characters and '/'.
+    pub fn encode_path(path: &Path) -> String
+    {
+        let mut encoded = String::new();
+        for &byte in path.as_os_str().as_bytes()
+        {
+            if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte)
+            {
+                encoded.push(byte as char);
+            }
+            else
+            {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
+
+    pub fn decode_path(encoded: &str) -> PathBuf
+    {
+        let bytes = encoded.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len()
+        {
+            let hex = bytes.get(i + 1..i + 3)
+                .and_then(|h| std::str::from_utf8(h).ok())
+                .and_then(|h| u8::from_str_radix(h, 16).ok());
+            match (bytes[i], hex)
+            {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    i += 3;
+                },
+                (byte, _) => {
+                    decoded.push(byte);
+                    i += 1;
+                },
+            }
+        }
+        PathBuf::from(OsString::from_vec(decoded))
+    }
+
+    /// The spec wants local time without a zone, e.g. 2024-05-01T13:45:09.
+    fn local_timestamp() -> String
+    {
+        let now = unsafe { libc::time(std::ptr::null_mut()) };
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        unsafe { libc::localtime_r(&now, &mut tm) };
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec
+        )
+    }
+}
+
+// ─── Platform Trash (macOS, Windows, BSD) ───────────────────────
+
+#[cfg(not(target_os = "linux"))]
+pub fn trash_path(path: &Path) -> io::Result<TrashedItem>
+{
+    let original = absolute_path(path)?;
+    trash::delete(&original).map_err(io::Error::other)?;
+
+    let original_path = original.to_string_lossy().into_owned();
+    Ok(TrashedItem { id: original_path.clone(), original_path, deleted_at: now_millis(), restorable: CAN_RESTORE })
+}
+
+/// Restores the most recently trashed item that came from `id`.
+#[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
+pub fn restore_path(id: &str) -> Result<PathBuf, FileError>
+{
+    check_entry_access(id)?;
+    let io_error = |e: io::Error| FileError::from_io(id, "restore", &e);
+    let original = PathBuf::from(id);
+    if original.symlink_metadata().is_ok()
+    {
+        return Err(io_error(io::Error::new(io::ErrorKind::AlreadyExists, format!("'{}' already exists", id))));
+    }
+
+    let item = trash::os_limited::list().map_err(|e| io_error(io::Error::other(e)))?
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| io_error(io::Error::new(io::ErrorKind::NotFound, format!("'{}' is not in the trash", id))))?;
+
+    trash::os_limited::restore_all([item]).map_err(|e| io_error(io::Error::other(e)))?;
+    Ok(original)
+}
+
+/// See `CAN_RESTORE`.
+#[cfg(target_os = "macos")]
+pub fn restore_path(id: &str) -> Result<PathBuf, FileError>
+{
+    Err(FileError::new(
+        id,
+        "unsupported",
+        format!("Restoring '{}' is not supported on macOS; use Put Back in the Finder", id),
+    ))
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests
+{
+    use super::freedesktop::*;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn info_for(trash: &Path, name: &str) -> String
+    {
+        fs::read_to_string(trash.join("info").join(format!("{}.trashinfo", name))).unwrap()
+    }
+
+    #[test]
+    fn trash_moves_file_and_writes_trashinfo()
+    {
+        let dir = TempDir::new().unwrap();
+        let trash = dir.path().join("Trash");
+        let path = dir.path().join("my notes.txt");
+        fs::write(&path, "keep me").unwrap();
+
+        let item = trash_with(&path, &trash).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(trash.join("files/my notes.txt")).unwrap(), "keep me");
+
+        let info = info_for(&trash, "my notes.txt");
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains("my%20notes.txt"));
+        assert!(info.contains("DeletionDate="));
+        assert_eq!(Path::new(&item.original_path), path.canonicalize().unwrap_or(path.clone()));
+    }
+
+    #[test]
+    fn trash_avoids_name_collisions()
+    {
+        let dir = TempDir::new().unwrap();
+        let trash = dir.path().join("Trash");
+        let path = dir.path().join("a.txt");
+
+        fs::write(&path, "first").unwrap();
+        let first = trash_with(&path, &trash).unwrap();
+        fs::write(&path, "second").unwrap();
+        let second = trash_with(&path, &trash).unwrap();
+
+        assert_ne!(first.id, second.id);
+        assert_eq!(fs::read_to_string(&second.id).unwrap(), "second");
+        assert!(trash.join("info/a.txt.2.trashinfo").exists());
+    }
+
+    #[test]
+    fn restore_puts_folder_back()
+    {
+        let dir = TempDir::new().unwrap();
+        let trash = dir.path().join("Trash");
+        let folder = dir.path().join("project");
+        fs::create_dir_all(folder.join("src")).unwrap();
+        fs::write(folder.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let item = trash_with(&folder, &trash).unwrap();
+        assert!(!folder.exists());
+        assert!(item.restorable);
+
+        let restored = restore(&locate(Path::new(&item.id), &trash).unwrap()).unwrap();
+        assert_eq!(restored.to_string_lossy(), item.original_path);
+        assert_eq!(fs::read_to_string(folder.join("src/main.rs")).unwrap(), "fn main() {}");
+        assert!(!trash.join("info/project.trashinfo").exists());
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite()
+    {
+        let dir = TempDir::new().unwrap();
+        let trash = dir.path().join("Trash");
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+
+        let item = trash_with(&path, &trash).unwrap();
+        fs::write(&path, "new").unwrap();
+
+        let err = restore(&locate(Path::new(&item.id), &trash).unwrap()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(Path::new(&item.id).exists());
+    }
+
+    #[test]
+    fn restore_rejects_paths_outside_trash()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("plain.txt");
+        fs::write(&path, "x").unwrap();
+        assert!(locate(&path, &dir.path().join("Trash")).is_err());
+    }
+
+    #[test]
+    fn restore_rejects_look_alike_trash_dirs()
+    {
+        let dir = TempDir::new().unwrap();
+        let fake = dir.path().join("x");
+        fs::create_dir_all(fake.join("files")).unwrap();
+        fs::create_dir_all(fake.join("info")).unwrap();
+        fs::write(fake.join("files/evil"), "payload").unwrap();
+        fs::write(
+            fake.join("info/evil.trashinfo"),
+            format!("[Trash Info]\nPath={}\n", dir.path().join("autostart/evil.desktop").display()),
+        ).unwrap();
+
+        let err = locate(&fake.join("files/evil"), &dir.path().join("Trash")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(!dir.path().join("autostart").exists());
+    }
+
+    #[test]
+    fn trashes_symlink_not_target()
+    {
+        let dir = TempDir::new().unwrap();
+        let trash = dir.path().join("Trash");
+        let real = dir.path().join("real.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&real, "x").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        trash_with(&link, &trash).unwrap();
+        assert!(real.exists());
+        assert!(fs::symlink_metadata(trash.join("files/link.txt")).unwrap().file_type().is_symlink());
+    }
+
+    #[test]
+    fn path_encoding_round_trips()
+    {
+        let path = Path::new("/tmp/100% done/naïve #1.txt");
+        let encoded = encode_path(path);
+        assert_eq!(encoded, "/tmp/100%25%20done/na%C3%AFve%20%231.txt");
+        assert_eq!(decode_path(&encoded), path);
+    }
+}
@@ -0,0 +1,950 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::{check_access, reserve};
+use super::file_operations::FileTreeNode;
+use super::save_operations::{atomic_write, content_hash};
+use super::watcher_operations::workspace_root;
+
+/// Levels listed by `read_dir_children` when no depth is given.
+const DEFAULT_DEPTH: u32 = 1;
+/// Entries per `dir-children-batch` event.
+const BATCH_SIZE: usize = 256;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Ignore files read in every directory; later files take precedence.
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore", ".notemacignore"];
+/// Directory under the config dir holding one settings file per workspace.
+const SETTINGS_DIR_NAME: &str = "tree-settings";
+
+static NEXT_SCAN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Cancel flags of running directory scans, keyed by scan ID.
+static SCANS: OnceLock<Mutex<HashMap<u64, Arc<AtomicBool>>>> = OnceLock::new();
+
+/// Settings of the open workspace.
+static TREE_SETTINGS: OnceLock<Mutex<TreeSettings>> = OnceLock::new();
+/// Where per-workspace settings are saved; unset in tests, which keep them
+/// in memory.
+static SETTINGS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+struct DirBatchPayload
+{
+    #[serde(rename = "scanId")]
+    scan_id: u64,
+    /// Directory the entries belong to.
+    parent: String,
+    entries: Vec<FileTreeNode>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ScanProgressPayload
+{
+    #[serde(rename = "scanId")]
+    scan_id: u64,
+    #[serde(rename = "directoriesScanned")]
+    directories_scanned: u64,
+    #[serde(rename = "entriesFound")]
+    entries_found: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ScanCompletePayload
+{
+    #[serde(rename = "scanId")]
+    scan_id: u64,
+    path: String,
+    cancelled: bool,
+    #[serde(rename = "directoriesScanned")]
+    directories_scanned: u64,
+    #[serde(rename = "entriesFound")]
+    entries_found: u64,
+}
+
+/// What the project tree shows. Globs match an entry's name, its path
+/// below the workspace root or its full path, so `*.log`, `src/*.rs` and
+/// `**/build/out` all work.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TreeSettings
+{
+    /// Show entries whose name starts with a dot.
+    #[serde(rename = "showHidden")]
+    pub show_hidden: bool,
+    /// Honor .gitignore, .ignore and .notemacignore files.
+    #[serde(rename = "respectIgnoreFiles")]
+    pub respect_ignore_files: bool,
+    /// When non-empty, only files matching one of these are listed.
+    pub include: Vec<String>,
+    /// Files and folders matching any of these are never listed.
+    pub exclude: Vec<String>,
+    /// Descend into symlinked folders. Links that lead back to one of
+    /// their own ancestors are never descended into.
+    #[serde(rename = "followLinks")]
+    pub follow_links: bool,
+}
+
+/// Compiled form of `TreeSettings`.
+pub struct TreeFilter
+{
+    show_hidden: bool,
+    respect_ignore_files: bool,
+    follow_links: bool,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Workspace root that relative globs are matched from.
+    root: Option<PathBuf>,
+}
+
+/// Ignore-file matchers from the outermost directory to the innermost.
+#[derive(Clone, Default)]
+pub struct IgnoreStack
+{
+    layers: Vec<Arc<Gitignore>>,
+}
+
+/// Identifies a directory regardless of the path used to reach it:
+/// device and inode on Unix, the canonical path elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DirIdentity
+{
+    Inode(u64, u64),
+    Path(PathBuf),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScanStats
+{
+    pub directories_scanned: u64,
+    pub entries_found: u64,
+    pub cancelled: bool,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Lists `path` and, down to `depth` levels, its subdirectories. Returns a
+/// scan ID right away; entries arrive as `dir-children-batch` events, one
+/// directory at a time and breadth-first, followed by `dir-scan-complete`.
+/// Directories beyond `depth` have no `children` and are expanded by
+/// calling this again.
+#[tauri::command]
+pub async fn read_dir_children(app: AppHandle, path: String, depth: Option<u32>) -> Result<u64, String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    let root = PathBuf::from(&path);
+    let check_root = root.clone();
+    let is_dir = tauri::async_runtime::spawn_blocking(move || fs::metadata(check_root).map(|m| m.is_dir()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read directory '{}': {}", path, e))?;
+    if !is_dir
+    {
+        return Err(format!("'{}' is not a directory", path));
+    }
+
+    let scan_id = NEXT_SCAN_ID.fetch_add(1, Ordering::SeqCst);
+    let cancel = Arc::new(AtomicBool::new(false));
+    scans().lock()
+        .map_err(|_| "Scan registry lock poisoned".to_string())?
+        .insert(scan_id, cancel.clone());
+
+    let depth = depth.unwrap_or(DEFAULT_DEPTH).max(1);
+    let filter = TreeFilter::current();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut last_progress = Instant::now();
+        let stats = scan_children(&root, depth, &filter, &cancel, |parent, entries, stats| {
+            let _ = app.emit("dir-children-batch", DirBatchPayload {
+                scan_id,
+                parent: parent.to_string_lossy().into_owned(),
+                entries,
+            });
+            if last_progress.elapsed() >= PROGRESS_INTERVAL
+            {
+                last_progress = Instant::now();
+                let _ = app.emit("dir-scan-progress", ScanProgressPayload {
+                    scan_id,
+                    directories_scanned: stats.directories_scanned,
+                    entries_found: stats.entries_found,
+                });
+            }
+        });
+
+        if let Ok(mut registry) = scans().lock()
+        {
+            registry.remove(&scan_id);
+        }
+        let _ = app.emit("dir-scan-complete", ScanCompletePayload {
+            scan_id,
+            path,
+            cancelled: stats.cancelled,
+            directories_scanned: stats.directories_scanned,
+            entries_found: stats.entries_found,
+        });
+    });
+
+    Ok(scan_id)
+}
+
+/// Cancels one scan, or every running scan when no ID is given.
+#[tauri::command]
+pub async fn cancel_dir_scan(scan_id: Option<u64>) -> Result<(), String>
+{
+    match scan_id
+    {
+        Some(id) => {
+            if let Some(cancel) = scans().lock()
+                .map_err(|_| "Scan registry lock poisoned".to_string())?
+                .get(&id)
+            {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        },
+        None => cancel_all_scans(),
+    }
+    Ok(())
+}
+
+/// Applies `settings` and saves them for the open workspace, so they
+/// come back when it is reopened.
+#[tauri::command]
+pub async fn set_tree_settings(settings: TreeSettings) -> Result<(), String>
+{
+    TreeFilter::new(&settings)?;
+    *settings_lock().lock().map_err(|_| "Tree settings lock poisoned".to_string())? = settings.clone();
+
+    match workspace_root().and_then(|root| settings_store(&root))
+    {
+        Some(store) => save_settings(&store, &settings)
+            .map_err(|e| format!("Failed to save tree settings '{}': {}", store.display(), e)),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_tree_settings() -> Result<TreeSettings, String>
+{
+    Ok(current_settings())
+}
+
+// ─── Persistence ────────────────────────────────────────────────
+
+/// Sets up the per-workspace settings directory. Called from the app setup.
+pub fn init_settings(config_dir: Option<PathBuf>)
+{
+    let Some(dir) = config_dir.map(|dir| dir.join(SETTINGS_DIR_NAME))
+    else
+    {
+        return;
+    };
+    reserve(&dir);
+    let _ = SETTINGS_DIR.set(dir);
+}
+
+/// Switches to the settings saved for the workspace at `root`, or to the
+/// defaults if none were. Called before the workspace is first listed.
+pub fn load_workspace_settings(root: &Path)
+{
+    let settings = settings_store(root).map(|store| read_settings(&store)).unwrap_or_default();
+    if let Ok(mut current) = settings_lock().lock()
+    {
+        *current = settings;
+    }
+}
+
+/// `<config>/tree-settings/<hash of root>.json`
+fn settings_store(root: &Path) -> Option<PathBuf>
+{
+    let key = content_hash(root.to_string_lossy().as_bytes());
+    SETTINGS_DIR.get().map(|dir| dir.join(format!("{:016x}.json", key)))
+}
+
+fn read_settings(store: &Path) -> TreeSettings
+{
+    match fs::read(store)
+    {
+        Ok(bytes) => match serde_json::from_slice::<TreeSettings>(&bytes)
+        {
+            Ok(settings) if TreeFilter::new(&settings).is_ok() => settings,
+            Ok(_) => {
+                eprintln!("Ignoring tree settings with invalid globs '{}'", store.display());
+                TreeSettings::default()
+            },
+            Err(e) => {
+                eprintln!("Ignoring unreadable tree settings '{}': {}", store.display(), e);
+                TreeSettings::default()
+            },
+        },
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound
+            {
+                eprintln!("Failed to read tree settings '{}': {}", store.display(), e);
+            }
+            TreeSettings::default()
+        },
+    }
+}
+
+fn save_settings(store: &Path, settings: &TreeSettings) -> io::Result<()>
+{
+    let json = serde_json::to_vec_pretty(settings).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(dir) = store.parent()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(store, &json).map(|_| ())
+}
+
+// ─── Filtering ──────────────────────────────────────────────────
+
+impl Default for TreeSettings
+{
+    fn default() -> Self
+    {
+        TreeSettings {
+            show_hidden: true,
+            respect_ignore_files: true,
+            include: Vec::new(),
+            exclude: vec![".git".to_string(), "node_modules".to_string()],
+            follow_links: true,
+        }
+    }
+}
+
+impl TreeFilter
+{
+    pub fn new(settings: &TreeSettings) -> Result<Self, String>
+    {
+        let include = if settings.include.is_empty()
+        {
+            None
+        }
+        else
+        {
+            Some(build_glob_set(&settings.include)?)
+        };
+
+        Ok(TreeFilter {
+            show_hidden: settings.show_hidden,
+            respect_ignore_files: settings.respect_ignore_files,
+            follow_links: settings.follow_links,
+            include,
+            exclude: build_glob_set(&settings.exclude)?,
+            root: None,
+        })
+    }
+
+    /// Filter for the settings currently configured by the frontend,
+    /// matching from the open workspace.
+    pub fn current() -> Self
+    {
+        let filter = TreeFilter::new(&current_settings()).unwrap_or_default();
+        match workspace_root()
+        {
+            Some(root) => filter.within(&root),
+            None => filter,
+        }
+    }
+
+    /// Matches relative globs from `root` instead.
+    pub fn within(mut self, root: &Path) -> Self
+    {
+        self.root = Some(root.to_path_buf());
+        self
+    }
+
+    /// Whether an entry of a directory listing should be shown.
+    /// Include globs only narrow files, so folders stay expandable.
+    pub fn allows(&self, path: &Path, name: &str, is_dir: bool, ignores: &IgnoreStack) -> bool
+    {
+        if !self.show_hidden && name.starts_with('.')
+        {
+            return false;
+        }
+        let matches = |set: &GlobSet| {
+            let relative = self.root.as_deref().and_then(|root| path.strip_prefix(root).ok());
+            set.is_match(name) || relative.is_some_and(|relative| set.is_match(relative)) || set.is_match(path)
+        };
+        if matches(&self.exclude)
+        {
+            return false;
+        }
+        if self.respect_ignore_files && ignores.is_ignored(path, is_dir)
+        {
+            return false;
+        }
+        match &self.include
+        {
+            Some(include) if !is_dir => matches(include),
+            _ => true,
+        }
+    }
+}
+
+impl TreeFilter
+{
+    /// Returns the identity of `node` if a walk should descend into it:
+    /// it must be a folder, a followable link, and not one of `ancestors`.
+    pub fn descend_into(&self, node: &FileTreeNode, ancestors: &[DirIdentity]) -> Option<DirIdentity>
+    {
+        if !node.is_directory || (node.is_symlink && !self.follow_links)
+        {
+            return None;
+        }
+        dir_identity(Path::new(&node.path)).filter(|identity| !ancestors.contains(identity))
+    }
+}
+
+impl Default for TreeFilter
+{
+    fn default() -> Self
+    {
+        TreeFilter::new(&TreeSettings::default()).expect("default tree globs are valid")
+    }
+}
+
+impl IgnoreStack
+{
+    /// Matchers that apply inside `dir`: its own ignore files plus those of
+    /// its ancestors, up to the enclosing Git repository or open workspace.
+    /// Outside both, only `dir`'s own files are used.
+    pub fn for_dir(dir: &Path) -> Self
+    {
+        let workspace = workspace_root();
+        let mut chain = Vec::new();
+        let mut bounded = false;
+        for ancestor in dir.ancestors()
+        {
+            chain.push(ancestor);
+            if ancestor.join(".git").exists() || workspace.as_deref() == Some(ancestor)
+            {
+                bounded = true;
+                break;
+            }
+        }
+        if !bounded
+        {
+            chain.truncate(1);
+        }
+
+        chain.into_iter().rev().fold(IgnoreStack::default(), |stack, ancestor| stack.child(ancestor))
+    }
+
+    /// This stack plus the ignore files found directly in `dir`.
+    pub fn child(&self, dir: &Path) -> Self
+    {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        for name in IGNORE_FILES
+        {
+            let file = dir.join(name);
+            if file.is_file()
+            {
+                found |= builder.add(file).is_none();
+            }
+        }
+
+        let mut stack = self.clone();
+        if found
+        {
+            if let Ok(matcher) = builder.build()
+            {
+                stack.layers.push(Arc::new(matcher));
+            }
+        }
+        stack
+    }
+
+    /// The innermost matcher with an opinion wins, so a nested `!pattern`
+    /// can re-include something an outer file ignores.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool
+    {
+        for layer in self.layers.iter().rev()
+        {
+            match layer.matched(path, is_dir)
+            {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {},
+            }
+        }
+        false
+    }
+}
+
+// ─── Scanning ───────────────────────────────────────────────────
+
+/// Stops every running scan; called when a different folder is opened.
+pub fn cancel_all_scans()
+{
+    if let Ok(registry) = scans().lock()
+    {
+        for cancel in registry.values()
+        {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Breadth-first walk that hands each directory's entries to `on_batch`
+/// in chunks of `BATCH_SIZE`. Unreadable subdirectories are skipped.
+pub fn scan_children<F>(
+    root: &Path,
+    depth: u32,
+    filter: &TreeFilter,
+    cancel: &AtomicBool,
+    mut on_batch: F,
+) -> ScanStats
+where
+    F: FnMut(&Path, Vec<FileTreeNode>, &ScanStats),
+{
+    let mut stats = ScanStats::default();
+    let root_identity: Vec<DirIdentity> = dir_identity(root).into_iter().collect();
+    let mut queue = VecDeque::from([(root.to_path_buf(), 1u32, IgnoreStack::for_dir(root), root_identity)]);
+
+    while let Some((dir, level, ignores, ancestors)) = queue.pop_front()
+    {
+        if cancel.load(Ordering::SeqCst)
+        {
+            stats.cancelled = true;
+            break;
+        }
+
+        let entries = match list_children(&dir, filter, &ignores)
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Skipping unreadable directory '{}': {}", dir.display(), e);
+                continue;
+            },
+        };
+        stats.directories_scanned += 1;
+
+        if level < depth
+        {
+            for node in &entries
+            {
+                if let Some(identity) = filter.descend_into(node, &ancestors)
+                {
+                    let path = PathBuf::from(&node.path);
+                    let child_ignores = ignores.child(&path);
+                    let mut child_ancestors = ancestors.clone();
+                    child_ancestors.push(identity);
+                    queue.push_back((path, level + 1, child_ignores, child_ancestors));
+                }
+            }
+        }
+
+        if entries.is_empty()
+        {
+            on_batch(&dir, entries, &stats);
+            continue;
+        }
+        for batch in entries.chunks(BATCH_SIZE)
+        {
+            stats.entries_found += batch.len() as u64;
+            on_batch(&dir, batch.to_vec(), &stats);
+        }
+    }
+
+    stats
+}
+
+/// One level of a directory, directories first, then case-insensitive by
+/// name. `ignores` must already include `dir`'s own ignore files.
+/// Directories are returned without `children`. A symlink is listed as
+/// whatever it points at, or as a broken file when its target is missing.
+/// A link leading outside the allowed roots is listed as a plain link and
+/// never resolved, so the tree cannot be used to look beyond them.
+pub fn list_children(dir: &Path, filter: &TreeFilter, ignores: &IgnoreStack) -> io::Result<Vec<FileTreeNode>>
+{
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        let is_symlink = file_type.is_symlink();
+
+        let (is_directory, link_target, is_broken) = if is_symlink
+        {
+            let target = fs::read_link(&path).ok().map(|t| t.to_string_lossy().into_owned());
+            if check_access(&path.to_string_lossy()).is_err()
+            {
+                (false, target, false)
+            }
+            else
+            {
+                match fs::metadata(&path)
+                {
+                    Ok(metadata) => (metadata.is_dir(), target, false),
+                    Err(_) => (false, target, true),
+                }
+            }
+        }
+        else
+        {
+            (file_type.is_dir(), None, false)
+        };
+
+        if !filter.allows(&path, &name, is_directory, ignores)
+        {
+            continue;
+        }
+
+        entries.push(FileTreeNode {
+            name,
+            path: path.to_string_lossy().into_owned(),
+            is_directory,
+            is_symlink,
+            link_target,
+            is_broken,
+            children: None,
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        b.is_directory.cmp(&a.is_directory)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn scans() -> &'static Mutex<HashMap<u64, Arc<AtomicBool>>>
+{
+    SCANS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Follows links, so a symlinked folder has the identity of its target.
+#[cfg(unix)]
+pub fn dir_identity(path: &Path) -> Option<DirIdentity>
+{
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| DirIdentity::Inode(m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn dir_identity(path: &Path) -> Option<DirIdentity>
+{
+    fs::canonicalize(path).ok().map(DirIdentity::Path)
+}
+
+fn settings_lock() -> &'static Mutex<TreeSettings>
+{
+    TREE_SETTINGS.get_or_init(|| Mutex::new(TreeSettings::default()))
+}
+
+pub fn current_settings() -> TreeSettings
+{
+    settings_lock().lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+pub fn build_glob_set(patterns: &[String]) -> Result<GlobSet, String>
+{
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns
+    {
+        builder.add(Glob::new(pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn collect(root: &Path, depth: u32) -> (Vec<(String, Vec<String>)>, ScanStats)
+    {
+        collect_with(root, depth, &TreeFilter::default())
+    }
+
+    fn collect_with(root: &Path, depth: u32, filter: &TreeFilter) -> (Vec<(String, Vec<String>)>, ScanStats)
+    {
+        let mut batches = Vec::new();
+        let stats = scan_children(root, depth, filter, &AtomicBool::new(false), |parent, entries, _| {
+            let parent = parent.strip_prefix(root).unwrap().to_string_lossy().into_owned();
+            batches.push((parent, entries.into_iter().map(|e| e.name).collect()));
+        });
+        (batches, stats)
+    }
+
+    fn sample_tree() -> TempDir
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("src/nested/deep.rs"), "").unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn scan_lists_only_requested_depth()
+    {
+        let dir = sample_tree();
+        let (batches, stats) = collect(dir.path(), 1);
+
+        assert_eq!(batches, vec![("".to_string(), vec!["src".to_string(), "README.md".to_string()])]);
+        assert_eq!(stats.directories_scanned, 1);
+        assert_eq!(stats.entries_found, 2);
+    }
+
+    #[test]
+    fn scan_walks_breadth_first_to_depth()
+    {
+        let dir = sample_tree();
+        let (batches, _) = collect(dir.path(), 3);
+
+        let parents: Vec<&str> = batches.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(parents, vec!["", "src", "src/nested"]);
+        assert_eq!(batches[2].1, vec!["deep.rs"]);
+    }
+
+    #[test]
+    fn scan_splits_large_directories_into_batches()
+    {
+        let dir = TempDir::new().unwrap();
+        for i in 0..(BATCH_SIZE + 10)
+        {
+            fs::write(dir.path().join(format!("f{:04}.txt", i)), "").unwrap();
+        }
+
+        let (batches, stats) = collect(dir.path(), 1);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].1.len(), BATCH_SIZE);
+        assert_eq!(stats.entries_found, (BATCH_SIZE + 10) as u64);
+    }
+
+    #[test]
+    fn scan_stops_when_cancelled()
+    {
+        let dir = sample_tree();
+        let cancel = AtomicBool::new(false);
+        let mut batches = 0;
+
+        let stats = scan_children(dir.path(), 5, &TreeFilter::default(), &cancel, |_, _, _| {
+            batches += 1;
+            cancel.store(true, Ordering::SeqCst);
+        });
+
+        assert!(stats.cancelled);
+        assert_eq!(batches, 1);
+    }
+
+    #[test]
+    fn list_children_leaves_directories_unexpanded()
+    {
+        let dir = sample_tree();
+        let children = list_children(dir.path(), &TreeFilter::default(), &IgnoreStack::default()).unwrap();
+        assert!(children[0].is_directory);
+        assert!(children[0].children.is_none());
+    }
+
+    // ── Filtering ───────────────────────────────────────────────
+
+    fn names(dir: &Path, filter: &TreeFilter) -> Vec<String>
+    {
+        list_children(dir, filter, &IgnoreStack::for_dir(dir)).unwrap()
+            .into_iter()
+            .map(|node| node.name)
+            .collect()
+    }
+
+    #[test]
+    fn default_filter_shows_dotfiles_but_not_git_or_node_modules()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::create_dir(dir.path().join(".github")).unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        fs::write(dir.path().join(".env.example"), "").unwrap();
+
+        assert_eq!(names(dir.path(), &TreeFilter::default()), vec![".github", ".env.example"]);
+    }
+
+    #[test]
+    fn hidden_toggle_hides_dotfiles()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".env"), "").unwrap();
+        fs::write(dir.path().join("main.rs"), "").unwrap();
+
+        let filter = TreeFilter::new(&TreeSettings { show_hidden: false, ..Default::default() }).unwrap();
+        assert_eq!(names(dir.path(), &filter), vec!["main.rs"]);
+    }
+
+    #[test]
+    fn honors_ignore_files_at_every_level()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(dir.path().join(".notemacignore"), "scratch.txt\n").unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::create_dir_all(dir.path().join("logs")).unwrap();
+        fs::write(dir.path().join("logs/.ignore"), "!keep.log\nold/\n").unwrap();
+        fs::create_dir(dir.path().join("logs/old")).unwrap();
+        fs::write(dir.path().join("logs/keep.log"), "").unwrap();
+        fs::write(dir.path().join("logs/drop.log"), "").unwrap();
+        fs::write(dir.path().join("scratch.txt"), "").unwrap();
+
+        let filter = TreeFilter::default();
+        assert_eq!(names(dir.path(), &filter), vec!["logs", ".gitignore", ".notemacignore"]);
+
+        // Expanding a subfolder picks up the repository's ignore files too
+        assert_eq!(names(&dir.path().join("logs"), &filter), vec![".ignore", "keep.log"]);
+    }
+
+    #[test]
+    fn ignore_files_can_be_disabled()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".gitignore"), "dist/\n").unwrap();
+        fs::create_dir(dir.path().join("dist")).unwrap();
+
+        let filter = TreeFilter::new(&TreeSettings { respect_ignore_files: false, ..Default::default() }).unwrap();
+        assert_eq!(names(dir.path(), &filter), vec!["dist", ".gitignore"]);
+    }
+
+    #[test]
+    fn include_and_exclude_globs()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("main.rs"), "").unwrap();
+        fs::write(dir.path().join("notes.md"), "").unwrap();
+        fs::write(dir.path().join("main.rs.orig"), "").unwrap();
+
+        let filter = TreeFilter::new(&TreeSettings {
+            include: vec!["*.rs".to_string(), "*.orig".to_string()],
+            exclude: vec!["*.orig".to_string()],
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(names(dir.path(), &filter), vec!["src", "main.rs"]);
+    }
+
+    #[test]
+    fn globs_match_paths_below_the_workspace_root()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("src/notes.md"), "").unwrap();
+        fs::write(dir.path().join("build.rs"), "").unwrap();
+
+        let filter = TreeFilter::new(&TreeSettings {
+            include: vec!["src/*.rs".to_string()],
+            ..Default::default()
+        }).unwrap().within(dir.path());
+        let (batches, _) = collect_with(dir.path(), 2, &filter);
+        assert_eq!(batches, vec![
+            ("".to_string(), vec!["src".to_string()]),
+            ("src".to_string(), vec!["main.rs".to_string()]),
+        ]);
+    }
+
+    // ── Symlinks ────────────────────────────────────────────────
+
+    #[cfg(unix)]
+    #[test]
+    fn lists_symlinks_with_targets_and_broken_flag()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("real")).unwrap();
+        std::os::unix::fs::symlink("real", dir.path().join("alias")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", dir.path().join("dangling")).unwrap();
+
+        let nodes = list_children(dir.path(), &TreeFilter::default(), &IgnoreStack::default()).unwrap();
+        let alias = nodes.iter().find(|n| n.name == "alias").unwrap();
+        assert!(alias.is_directory && alias.is_symlink && !alias.is_broken);
+        assert_eq!(alias.link_target.as_deref(), Some("real"));
+
+        let dangling = nodes.iter().find(|n| n.name == "dangling").unwrap();
+        assert!(!dangling.is_directory && dangling.is_symlink && dangling.is_broken);
+
+        let real = nodes.iter().find(|n| n.name == "real").unwrap();
+        assert!(!real.is_symlink && real.link_target.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_does_not_loop_through_symlink_cycles()
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("a/b/up")).unwrap();
+
+        let (batches, _) = collect(dir.path(), 10);
+        let parents: Vec<&str> = batches.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(parents, vec!["", "a", "a/b"]);
+        assert_eq!(batches[2].1, vec!["up"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_follows_links_only_when_enabled()
+    {
+        let dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("shared.txt"), "").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("linked")).unwrap();
+
+        let (followed, _) = collect(dir.path(), 2);
+        assert_eq!(followed.len(), 2);
+        assert_eq!(followed[1].1, vec!["shared.txt"]);
+
+        let filter = TreeFilter::new(&TreeSettings { follow_links: false, ..Default::default() }).unwrap();
+        let (unfollowed, _) = collect_with(dir.path(), 2, &filter);
+        assert_eq!(unfollowed.len(), 1);
+    }
+
+    #[test]
+    fn invalid_glob_is_rejected()
+    {
+        let settings = TreeSettings { exclude: vec!["[unclosed".to_string()], ..Default::default() };
+        assert!(TreeFilter::new(&settings).is_err());
+    }
+
+    #[test]
+    fn settings_are_saved_and_read_back()
+    {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join(SETTINGS_DIR_NAME).join("ws.json");
+        assert_eq!(read_settings(&store), TreeSettings::default());
+
+        let settings = TreeSettings { show_hidden: false, include: vec!["*.rs".to_string()], ..TreeSettings::default() };
+        save_settings(&store, &settings).unwrap();
+        assert_eq!(read_settings(&store), settings);
+
+        fs::write(&store, r#"{"exclude": ["["]}"#).unwrap();
+        assert_eq!(read_settings(&store), TreeSettings::default());
+    }
+
+    #[test]
+    fn settings_deserialize_with_defaults()
+    {
+        let settings: TreeSettings = serde_json::from_value(serde_json::json!({ "showHidden": false })).unwrap();
+        assert!(!settings.show_hidden);
+        assert!(settings.respect_ignore_files);
+        assert_eq!(settings.exclude, vec![".git", "node_modules"]);
+    }
+}
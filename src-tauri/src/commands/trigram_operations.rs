@@ -0,0 +1,933 @@
+use ignore::WalkState;
+use regex_syntax::hir::{Hir, HirKind};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::access_operations::check_access;
+use super::save_operations::{atomic_write, content_hash};
+use super::search_operations::{SearchQuery, read_searchable, walk_files};
+use super::tree_operations::current_settings;
+use super::watcher_operations::workspace_root;
+
+/// Directory under the app data dir holding one index file per workspace.
+const INDEX_DIR_NAME: &str = "trigram-index";
+const INDEX_MAGIC: &[u8; 8] = b"NMTRIGR1";
+/// Quiet time after the last reported change before queued files are
+/// re-indexed and the index is saved.
+const REFRESH_DELAY: Duration = Duration::from_secs(2);
+/// Queued files that trigger a refresh without waiting for a quiet moment.
+const REFRESH_EVERY_CHANGES: usize = 100;
+/// How often the refresh worker checks its queue.
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Files between `trigram-index-progress` events while building.
+const PROGRESS_EVERY_FILES: u64 = 1000;
+
+/// The index of the open workspace. Searches take a snapshot by cloning
+/// the `Arc`; refreshes swap in an updated copy, so neither blocks the other.
+static ACTIVE: OnceLock<Mutex<Option<ActiveIndex>>> = OnceLock::new();
+/// Files waiting to be re-indexed by the refresh worker.
+static PENDING: OnceLock<Mutex<PendingRefresh>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+/// Size, modification time (ms) and trigrams of one file.
+type FileEntry = (u64, u64, HashSet<u32>);
+
+/// Files under `root` and, for each trigram of their ASCII-lowercased
+/// text, the files that contain it. Removed or changed files are marked
+/// dead and re-added under a new ID; `compact` drops the dead ones.
+#[derive(Clone, Debug, Default)]
+pub struct TrigramIndex
+{
+    root: PathBuf,
+    files: Vec<IndexedFile>,
+    by_path: HashMap<String, u32>,
+    /// Trigram → ascending file IDs.
+    postings: HashMap<u32, Vec<u32>>,
+    dead: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct IndexedFile
+{
+    /// '/'-separated path below the root.
+    relative: String,
+    size: u64,
+    /// Modification time in milliseconds since the Unix epoch.
+    modified: u64,
+    live: bool,
+}
+
+struct ActiveIndex
+{
+    index: Arc<TrigramIndex>,
+    store: PathBuf,
+}
+
+/// Until a refresh lands, searches read changed files anyway, because
+/// `Narrowing` compares each file's stamp with the indexed one.
+#[derive(Default)]
+struct PendingRefresh
+{
+    paths: HashSet<PathBuf>,
+    last_change: Option<Instant>,
+    worker_running: bool,
+}
+
+/// What one search may skip: files that are indexed, unchanged since,
+/// and lack a required trigram. Anything else is searched as usual.
+pub struct Narrowing
+{
+    index: Arc<TrigramIndex>,
+    candidates: HashSet<u32>,
+    stale: Mutex<Vec<PathBuf>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TrigramIndexStatus
+{
+    pub root: Option<String>,
+    #[serde(rename = "fileCount")]
+    pub file_count: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct IndexProgressPayload
+{
+    root: String,
+    #[serde(rename = "filesIndexed")]
+    files_indexed: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct IndexReadyPayload
+{
+    root: String,
+    #[serde(rename = "fileCount")]
+    file_count: usize,
+    #[serde(rename = "elapsedMs")]
+    elapsed_ms: u64,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Builds (or rebuilds) the trigram index for `root` in the background and
+/// saves it under the app data dir. Find in Files uses it from then on,
+/// including after a restart. Emits `trigram-index-progress` and
+/// `trigram-index-ready`.
+#[tauri::command]
+pub async fn build_trigram_index(app: AppHandle, root: String) -> Result<(), String>
+{
+    check_access(&root).map_err(|e| e.message)?;
+    let store = index_store(&app, Path::new(&root))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let root_path = PathBuf::from(&root);
+        let index = TrigramIndex::build(&root_path, &AtomicBool::new(false), |files_indexed| {
+            let _ = app.emit("trigram-index-progress", IndexProgressPayload { root: root.clone(), files_indexed });
+        });
+
+        if let Err(e) = index.save(&store)
+        {
+            eprintln!("Failed to save trigram index for '{}': {}", root, e);
+        }
+        let file_count = index.live_files();
+        // The saved copy is still picked up if that folder is reopened
+        if !activate(index, store)
+        {
+            return;
+        }
+
+        let _ = app.emit("trigram-index-ready", IndexReadyPayload {
+            root,
+            file_count,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    });
+
+    Ok(())
+}
+
+/// Stops using the index for `root` and deletes it from disk.
+#[tauri::command]
+pub async fn delete_trigram_index(app: AppHandle, root: String) -> Result<(), String>
+{
+    let store = index_store(&app, Path::new(&root))?;
+    if let Ok(mut active) = active_lock().lock()
+    {
+        if active.as_ref().is_some_and(|a| a.index.root == Path::new(&root))
+        {
+            *active = None;
+        }
+    }
+
+    match fs::remove_file(&store)
+    {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(format!("Failed to delete trigram index: {}", e)),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn trigram_index_status() -> Result<TrigramIndexStatus, String>
+{
+    let active = active_lock().lock().map_err(|_| "Trigram index lock poisoned".to_string())?;
+    Ok(match active.as_ref()
+    {
+        Some(active) => TrigramIndexStatus {
+            root: Some(active.index.root.to_string_lossy().into_owned()),
+            file_count: active.index.live_files(),
+        },
+        None => TrigramIndexStatus { root: None, file_count: 0 },
+    })
+}
+
+// ─── Workspace Hooks ────────────────────────────────────────────
+
+/// Loads the saved index for a newly opened workspace, if one was ever
+/// built. Without one, searches simply read every file.
+pub fn load_workspace_index(app: &AppHandle, root: &Path)
+{
+    if let Ok(mut active) = active_lock().lock()
+    {
+        *active = None;
+    }
+    let Ok(store) = index_store(app, root)
+    else
+    {
+        return;
+    };
+    let root = root.to_path_buf();
+
+    std::thread::spawn(move || {
+        match TrigramIndex::load(&store)
+        {
+            Ok(index) if index.root == root => {
+                activate(index, store);
+            },
+            Ok(_) => {},
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => eprintln!("Ignoring unreadable trigram index '{}': {}", store.display(), e),
+        }
+    });
+}
+
+/// Queues files reported by the watcher or found stale by a search for
+/// re-indexing on a background thread, once changes settle. Paths outside
+/// the indexed root are ignored.
+pub fn refresh_files(paths: &[PathBuf])
+{
+    if paths.is_empty()
+    {
+        return;
+    }
+    let Ok(mut pending) = pending_lock().lock()
+    else
+    {
+        return;
+    };
+
+    pending.paths.extend(paths.iter().cloned());
+    pending.last_change = Some(Instant::now());
+    if !pending.worker_running
+    {
+        pending.worker_running = true;
+        std::thread::spawn(refresh_worker);
+    }
+}
+
+/// Drains the queue, one batch per quiet moment, and exits once it is empty.
+fn refresh_worker()
+{
+    loop
+    {
+        std::thread::sleep(REFRESH_POLL_INTERVAL);
+
+        let paths = {
+            let Ok(mut pending) = pending_lock().lock()
+            else
+            {
+                return;
+            };
+            if pending.paths.is_empty()
+            {
+                pending.worker_running = false;
+                return;
+            }
+            let settled = pending.last_change.is_none_or(|at| at.elapsed() >= REFRESH_DELAY);
+            if !settled && pending.paths.len() < REFRESH_EVERY_CHANGES
+            {
+                continue;
+            }
+            std::mem::take(&mut pending.paths)
+        };
+
+        apply_refresh(paths);
+    }
+}
+
+/// Re-indexes `paths` into a copy of the active index, swaps the copy in
+/// unless the index was replaced meanwhile, and saves it. Only the swap
+/// holds the lock.
+fn apply_refresh(paths: HashSet<PathBuf>)
+{
+    let Some(snapshot) = active_snapshot()
+    else
+    {
+        return;
+    };
+
+    let updates: Vec<(String, Option<FileEntry>)> = paths.iter()
+        .filter_map(|path| {
+            let relative = relative_path(&snapshot.root, path)?;
+            Some((relative, index_file(path)))
+        })
+        .collect();
+    if updates.is_empty()
+    {
+        return;
+    }
+
+    let mut index = TrigramIndex::clone(&snapshot);
+    for (relative, entry) in updates
+    {
+        index.remove(&relative);
+        if let Some((size, modified, trigrams)) = entry
+        {
+            index.insert(relative, size, modified, &trigrams);
+        }
+    }
+    if index.dead > index.live_files()
+    {
+        index.compact();
+    }
+    let index = Arc::new(index);
+
+    let store = {
+        let Ok(mut guard) = active_lock().lock()
+        else
+        {
+            return;
+        };
+        let Some(active) = guard.as_mut().filter(|a| Arc::ptr_eq(&a.index, &snapshot))
+        else
+        {
+            return;
+        };
+        active.index = index.clone();
+        active.store.clone()
+    };
+
+    if let Err(e) = index.save(&store)
+    {
+        eprintln!("Failed to save trigram index: {}", e);
+    }
+}
+
+/// The narrowing a search of `root` can use, when an index covers `root`
+/// and the query yields at least one required trigram.
+pub fn narrow_search(root: &Path, query: &SearchQuery) -> Option<Narrowing>
+{
+    let index = active_snapshot()?;
+    if !root.starts_with(&index.root)
+    {
+        return None;
+    }
+
+    let trigrams = required_trigrams(query)?;
+    let candidates = index.candidates(&trigrams);
+    Some(Narrowing { index, candidates, stale: Mutex::new(Vec::new()) })
+}
+
+impl Narrowing
+{
+    /// Whether a file must be read. Files the index does not know, or
+    /// that changed since they were indexed, are always read.
+    pub fn should_search(&self, path: &Path) -> bool
+    {
+        let fresh = relative_path(&self.index.root, path)
+            .and_then(|relative| self.index.by_path.get(&relative).copied())
+            .filter(|&id| {
+                let file = &self.index.files[id as usize];
+                file.live && file_stamp(path).is_some_and(|stamp| stamp == (file.size, file.modified))
+            });
+
+        match fresh
+        {
+            Some(id) => self.candidates.contains(&id),
+            None => {
+                if let Ok(mut stale) = self.stale.lock()
+                {
+                    stale.push(path.to_path_buf());
+                }
+                true
+            },
+        }
+    }
+
+    /// Files the search found out of date, to be re-indexed afterwards.
+    pub fn into_stale(self) -> Vec<PathBuf>
+    {
+        self.stale.into_inner().unwrap_or_default()
+    }
+}
+
+// ─── Index ──────────────────────────────────────────────────────
+
+impl TrigramIndex
+{
+    /// Indexes every file the project tree would list under `root`.
+    pub fn build<F>(root: &Path, cancel: &AtomicBool, on_progress: F) -> Self
+    where
+        F: Fn(u64) + Sync,
+    {
+        let found = Mutex::new(Vec::new());
+        let counted = AtomicU64::new(0);
+
+        let _ = walk_files(root, &SearchQuery::default(), &current_settings(), cancel, |path| {
+            if let (Some(relative), Some(entry)) = (relative_path(root, path), index_file(path))
+            {
+                if let Ok(mut found) = found.lock()
+                {
+                    found.push((relative, entry));
+                }
+                let count = counted.fetch_add(1, Ordering::SeqCst) + 1;
+                if count.is_multiple_of(PROGRESS_EVERY_FILES)
+                {
+                    on_progress(count);
+                }
+            }
+            WalkState::Continue
+        });
+
+        let mut found = found.into_inner().unwrap_or_default();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut index = TrigramIndex { root: root.to_path_buf(), ..TrigramIndex::default() };
+        for (relative, (size, modified, trigrams)) in found
+        {
+            index.insert(relative, size, modified, &trigrams);
+        }
+        index
+    }
+
+    fn insert(&mut self, relative: String, size: u64, modified: u64, trigrams: &HashSet<u32>)
+    {
+        let id = self.files.len() as u32;
+        for &trigram in trigrams
+        {
+            // IDs only grow, so pushing keeps every list sorted
+            self.postings.entry(trigram).or_default().push(id);
+        }
+        self.by_path.insert(relative.clone(), id);
+        self.files.push(IndexedFile { relative, size, modified, live: true });
+    }
+
+    fn remove(&mut self, relative: &str)
+    {
+        let prefix = format!("{}/", relative);
+        let removed: Vec<String> = self.by_path.keys()
+            .filter(|key| *key == relative || key.starts_with(&prefix))
+            .cloned()
+            .collect();
+
+        for key in removed
+        {
+            if let Some(id) = self.by_path.remove(&key)
+            {
+                self.files[id as usize].live = false;
+                self.dead += 1;
+            }
+        }
+    }
+
+    /// Renumbers the live files and drops the dead ones from every list.
+    fn compact(&mut self)
+    {
+        let mut remap = vec![None; self.files.len()];
+        let mut files = Vec::with_capacity(self.files.len() - self.dead);
+        for (old, file) in self.files.drain(..).enumerate()
+        {
+            if file.live
+            {
+                remap[old] = Some(files.len() as u32);
+                files.push(file);
+            }
+        }
+
+        for ids in self.postings.values_mut()
+        {
+            *ids = ids.iter().filter_map(|&id| remap[id as usize]).collect();
+        }
+        self.postings.retain(|_, ids| !ids.is_empty());
+        self.by_path = files.iter().enumerate().map(|(id, f)| (f.relative.clone(), id as u32)).collect();
+        self.files = files;
+        self.dead = 0;
+    }
+
+    /// Live files containing every one of `trigrams`.
+    fn candidates(&self, trigrams: &[u32]) -> HashSet<u32>
+    {
+        let mut lists: Vec<&[u32]> = Vec::with_capacity(trigrams.len());
+        for trigram in trigrams
+        {
+            match self.postings.get(trigram)
+            {
+                Some(ids) => lists.push(ids),
+                None => return HashSet::new(),
+            }
+        }
+        lists.sort_by_key(|ids| ids.len());
+
+        let Some((first, rest)) = lists.split_first()
+        else
+        {
+            return HashSet::new();
+        };
+        first.iter()
+            .copied()
+            .filter(|id| self.files[*id as usize].live && rest.iter().all(|ids| ids.binary_search(id).is_ok()))
+            .collect()
+    }
+
+    fn live_files(&self) -> usize
+    {
+        self.files.len() - self.dead
+    }
+
+    // ── Persistence ─────────────────────────────────────────────
+
+    /// Writes live files and postings only, so a loaded index starts compact.
+    fn save(&self, store: &Path) -> io::Result<()>
+    {
+        let mut index = self.clone();
+        if index.dead > 0
+        {
+            index.compact();
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(INDEX_MAGIC);
+        write_bytes(&mut out, index.root.to_string_lossy().as_bytes());
+        out.extend_from_slice(&(index.files.len() as u32).to_le_bytes());
+        for file in &index.files
+        {
+            write_bytes(&mut out, file.relative.as_bytes());
+            out.extend_from_slice(&file.size.to_le_bytes());
+            out.extend_from_slice(&file.modified.to_le_bytes());
+        }
+        out.extend_from_slice(&(index.postings.len() as u32).to_le_bytes());
+        for (trigram, ids) in &index.postings
+        {
+            out.extend_from_slice(&trigram.to_le_bytes());
+            out.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+            for id in ids
+            {
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+
+        if let Some(dir) = store.parent()
+        {
+            fs::create_dir_all(dir)?;
+        }
+        atomic_write(store, &out).map(|_| ())
+    }
+
+    fn load(store: &Path) -> io::Result<Self>
+    {
+        let bytes = fs::read(store)?;
+        let mut reader = Reader { bytes: &bytes, at: 0 };
+        if reader.take(INDEX_MAGIC.len())? != INDEX_MAGIC
+        {
+            return Err(invalid("not a trigram index"));
+        }
+
+        let root = PathBuf::from(reader.string()?);
+        let file_count = reader.u32()? as usize;
+        let mut index = TrigramIndex { root, ..TrigramIndex::default() };
+        for id in 0..file_count
+        {
+            let relative = reader.string()?;
+            let size = reader.u64()?;
+            let modified = reader.u64()?;
+            index.by_path.insert(relative.clone(), id as u32);
+            index.files.push(IndexedFile { relative, size, modified, live: true });
+        }
+
+        let posting_count = reader.u32()? as usize;
+        for _ in 0..posting_count
+        {
+            let trigram = reader.u32()?;
+            let length = reader.u32()? as usize;
+            let ids = (0..length).map(|_| reader.u32()).collect::<io::Result<Vec<u32>>>()?;
+            if ids.iter().any(|&id| id as usize >= file_count)
+            {
+                return Err(invalid("file ID out of range"));
+            }
+            index.postings.insert(trigram, ids);
+        }
+        Ok(index)
+    }
+}
+
+// ─── Trigrams ───────────────────────────────────────────────────
+
+/// Trigrams of the ASCII-lowercased UTF-8 text. Only all-ASCII trigrams
+/// are kept, so case-insensitive queries can use the same index safely.
+pub fn text_trigrams(text: &str) -> HashSet<u32>
+{
+    text.as_bytes()
+        .windows(3)
+        .filter(|w| w.iter().all(u8::is_ascii))
+        .map(|w| trigram_key(w[0].to_ascii_lowercase(), w[1].to_ascii_lowercase(), w[2].to_ascii_lowercase()))
+        .collect()
+}
+
+/// Trigrams every matching file must contain, or `None` when the query
+/// implies none (short literals, alternations, case-insensitive regex
+/// groups) and the index cannot help.
+pub fn required_trigrams(query: &SearchQuery) -> Option<Vec<u32>>
+{
+    let runs = if query.is_regex
+    {
+        let hir = regex_syntax::ParserBuilder::new().build().parse(&query.pattern).ok()?;
+        let (mut runs, exact) = required_runs(&hir);
+        runs.extend(exact);
+        runs
+    }
+    else
+    {
+        vec![query.pattern.as_bytes().to_vec()]
+    };
+
+    // Case-insensitive Unicode matching lets 'k' and 's' match the Kelvin
+    // sign and long s, which the ASCII-only index never records
+    let usable = |byte: &u8| byte.is_ascii() && (query.case_sensitive || !matches!(byte.to_ascii_lowercase(), b'k' | b's'));
+    let trigrams: HashSet<u32> = runs.iter()
+        .flat_map(|run| run.windows(3).filter(|w| w.iter().all(usable)).map(|w| {
+            trigram_key(w[0].to_ascii_lowercase(), w[1].to_ascii_lowercase(), w[2].to_ascii_lowercase())
+        }).collect::<Vec<_>>())
+        .collect();
+
+    if trigrams.is_empty() { None } else { Some(trigrams.into_iter().collect()) }
+}
+
+/// Literal byte runs that any match of `hir` must contain, plus the exact
+/// text of `hir` when it can only match one string (so neighbours in a
+/// concatenation can be joined to it).
+fn required_runs(hir: &Hir) -> (Vec<Vec<u8>>, Option<Vec<u8>>)
+{
+    match hir.kind()
+    {
+        HirKind::Empty | HirKind::Look(_) => (Vec::new(), Some(Vec::new())),
+        HirKind::Literal(literal) => (Vec::new(), Some(literal.0.to_vec())),
+        HirKind::Capture(capture) => required_runs(&capture.sub),
+        HirKind::Repetition(repetition) if repetition.min >= 1 => {
+            let (mut runs, exact) = required_runs(&repetition.sub);
+            if repetition.min == 1 && repetition.max == Some(1)
+            {
+                return (runs, exact);
+            }
+            runs.extend(exact);
+            (runs, None)
+        },
+        HirKind::Concat(parts) => {
+            let mut runs = Vec::new();
+            let mut current = Vec::new();
+            let mut all_exact = true;
+            for part in parts
+            {
+                let (part_runs, exact) = required_runs(part);
+                runs.extend(part_runs);
+                match exact
+                {
+                    Some(text) => current.extend(text),
+                    None => {
+                        all_exact = false;
+                        runs.push(std::mem::take(&mut current));
+                    },
+                }
+            }
+            if all_exact
+            {
+                return (runs, Some(current));
+            }
+            runs.push(current);
+            runs.retain(|run| run.len() >= 3);
+            (runs, None)
+        },
+        _ => (Vec::new(), None),
+    }
+}
+
+fn trigram_key(a: u8, b: u8, c: u8) -> u32
+{
+    (a as u32) << 16 | (b as u32) << 8 | c as u32
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn active_lock() -> &'static Mutex<Option<ActiveIndex>>
+{
+    ACTIVE.get_or_init(|| Mutex::new(None))
+}
+
+fn active_snapshot() -> Option<Arc<TrigramIndex>>
+{
+    active_lock().lock().ok()?.as_ref().map(|active| active.index.clone())
+}
+
+fn pending_lock() -> &'static Mutex<PendingRefresh>
+{
+    PENDING.get_or_init(|| Mutex::new(PendingRefresh::default()))
+}
+
+/// Makes `index` the one searches use, unless a different workspace has
+/// been opened since it was requested. Returns whether it was activated.
+fn activate(index: TrigramIndex, store: PathBuf) -> bool
+{
+    if workspace_root().is_none_or(|root| root != index.root)
+    {
+        return false;
+    }
+    let Ok(mut active) = active_lock().lock()
+    else
+    {
+        return false;
+    };
+    *active = Some(ActiveIndex { index: Arc::new(index), store });
+    true
+}
+
+/// `<app data>/trigram-index/<hash of root>.bin`
+fn index_store(app: &AppHandle, root: &Path) -> Result<PathBuf, String>
+{
+    let key = content_hash(root.to_string_lossy().as_bytes());
+    app.path().app_data_dir()
+        .map(|dir| dir.join(INDEX_DIR_NAME).join(format!("{:016x}.bin", key)))
+        .map_err(|e| format!("Cannot locate the app data directory: {}", e))
+}
+
+/// Size, modification time and trigrams of a file; binary and oversized
+/// files are recorded without trigrams. `None` if it cannot be read.
+fn index_file(path: &Path) -> Option<FileEntry>
+{
+    let (size, modified) = file_stamp(path)?;
+    let trigrams = match read_searchable(path)
+    {
+        Ok(Some(text)) => text_trigrams(&text),
+        Ok(None) => HashSet::new(),
+        Err(_) => return None,
+    };
+    Some((size, modified, trigrams))
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, u64)>
+{
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+    Some((metadata.len(), modified))
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String>
+{
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty()
+    {
+        return None;
+    }
+    let parts: Vec<String> = relative.components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("/"))
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8])
+{
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn invalid(message: &str) -> io::Error
+{
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Bounds-checked little-endian reader for the index file.
+struct Reader<'a>
+{
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a>
+{
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]>
+    {
+        let end = self.at.checked_add(length).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated trigram index"))?;
+        let slice = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> io::Result<u32>
+    {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> io::Result<u64>
+    {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    fn string(&mut self) -> io::Result<String>
+    {
+        let length = self.u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| invalid("path is not UTF-8"))
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(pattern: &str) -> SearchQuery
+    {
+        SearchQuery { pattern: pattern.to_string(), ..SearchQuery::default() }
+    }
+
+    fn regex(pattern: &str) -> SearchQuery
+    {
+        SearchQuery { is_regex: true, ..query(pattern) }
+    }
+
+    fn sample_index() -> (TempDir, TrigramIndex)
+    {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/alpha.rs"), "fn Parse_Config() {}").unwrap();
+        fs::write(dir.path().join("src/beta.rs"), "fn render() {}").unwrap();
+        fs::write(dir.path().join("notes.md"), "parse the config later").unwrap();
+        let index = TrigramIndex::build(dir.path(), &AtomicBool::new(false), |_| {});
+        (dir, index)
+    }
+
+    fn candidate_names(index: &TrigramIndex, query: &SearchQuery) -> Vec<String>
+    {
+        let mut names: Vec<String> = index.candidates(&required_trigrams(query).unwrap())
+            .into_iter()
+            .map(|id| index.files[id as usize].relative.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // ── Query trigrams ──────────────────────────────────────────
+
+    #[test]
+    fn short_literals_and_alternations_yield_no_trigrams()
+    {
+        assert!(required_trigrams(&query("ab")).is_none());
+        assert!(required_trigrams(&regex("foo|bar")).is_none());
+        assert!(required_trigrams(&regex("(?i)config")).is_none());
+        assert!(required_trigrams(&query("ask")).is_none());
+        assert!(required_trigrams(&SearchQuery { case_sensitive: true, ..query("ask") }).is_some());
+    }
+
+    #[test]
+    fn regex_literal_runs_are_required()
+    {
+        let trigrams = required_trigrams(&regex(r"parse_\w+\(config")).unwrap();
+        assert!(trigrams.contains(&trigram_key(b'p', b'a', b'r')));
+        assert!(trigrams.contains(&trigram_key(b'f', b'i', b'g')));
+        assert!(!trigrams.contains(&trigram_key(b'e', b'_', b'c')));
+
+        // Word boundaries are zero-width, so "a\bbc" still needs "abc"
+        let joined = required_trigrams(&regex(r"con\bfig+")).unwrap();
+        assert!(joined.contains(&trigram_key(b'n', b'f', b'i')));
+    }
+
+    // ── Candidates ──────────────────────────────────────────────
+
+    #[test]
+    fn candidates_match_case_insensitively()
+    {
+        let (_dir, index) = sample_index();
+        assert_eq!(candidate_names(&index, &query("parse_config")), vec!["src/alpha.rs"]);
+        assert_eq!(candidate_names(&index, &query("config")), vec!["notes.md", "src/alpha.rs"]);
+        assert!(candidate_names(&index, &query("missing")).is_empty());
+    }
+
+    #[test]
+    fn narrowing_searches_changed_and_unknown_files()
+    {
+        let (dir, index) = sample_index();
+        let narrowing = Narrowing {
+            candidates: index.candidates(&required_trigrams(&query("render")).unwrap()),
+            index: Arc::new(index),
+            stale: Mutex::new(Vec::new()),
+        };
+
+        assert!(narrowing.should_search(&dir.path().join("src/beta.rs")));
+        assert!(!narrowing.should_search(&dir.path().join("notes.md")));
+
+        fs::write(dir.path().join("notes.md"), "now mentions render() and is longer").unwrap();
+        fs::write(dir.path().join("new.rs"), "render").unwrap();
+        assert!(narrowing.should_search(&dir.path().join("notes.md")));
+        assert!(narrowing.should_search(&dir.path().join("new.rs")));
+        assert_eq!(narrowing.into_stale().len(), 2);
+    }
+
+    #[test]
+    fn removed_files_stop_matching_and_compact_away()
+    {
+        let (_dir, mut index) = sample_index();
+        index.remove("src");
+        assert!(candidate_names(&index, &query("parse_config")).is_empty());
+        assert_eq!(index.live_files(), 1);
+
+        index.compact();
+        assert_eq!(index.files.len(), 1);
+        assert_eq!(candidate_names(&index, &query("config")), vec!["notes.md"]);
+    }
+
+    // ── Persistence ─────────────────────────────────────────────
+
+    #[test]
+    fn index_round_trips_through_disk()
+    {
+        let (dir, mut index) = sample_index();
+        index.remove("src/beta.rs");
+        let store = dir.path().join("store/index.bin");
+
+        index.save(&store).unwrap();
+        let loaded = TrigramIndex::load(&store).unwrap();
+
+        assert_eq!(loaded.root, index.root);
+        assert_eq!(loaded.live_files(), 2);
+        assert_eq!(candidate_names(&loaded, &query("parse_config")), vec!["src/alpha.rs"]);
+    }
+
+    #[test]
+    fn load_rejects_truncated_files()
+    {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join("index.bin");
+        fs::write(&store, b"NMTRIGR1\xff\xff").unwrap();
+        assert_eq!(TrigramIndex::load(&store).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,672 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use super::access_operations::check_access;
+use super::quick_open_operations::{apply_workspace_change, is_indexed};
+use super::trigram_operations::refresh_files;
+
+/// Quiet period before a burst of events for one path is reported.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+/// A path that never goes quiet (e.g. a growing log) is still reported
+/// this often.
+const MAX_DEBOUNCE_DELAY: Duration = Duration::from_secs(2);
+/// How long a recorded save is remembered; comfortably longer than it
+/// takes the save's own events to be flushed.
+const OWN_WRITE_TTL: Duration = Duration::from_secs(10);
+
+/// The shared watcher, created on first use.
+static REGISTRY: OnceLock<Mutex<Option<WatchRegistry>>> = OnceLock::new();
+
+/// Modification times of files this process just wrote, and when they
+/// were recorded, so our own saves are not reported back to the frontend
+/// as external changes.
+static OWN_WRITES: OnceLock<Mutex<HashMap<PathBuf, (SystemTime, Instant)>>> = OnceLock::new();
+
+// ─── Types ──────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileSystemChangePayload
+{
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind
+{
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Everything seen for one path during the current debounce window.
+#[derive(Debug, Clone)]
+pub struct PendingChange
+{
+    pub first: ChangeKind,
+    pub created: bool,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+}
+
+/// Watched documents and folders. Documents are watched through their
+/// parent directory, because editors that save by renaming a temp file
+/// over the original would otherwise silently break an inode watch.
+struct WatchRegistry
+{
+    watcher: RecommendedWatcher,
+    /// Canonical path → (path as given by the frontend, reference count)
+    files: HashMap<PathBuf, (String, usize)>,
+    folders: HashMap<PathBuf, (String, usize)>,
+    /// Parent directories watched non-recursively on behalf of `files`.
+    file_dirs: HashMap<PathBuf, usize>,
+    workspace_root: Option<String>,
+}
+
+// ─── Commands ───────────────────────────────────────────────────
+
+/// Starts watching a file (an open document) or a folder (recursively).
+/// Calls are reference counted, so each needs a matching `unwatch_path`.
+#[tauri::command]
+pub async fn watch_path(app: AppHandle, path: String) -> Result<(), String>
+{
+    check_access(&path).map_err(|e| e.message)?;
+    watch(&app, &path)
+}
+
+#[tauri::command]
+pub async fn unwatch_path(path: String) -> Result<(), String>
+{
+    unwatch(&path)
+}
+
+// ─── Registry ───────────────────────────────────────────────────
+
+pub fn watch(app: &AppHandle, path: &str) -> Result<(), String>
+{
+    let mut guard = registry_lock()?;
+    if guard.is_none()
+    {
+        *guard = Some(WatchRegistry::new(app)?);
+    }
+    let registry = guard.as_mut().ok_or_else(|| "Watcher unavailable".to_string())?;
+
+    let canonical = canonical_or_original(Path::new(path));
+    if canonical.is_dir()
+    {
+        if let Some(entry) = registry.folders.get_mut(&canonical)
+        {
+            entry.1 += 1;
+            return Ok(());
+        }
+        registry.watcher.watch(&canonical, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch '{}': {}", path, e))?;
+        registry.folders.insert(canonical, (path.to_string(), 1));
+    }
+    else
+    {
+        if let Some(entry) = registry.files.get_mut(&canonical)
+        {
+            entry.1 += 1;
+            return Ok(());
+        }
+        let dir = canonical.parent()
+            .ok_or_else(|| format!("Cannot determine parent directory of '{}'", path))?
+            .to_path_buf();
+        let dir_count = registry.file_dirs.entry(dir.clone()).or_insert(0);
+        if *dir_count == 0
+        {
+            registry.watcher.watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch '{}': {}", path, e))?;
+        }
+        *dir_count += 1;
+        registry.files.insert(canonical, (path.to_string(), 1));
+    }
+
+    Ok(())
+}
+
+pub fn unwatch(path: &str) -> Result<(), String>
+{
+    let mut guard = registry_lock()?;
+    let registry = match guard.as_mut()
+    {
+        Some(registry) => registry,
+        None => return Ok(()),
+    };
+
+    let canonical = canonical_or_original(Path::new(path));
+    let key = registry.folders.keys()
+        .chain(registry.files.keys())
+        .find(|k| **k == canonical || registry_display(registry, k) == Some(path))
+        .cloned();
+    let key = match key
+    {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    if let Some(entry) = registry.folders.get_mut(&key)
+    {
+        entry.1 -= 1;
+        if entry.1 == 0
+        {
+            registry.folders.remove(&key);
+            let _ = registry.watcher.unwatch(&key);
+        }
+    }
+    else if let Some(entry) = registry.files.get_mut(&key)
+    {
+        entry.1 -= 1;
+        if entry.1 == 0
+        {
+            registry.files.remove(&key);
+            if let Some(dir) = key.parent()
+            {
+                if let Some(count) = registry.file_dirs.get_mut(dir)
+                {
+                    *count -= 1;
+                    if *count == 0
+                    {
+                        registry.file_dirs.remove(dir);
+                        let _ = registry.watcher.unwatch(dir);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the watched workspace folder, as `open_folder_dialog` does
+/// each time the user opens a different folder.
+pub fn watch_workspace(app: &AppHandle, path: &str) -> Result<(), String>
+{
+    let previous = registry_lock()?
+        .as_mut()
+        .and_then(|registry| registry.workspace_root.take());
+    if let Some(previous) = previous
+    {
+        unwatch(&previous)?;
+    }
+
+    watch(app, path)?;
+    if let Some(registry) = registry_lock()?.as_mut()
+    {
+        registry.workspace_root = Some(path.to_string());
+    }
+    Ok(())
+}
+
+/// The folder most recently opened as the workspace, if any.
+pub fn workspace_root() -> Option<PathBuf>
+{
+    registry_lock().ok()?
+        .as_ref()
+        .and_then(|registry| registry.workspace_root.as_ref())
+        .map(PathBuf::from)
+}
+
+/// Remembers the modification time of a file this process just saved.
+pub fn record_own_write(path: &Path)
+{
+    let canonical = canonical_or_original(path);
+    if let Ok(modified) = fs::metadata(&canonical).and_then(|m| m.modified())
+    {
+        if let Ok(mut writes) = own_writes().lock()
+        {
+            remember_write(&mut writes, canonical, modified, Instant::now());
+        }
+    }
+}
+
+/// Records a save and forgets those whose events have long been flushed.
+fn remember_write(
+    writes: &mut HashMap<PathBuf, (SystemTime, Instant)>,
+    path: PathBuf,
+    modified: SystemTime,
+    now: Instant,
+)
+{
+    writes.retain(|_, (_, recorded)| now.duration_since(*recorded) < OWN_WRITE_TTL);
+    writes.insert(path, (modified, now));
+}
+
+impl WatchRegistry
+{
+    fn new(app: &AppHandle) -> Result<Self, String>
+    {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)
+            .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+
+        let app = app.clone();
+        std::thread::spawn(move || {
+            run_debouncer(receiver, move |event, path| {
+                let _ = app.emit(event, FileSystemChangePayload { path });
+            });
+        });
+
+        Ok(WatchRegistry {
+            watcher,
+            files: HashMap::new(),
+            folders: HashMap::new(),
+            file_dirs: HashMap::new(),
+            workspace_root: None,
+        })
+    }
+
+    /// Maps an event path to the path the frontend knows, and whether it
+    /// is an open document. Returns `None` for paths nobody asked about.
+    fn resolve(&self, path: &Path) -> Option<(String, bool)>
+    {
+        if let Some((display, _)) = self.files.get(path)
+        {
+            return Some((display.clone(), true));
+        }
+
+        self.folders.iter()
+            .filter_map(|(root, (display, _))| {
+                path.strip_prefix(root).ok().map(|rel| (root, display, rel))
+            })
+            .max_by_key(|(root, _, _)| root.as_os_str().len())
+            .map(|(_, display, rel)| {
+                let display = if rel.as_os_str().is_empty()
+                {
+                    display.clone()
+                }
+                else
+                {
+                    Path::new(display).join(rel).to_string_lossy().into_owned()
+                };
+                (display, false)
+            })
+    }
+}
+
+// ─── Debouncer ──────────────────────────────────────────────────
+
+/// Collects raw notify events and reports each path once it has been
+/// quiet for `DEBOUNCE_INTERVAL`, or busy for `MAX_DEBOUNCE_DELAY`. Runs
+/// until the watcher is dropped.
+fn run_debouncer<F>(receiver: Receiver<notify::Result<Event>>, emit: F)
+where
+    F: Fn(&str, String),
+{
+    let mut pending: HashMap<PathBuf, PendingChange> = HashMap::new();
+
+    loop
+    {
+        match receiver.recv_timeout(DEBOUNCE_INTERVAL)
+        {
+            Ok(Ok(event)) => {
+                for (path, kind) in event_changes(&event)
+                {
+                    record_change(&mut pending, path, kind, Instant::now());
+                }
+            },
+            Ok(Err(e)) => eprintln!("File watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let now = Instant::now();
+        let ready: Vec<PathBuf> = pending.iter()
+            .filter(|(_, change)| change.is_due(now))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in ready
+        {
+            if let Some(change) = pending.remove(&path)
+            {
+                flush_change(&path, &change, &emit);
+            }
+        }
+    }
+}
+
+fn flush_change<F>(path: &Path, change: &PendingChange, emit: &F)
+where
+    F: Fn(&str, String),
+{
+    let resolved = match registry_lock()
+    {
+        Ok(guard) => guard.as_ref().and_then(|registry| registry.resolve(path)),
+        Err(_) => None,
+    };
+    let (display, is_document) = match resolved
+    {
+        Some(resolved) => resolved,
+        None => return,
+    };
+
+    let metadata = fs::metadata(path).ok();
+    // Checked before the change is applied to the quick open index below
+    let known = is_indexed(Path::new(&display));
+    let event = match classify_change(change, metadata.as_ref().map(|m| m.is_dir()), is_document, known)
+    {
+        Some(event) => event,
+        None => return,
+    };
+
+    match event
+    {
+        "file-created" => apply_workspace_change(Path::new(&display), true),
+        "file-deleted" => apply_workspace_change(Path::new(&display), false),
+        _ => {},
+    }
+    refresh_files(&[PathBuf::from(&display)]);
+
+    if event == "file-changed-on-disk" && is_own_write(path, metadata.as_ref())
+    {
+        return;
+    }
+
+    emit(event, display);
+}
+
+/// Splits a notify event into per-path changes. Access and metadata-only
+/// events are ignored; renames become a removal plus a creation.
+pub fn event_changes(event: &Event) -> Vec<(PathBuf, ChangeKind)>
+{
+    let kind = match event.kind
+    {
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        EventKind::Modify(ModifyKind::Metadata(_)) => return Vec::new(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => ChangeKind::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => ChangeKind::Created,
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            return vec![
+                (event.paths[0].clone(), ChangeKind::Removed),
+                (event.paths[1].clone(), ChangeKind::Created),
+            ];
+        },
+        EventKind::Modify(_) => ChangeKind::Modified,
+        _ => return Vec::new(),
+    };
+
+    event.paths.iter().map(|path| (path.clone(), kind)).collect()
+}
+
+pub fn record_change(
+    pending: &mut HashMap<PathBuf, PendingChange>,
+    path: PathBuf,
+    kind: ChangeKind,
+    now: Instant,
+)
+{
+    let change = pending.entry(path).or_insert(PendingChange {
+        first: kind,
+        created: false,
+        first_seen: now,
+        last_seen: now,
+    });
+    change.created |= kind == ChangeKind::Created;
+    change.last_seen = now;
+}
+
+impl PendingChange
+{
+    pub fn is_due(&self, now: Instant) -> bool
+    {
+        now.duration_since(self.last_seen) >= DEBOUNCE_INTERVAL
+            || now.duration_since(self.first_seen) >= MAX_DEBOUNCE_DELAY
+    }
+}
+
+/// Decides which event (if any) a settled change produces.
+/// `is_dir` is `None` when the path no longer exists; `known` says the
+/// workspace already listed it before this change.
+pub fn classify_change(
+    change: &PendingChange,
+    is_dir: Option<bool>,
+    is_document: bool,
+    known: bool,
+) -> Option<&'static str>
+{
+    match is_dir
+    {
+        // Created and removed again within the window (e.g. a temp file)
+        None if change.first == ChangeKind::Created => None,
+        None => Some("file-deleted"),
+        // Replacing an open document or a listed file (delete + create,
+        // rename over) is a change
+        Some(_) if is_document => Some("file-changed-on-disk"),
+        Some(_) if change.created && change.first != ChangeKind::Removed && !known => Some("file-created"),
+        // Directory mtimes change whenever children do; that is not news
+        Some(true) => None,
+        Some(false) => Some("file-changed-on-disk"),
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────
+
+fn registry_lock() -> Result<std::sync::MutexGuard<'static, Option<WatchRegistry>>, String>
+{
+    REGISTRY.get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|_| "Watcher lock poisoned".to_string())
+}
+
+fn registry_display<'a>(registry: &'a WatchRegistry, key: &Path) -> Option<&'a str>
+{
+    registry.folders.get(key)
+        .or_else(|| registry.files.get(key))
+        .map(|(display, _)| display.as_str())
+}
+
+fn own_writes() -> &'static Mutex<HashMap<PathBuf, (SystemTime, Instant)>>
+{
+    OWN_WRITES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn is_own_write(path: &Path, metadata: Option<&fs::Metadata>) -> bool
+{
+    let modified = match metadata.and_then(|m| m.modified().ok())
+    {
+        Some(modified) => modified,
+        None => return false,
+    };
+    own_writes().lock()
+        .map(|writes| writes.get(path).is_some_and(|(recorded, _)| *recorded == modified))
+        .unwrap_or(false)
+}
+
+fn canonical_or_original(path: &Path) -> PathBuf
+{
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use notify::event::{CreateKind, DataChange, MetadataKind, RemoveKind};
+
+    fn change(first: ChangeKind, created: bool) -> PendingChange
+    {
+        let now = Instant::now();
+        PendingChange { first, created, first_seen: now, last_seen: now }
+    }
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event
+    {
+        let mut event = Event::new(kind);
+        for path in paths
+        {
+            event = event.add_path(PathBuf::from(path));
+        }
+        event
+    }
+
+    // ── event_changes ────────────────────────────────────────────
+
+    #[test]
+    fn maps_basic_event_kinds()
+    {
+        let created = event(EventKind::Create(CreateKind::File), &["/a"]);
+        let removed = event(EventKind::Remove(RemoveKind::File), &["/a"]);
+        let modified = event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["/a"]);
+
+        assert_eq!(event_changes(&created), vec![(PathBuf::from("/a"), ChangeKind::Created)]);
+        assert_eq!(event_changes(&removed), vec![(PathBuf::from("/a"), ChangeKind::Removed)]);
+        assert_eq!(event_changes(&modified), vec![(PathBuf::from("/a"), ChangeKind::Modified)]);
+    }
+
+    #[test]
+    fn splits_renames_into_remove_and_create()
+    {
+        let rename = event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/old", "/new"]);
+        assert_eq!(
+            event_changes(&rename),
+            vec![
+                (PathBuf::from("/old"), ChangeKind::Removed),
+                (PathBuf::from("/new"), ChangeKind::Created),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_access_and_metadata_events()
+    {
+        let access = event(EventKind::Access(notify::event::AccessKind::Any), &["/a"]);
+        let chmod = event(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions)), &["/a"]);
+        assert!(event_changes(&access).is_empty());
+        assert!(event_changes(&chmod).is_empty());
+    }
+
+    // ── Debouncing ───────────────────────────────────────────────
+
+    #[test]
+    fn record_change_merges_bursts()
+    {
+        let mut pending = HashMap::new();
+        let start = Instant::now();
+        record_change(&mut pending, PathBuf::from("/a"), ChangeKind::Removed, start);
+        record_change(&mut pending, PathBuf::from("/a"), ChangeKind::Created, start + Duration::from_millis(10));
+
+        let merged = &pending[&PathBuf::from("/a")];
+        assert_eq!(pending.len(), 1);
+        assert_eq!(merged.first, ChangeKind::Removed);
+        assert!(merged.created);
+        assert_eq!(merged.first_seen, start);
+        assert_eq!(merged.last_seen, start + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn busy_paths_flush_after_the_max_delay()
+    {
+        let mut pending = HashMap::new();
+        let start = Instant::now();
+        let mut now = start;
+        while now < start + MAX_DEBOUNCE_DELAY
+        {
+            record_change(&mut pending, PathBuf::from("/log"), ChangeKind::Modified, now);
+            assert!(!pending[&PathBuf::from("/log")].is_due(now));
+            now += DEBOUNCE_INTERVAL / 2;
+        }
+
+        record_change(&mut pending, PathBuf::from("/log"), ChangeKind::Modified, now);
+        assert!(pending[&PathBuf::from("/log")].is_due(now));
+    }
+
+    // ── classify_change ──────────────────────────────────────────
+
+    #[test]
+    fn classifies_deletions_and_transient_files()
+    {
+        assert_eq!(classify_change(&change(ChangeKind::Removed, false), None, false, false), Some("file-deleted"));
+        assert_eq!(classify_change(&change(ChangeKind::Modified, false), None, true, false), Some("file-deleted"));
+        assert_eq!(classify_change(&change(ChangeKind::Created, true), None, false, false), None);
+    }
+
+    #[test]
+    fn classifies_creations_in_folders()
+    {
+        assert_eq!(classify_change(&change(ChangeKind::Created, true), Some(false), false, false), Some("file-created"));
+        assert_eq!(classify_change(&change(ChangeKind::Created, true), Some(true), false, false), Some("file-created"));
+    }
+
+    #[test]
+    fn replaced_files_are_changes()
+    {
+        assert_eq!(
+            classify_change(&change(ChangeKind::Removed, true), Some(false), false, false),
+            Some("file-changed-on-disk")
+        );
+        assert_eq!(
+            classify_change(&change(ChangeKind::Created, true), Some(false), true, false),
+            Some("file-changed-on-disk")
+        );
+    }
+
+    #[test]
+    fn renames_over_listed_files_are_changes()
+    {
+        assert_eq!(
+            classify_change(&change(ChangeKind::Created, true), Some(false), false, true),
+            Some("file-changed-on-disk")
+        );
+    }
+
+    #[test]
+    fn directory_modifications_are_ignored()
+    {
+        assert_eq!(classify_change(&change(ChangeKind::Modified, false), Some(true), false, false), None);
+    }
+
+    // ── Own writes ───────────────────────────────────────────────
+
+    #[test]
+    fn own_writes_are_recognized_until_modified_again()
+    {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("saved.txt");
+        fs::write(&path, "ours").unwrap();
+        record_own_write(&path);
+
+        let canonical = canonical_or_original(&path);
+        let metadata = fs::metadata(&canonical).unwrap();
+        assert!(is_own_write(&canonical, Some(&metadata)));
+
+        let later = metadata.modified().unwrap() + Duration::from_secs(5);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert!(!is_own_write(&canonical, Some(&fs::metadata(&canonical).unwrap())));
+    }
+
+    #[test]
+    fn own_writes_are_forgotten_after_their_events_settle()
+    {
+        let mut writes = HashMap::new();
+        let start = Instant::now();
+        remember_write(&mut writes, PathBuf::from("/a"), SystemTime::now(), start);
+        remember_write(&mut writes, PathBuf::from("/b"), SystemTime::now(), start + Duration::from_secs(1));
+        assert_eq!(writes.len(), 2);
+
+        remember_write(&mut writes, PathBuf::from("/c"), SystemTime::now(), start + OWN_WRITE_TTL);
+        let mut kept: Vec<&PathBuf> = writes.keys().collect();
+        kept.sort();
+        assert_eq!(kept, vec![&PathBuf::from("/b"), &PathBuf::from("/c")]);
+    }
+
+    // ── Serialization ────────────────────────────────────────────
+
+    #[test]
+    fn payload_serializes_path()
+    {
+        let json = serde_json::to_value(FileSystemChangePayload { path: "/tmp/a.txt".into() }).unwrap();
+        assert_eq!(json["path"], "/tmp/a.txt");
+    }
+}
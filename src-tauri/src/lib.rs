@@ -1,11 +1,33 @@
 pub mod commands;
 pub mod menu;
 
+use tauri::Manager;
+
 use commands::file_operations;
 use commands::dialog_operations;
 use commands::window_operations;
 use commands::crypto_operations;
 use commands::process_operations;
+use commands::encoding_operations;
+use commands::backup_operations;
+use commands::watcher_operations;
+use commands::monitor_operations;
+use commands::large_file_operations;
+use commands::binary_operations;
+use commands::metadata_operations;
+use commands::trash_operations;
+use commands::tree_operations;
+use commands::path_operations;
+use commands::access_operations;
+use commands::search_operations;
+use commands::replace_operations;
+use commands::quick_open_operations;
+use commands::trigram_operations;
+use commands::diff_operations;
+use commands::merge_operations;
+use commands::recovery_operations;
+use commands::session_operations;
+use commands::recent_operations;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run()
@@ -15,7 +37,12 @@ pub fn run()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
+            access_operations::enforce(app.path().app_config_dir().ok());
+            backup_operations::load_settings(app.path().app_config_dir().ok());
+            tree_operations::init_settings(app.path().app_config_dir().ok());
             let handle = app.handle().clone();
+            recovery_operations::start(&handle, app.path().app_data_dir().ok());
+            recent_operations::load(app.path().app_config_dir().ok());
             let menu = menu::build_menu(&handle)?;
             app.set_menu(menu)?;
             Ok(())
@@ -26,6 +53,7 @@ pub fn run()
         .invoke_handler(tauri::generate_handler![
             // File operations
             file_operations::read_file,
+            file_operations::read_file_with_encoding,
             file_operations::write_file,
             file_operations::read_dir,
             file_operations::rename_file,
@@ -45,7 +73,93 @@ pub fn run()
             // Process operations
             process_operations::execute_command,
             process_operations::kill_process,
+            // Encoding operations
+            encoding_operations::detect_file_encoding,
+            encoding_operations::check_encodable,
+            // Backup operations
+            backup_operations::set_backup_settings,
+            backup_operations::get_backup_settings,
+            backup_operations::list_backups,
+            backup_operations::restore_backup,
+            // Watcher operations
+            watcher_operations::watch_path,
+            watcher_operations::unwatch_path,
+            // Monitor operations
+            monitor_operations::start_monitoring,
+            monitor_operations::stop_monitoring,
+            // Large file operations
+            large_file_operations::open_large_file,
+            large_file_operations::close_large_file,
+            large_file_operations::read_lines,
+            large_file_operations::read_byte_range,
+            // Binary operations
+            binary_operations::read_bytes,
+            binary_operations::write_bytes_patch,
+            binary_operations::sniff_file_kind,
+            // Metadata operations
+            metadata_operations::stat_file,
+            // Trash operations
+            trash_operations::delete_to_trash,
+            trash_operations::restore_from_trash,
+            // Tree operations
+            tree_operations::read_dir_children,
+            tree_operations::cancel_dir_scan,
+            tree_operations::set_tree_settings,
+            tree_operations::get_tree_settings,
+            // Path operations
+            path_operations::create_file,
+            path_operations::create_dir,
+            path_operations::copy_path,
+            path_operations::move_path,
+            path_operations::duplicate_path,
+            path_operations::delete_path,
+            // Access operations
+            access_operations::request_path_access,
+            access_operations::revoke_path_access,
+            access_operations::list_path_grants,
+            // Search operations
+            search_operations::find_in_files,
+            search_operations::cancel_search,
+            // Replace operations
+            replace_operations::replace_in_files,
+            replace_operations::apply_replace_in_files,
+            replace_operations::undo_replace_in_files,
+            replace_operations::discard_replace_preview,
+            // Quick open operations
+            quick_open_operations::quick_open,
+            quick_open_operations::refresh_quick_open_index,
+            // Trigram operations
+            trigram_operations::build_trigram_index,
+            trigram_operations::delete_trigram_index,
+            trigram_operations::trigram_index_status,
+            // Diff operations
+            diff_operations::compare_files,
+            diff_operations::compare_buffer_with_file,
+            diff_operations::compare_folders,
+            // Merge operations
+            merge_operations::save_with_merge,
+            merge_operations::forget_loaded_file,
+            // Recovery operations
+            recovery_operations::snapshot_buffers,
+            recovery_operations::list_recoverable_buffers,
+            recovery_operations::restore_buffer,
+            recovery_operations::discard_recoverable_buffers,
+            // Session operations
+            session_operations::load_session,
+            session_operations::save_session,
+            session_operations::resolve_session_tab,
+            // Recent operations
+            recent_operations::list_recent,
+            recent_operations::clear_recent,
+            recent_operations::remove_recent,
+            recent_operations::set_recent_limits,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event
+            {
+                recovery_operations::finish();
+            }
+        });
 }
@@ -1,8 +1,15 @@
+use std::sync::Mutex;
 use tauri::{
-    menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder, PredefinedMenuItem, CheckMenuItemBuilder},
+    menu::{Menu, MenuBuilder, MenuItemBuilder, Submenu, SubmenuBuilder, PredefinedMenuItem, CheckMenuItemBuilder},
     AppHandle, Emitter,
 };
 
+use crate::commands::recent_operations::{self, RecentKind, RecentList};
+
+/// The File > Open Recent submenu, kept so its items can be replaced
+/// without rebuilding (and resetting) the rest of the menu.
+static OPEN_RECENT: Mutex<Option<Submenu<tauri::Wry>>> = Mutex::new(None);
+
 // ─── Menu Action Event ──────────────────────────────────────────
 
 #[derive(Clone, serde::Serialize)]
@@ -89,6 +96,13 @@ pub fn build_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error>
     separator!(file_menu, app);
     action_item!(file_menu, "open", "Open...", "CmdOrCtrl+O", app);
     action_item!(file_menu, "open-folder", "Open Folder as Workspace", app);
+    let open_recent = SubmenuBuilder::with_id(app, "open-recent", "Open Recent").build()?;
+    append_recent_items(app, &open_recent, &recent_operations::current())?;
+    file_menu = file_menu.item(&open_recent);
+    if let Ok(mut slot) = OPEN_RECENT.lock()
+    {
+        *slot = Some(open_recent);
+    }
     action_item!(file_menu, "reload-from-disk", "Reload from Disk", app);
     separator!(file_menu, app);
     action_item!(file_menu, "save", "Save", "CmdOrCtrl+S", app);
@@ -356,6 +370,58 @@ pub fn build_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error>
         .build()
 }
 
+// ─── Open Recent ────────────────────────────────────────────────
+
+/// Replaces the Open Recent items with `list`. Called whenever the recent
+/// lists change.
+pub fn refresh_open_recent(app: &AppHandle, list: &RecentList)
+{
+    let Ok(slot) = OPEN_RECENT.lock()
+    else
+    {
+        return;
+    };
+    let Some(submenu) = slot.as_ref()
+    else
+    {
+        return;
+    };
+
+    let result = (|| {
+        while submenu.remove_at(0)?.is_some() {}
+        append_recent_items(app, submenu, list)
+    })();
+    if let Err(e) = result
+    {
+        eprintln!("Failed to rebuild Open Recent menu: {}", e);
+    }
+}
+
+fn append_recent_items(app: &AppHandle, submenu: &Submenu<tauri::Wry>, list: &RecentList) -> Result<(), tauri::Error>
+{
+    if list.files.is_empty() && list.folders.is_empty()
+    {
+        let empty = MenuItemBuilder::with_id("recent-empty", "No Recent Items")
+            .enabled(false)
+            .build(app)?;
+        return submenu.append(&empty);
+    }
+
+    let groups = [(RECENT_FILE_PREFIX, &list.files), (RECENT_FOLDER_PREFIX, &list.folders)];
+    for (prefix, entries) in groups.into_iter().filter(|(_, entries)| !entries.is_empty())
+    {
+        for entry in entries.iter()
+        {
+            let item = MenuItemBuilder::with_id(format!("{}{}", prefix, entry.path), &entry.path).build(app)?;
+            submenu.append(&item)?;
+        }
+        submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+
+    let clear = MenuItemBuilder::with_id("clear-recent", "Clear Recent List").build(app)?;
+    submenu.append(&clear)
+}
+
 // ─── Menu Event Handler ─────────────────────────────────────────
 
 // ─── Menu Event Routing ─────────────────────────────────────────
@@ -374,6 +440,10 @@ pub enum MenuEventAction
     Checkbox(String),
     /// Dialog command: open file or folder picker
     Dialog(String),
+    /// Open Recent entry: reopen the file or folder at this path
+    OpenRecent(RecentKind, String),
+    /// Open Recent > Clear Recent List
+    ClearRecent,
     /// Generic action: ID maps directly to action string
     Action(String),
 }
@@ -385,6 +455,9 @@ pub const CHECKBOX_IDS: &[&str] = &[
     "distraction-free", "always-on-top",
 ];
 
+const RECENT_FILE_PREFIX: &str = "recent-file:";
+const RECENT_FOLDER_PREFIX: &str = "recent-folder:";
+
 /// Pure function: parses a menu event ID into a structured action.
 /// This contains all the routing logic, separated from side effects.
 pub fn parse_menu_event(event_id: &str) -> MenuEventAction
@@ -419,6 +492,20 @@ pub fn parse_menu_event(event_id: &str) -> MenuEventAction
         return MenuEventAction::Dialog(event_id.to_string());
     }
 
+    // Open Recent items: "recent-file:/a/b.txt" → OpenRecent(File, "/a/b.txt")
+    if let Some(path) = event_id.strip_prefix(RECENT_FILE_PREFIX)
+    {
+        return MenuEventAction::OpenRecent(RecentKind::File, path.to_string());
+    }
+    if let Some(path) = event_id.strip_prefix(RECENT_FOLDER_PREFIX)
+    {
+        return MenuEventAction::OpenRecent(RecentKind::Folder, path.to_string());
+    }
+    if event_id == "clear-recent"
+    {
+        return MenuEventAction::ClearRecent;
+    }
+
     // All other items: generic action
     MenuEventAction::Action(event_id.to_string())
 }
@@ -460,6 +547,18 @@ pub fn handle_menu_event(app: &AppHandle, event_id: &str)
                 _ => {}
             }
         },
+        MenuEventAction::OpenRecent(kind, path) => {
+            let app_clone = app.clone();
+            tauri::async_runtime::spawn(async move {
+                crate::commands::dialog_operations::open_recent(&app_clone, &path, kind).await;
+            });
+        },
+        MenuEventAction::ClearRecent => {
+            let app_clone = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = recent_operations::clear_recent(app_clone, None).await;
+            });
+        },
         MenuEventAction::Action(id) => {
             emit_action(app, &id, None);
         },
@@ -641,6 +740,32 @@ mod tests
         );
     }
 
+    // ── Open Recent parsing ──────────────────────────────────────
+
+    #[test]
+    fn parse_open_recent_file_keeps_full_path()
+    {
+        assert_eq!(
+            parse_menu_event("recent-file:/Users/me/notes: draft.txt"),
+            MenuEventAction::OpenRecent(RecentKind::File, "/Users/me/notes: draft.txt".into())
+        );
+    }
+
+    #[test]
+    fn parse_open_recent_folder()
+    {
+        assert_eq!(
+            parse_menu_event("recent-folder:/Users/me/project"),
+            MenuEventAction::OpenRecent(RecentKind::Folder, "/Users/me/project".into())
+        );
+    }
+
+    #[test]
+    fn parse_clear_recent()
+    {
+        assert_eq!(parse_menu_event("clear-recent"), MenuEventAction::ClearRecent);
+    }
+
     // ── Generic action parsing ───────────────────────────────────
 
     #[test]
@@ -760,13 +885,15 @@ mod tests
     #[test]
     fn all_menu_categories_have_coverage()
     {
-        // Verify all 6 variants are reachable
+        // Verify all 8 variants are reachable
         let cases: Vec<(&str, MenuEventAction)> = vec![
             ("encoding-utf-8", MenuEventAction::Encoding("utf-8".into())),
             ("lang-python", MenuEventAction::Language("python".into())),
             ("line-ending-lf", MenuEventAction::LineEnding("LF".into())),
             ("word-wrap", MenuEventAction::Checkbox("word-wrap".into())),
             ("open", MenuEventAction::Dialog("open".into())),
+            ("recent-file:/a.txt", MenuEventAction::OpenRecent(RecentKind::File, "/a.txt".into())),
+            ("clear-recent", MenuEventAction::ClearRecent),
             ("save", MenuEventAction::Action("save".into())),
         ];
 